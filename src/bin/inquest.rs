@@ -99,6 +99,7 @@ fn command_execute(config: &Path) -> Result<()> {
                     let color = match failure.0 {
                         InquestError::FailedExecutionError { .. } => term::color::RED,
                         InquestError::FailedAssertionError { .. } => term::color::RED,
+                        InquestError::CertificateMismatchError { .. } => term::color::RED,
                         InquestError::AssertionMatchingError(..) => term::color::YELLOW,
                         _ => term::color::WHITE,
                     };
@@ -156,6 +157,21 @@ impl<'a> Display for ErrorDisplay<'a, InquestError> {
                 writeln!(f, "Failed in '{}': {}", probe_identifier, desc)?;
                 writeln!(f, "\tCause: {}", source)?;
             }
+            &libinquest::error::InquestError::CertificateMismatchError {
+                probe_identifier,
+                desc,
+                presented_chain,
+            } => {
+                writeln!(
+                    f,
+                    "Certificate mismatch in '{}' (TLS interception?)",
+                    probe_identifier
+                )?;
+                writeln!(f, "\tCause: {}", desc)?;
+                for certificate in presented_chain {
+                    writeln!(f, "\tPresented: {}", certificate)?;
+                }
+            }
             &libinquest::error::InquestError::AssertionMatchingError(desc, report) => {
                 let rd = ReportDisplay(report);
                 writeln!(
@@ -163,6 +179,18 @@ impl<'a> Display for ErrorDisplay<'a, InquestError> {
                     "Assertion failed in '{}': {}",
                     rd.0.probe_identifier, desc
                 )?;
+                if let Some(body) = &rd.0.body {
+                    writeln!(
+                        f,
+                        "\tBody ({}, {} bytes{}):",
+                        body.content_type.as_deref().unwrap_or("no content-type"),
+                        body.size,
+                        if body.truncated { ", truncated" } else { "" }
+                    )?;
+                    for line in body.content.lines() {
+                        writeln!(f, "\t\t{}", line)?;
+                    }
+                }
             }
             e => {
                 writeln!(f, "Unhandled Error: {:?}", e)?;
@@ -53,6 +53,18 @@ pub enum InquestError {
     #[error("Probe execution failed, due to unmatched assertions")]
     AssertionMatchingError(String, ProbeReport),
 
+    /// The certificate chain presented by the server does not match the configured pins or
+    /// issuer, which usually means that the TLS traffic is intercepted.
+    #[error("Presented certificate chain does not match the expectation!")]
+    CertificateMismatchError {
+        probe_identifier: String,
+        desc: String,
+        presented_chain: Vec<String>,
+    },
+
+    #[error("Unable to load the client certificate from '{path}': {reason}")]
+    ClientIdentityError { path: String, reason: String },
+
     #[error(transparent)]
     CryptoError(#[from] DecodeError),
 
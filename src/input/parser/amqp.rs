@@ -0,0 +1,102 @@
+use hocon::Hocon;
+use log::error;
+use secrecy::SecretString;
+
+use crate::error::InquestError;
+use crate::input::parser::{parse_timeout, parse_tls_switch};
+use crate::{Amqp, Certificates, Config};
+use crate::{Result, GO};
+
+pub(crate) fn parse_amqp(hocon: &Hocon, certs: Option<Certificates>) -> Result<Vec<Config>> {
+    if let Hocon::Array(amqps) = &hocon {
+        Ok(amqps
+            .iter()
+            .flat_map(|amqp| parse(amqp, certs.clone()))
+            .collect())
+    } else {
+        Err(InquestError::ConfigurationError)
+    }
+}
+
+fn parse(hocon: &Hocon, certs: Option<Certificates>) -> Result<Config> {
+    let (Some(host), Some(user), Some(password)) = (
+        hocon["host"].as_string(),
+        hocon["user"].as_string(),
+        hocon["password"].as_string(),
+    ) else {
+        error!("Invalid AMQP configuration. 'host', 'user' and 'password' are required");
+        return Err(InquestError::ConfigurationError);
+    };
+    let amqp = Amqp::new(host, user, SecretString::new(password), certs, &GO);
+    let (tls, tls_test) = parse_tls_switch(hocon, "tls")?;
+    let port = match hocon["port"].as_i64() {
+        Some(port) => u16::try_from(port).map_err(|_| InquestError::ConfigurationError)?,
+        None if tls => 5671,
+        None => amqp.port,
+    };
+    let timeout = parse_timeout(hocon)?.unwrap_or(amqp.timeout);
+
+    Ok(Amqp {
+        port,
+        vhost: hocon["vhost"].as_string().unwrap_or(amqp.vhost.clone()),
+        tls,
+        tls_test,
+        timeout,
+        queue: hocon["queue"].as_string(),
+        ..amqp
+    }
+    .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use secrecy::ExposeSecret;
+
+    use crate::input::parser::tests::match_content;
+    use crate::{Amqp, Config, TlsTest};
+
+    #[test]
+    fn parse_amqp() {
+        let content = r#"
+            probe-specification {
+                my-service {
+                    amqp = [{
+                        host = "rabbitmq.corp"
+                        vhost = "orders"
+                        user = "probe"
+                        password = "hX8AgBVOd/GvecheybpEPA==" # 'changeit'
+                        tls {
+                            pin-sha256 = ["Bgc+1/BgEXozqmfTBGHIdHB+nLhoWxJFa8BAkxv3cxA="]
+                        }
+                        queue = "incoming"
+                    }]
+                }
+            }"#;
+        match_content(content, |config| match config {
+            Config::Amqp(Amqp {
+                host,
+                port,
+                vhost,
+                user,
+                password,
+                tls,
+                tls_test: Some(TlsTest { pin_sha256, .. }),
+                queue,
+                ..
+            }) => {
+                assert_eq!("rabbitmq.corp", host);
+                assert_eq!(5671, *port);
+                assert_eq!("orders", vhost);
+                assert_eq!("probe", user);
+                assert_eq!("hX8AgBVOd/GvecheybpEPA==", password.expose_secret());
+                assert!(*tls);
+                assert_eq!(
+                    vec!["Bgc+1/BgEXozqmfTBGHIdHB+nLhoWxJFa8BAkxv3cxA=".to_string()],
+                    *pin_sha256
+                );
+                assert_eq!(Some("incoming".to_string()), *queue);
+            }
+            _ => panic!("did not match AMQP probe"),
+        });
+    }
+}
@@ -0,0 +1,146 @@
+use std::net::{IpAddr, SocketAddr};
+
+use hocon::Hocon;
+use log::error;
+use trust_dns_resolver::config::Protocol;
+use trust_dns_resolver::proto::rr::RecordType;
+
+use crate::error::InquestError;
+use crate::input::parser::parse_timeout;
+use crate::{Config, Dns};
+use crate::{Result, GO};
+
+const RECORD_TYPES: &[RecordType] = &[
+    RecordType::A,
+    RecordType::AAAA,
+    RecordType::CNAME,
+    RecordType::MX,
+    RecordType::SRV,
+    RecordType::TXT,
+    RecordType::PTR,
+];
+
+pub(crate) fn parse_dns(hocon: &Hocon) -> Result<Vec<Config>> {
+    if let Hocon::Array(queries) = &hocon {
+        Ok(queries.iter().flat_map(parse).collect())
+    } else {
+        Err(InquestError::ConfigurationError)
+    }
+}
+
+fn parse(hocon: &Hocon) -> Result<Config> {
+    let name = hocon["name"]
+        .as_string()
+        .ok_or(InquestError::ConfigurationError)?;
+    let dns = Dns::new(name, &GO);
+    let record_type = match hocon["type"].as_string() {
+        Some(record_type) => parse_record_type(&record_type)?,
+        None => dns.record_type,
+    };
+    let nameserver = match hocon["nameserver"].as_string() {
+        Some(nameserver) => Some(parse_nameserver(&nameserver)?),
+        None => None,
+    };
+    let protocol = match hocon["protocol"].as_string().as_deref() {
+        Some("udp") => Protocol::Udp,
+        Some("tcp") => Protocol::Tcp,
+        None => dns.protocol,
+        Some(other) => {
+            error!("Invalid DNS protocol '{}', expected 'udp' or 'tcp'", other);
+            return Err(InquestError::ConfigurationError);
+        }
+    };
+    let timeout = parse_timeout(hocon)?.unwrap_or(dns.timeout);
+    let expected = match &hocon["expected"] {
+        Hocon::Array(values) => values
+            .iter()
+            .map(|value| value.as_string().ok_or(InquestError::ConfigurationError))
+            .collect::<Result<Vec<String>>>()?,
+        Hocon::BadValue(_) => Vec::new(),
+        value => vec![value.as_string().ok_or(InquestError::ConfigurationError)?],
+    };
+
+    Ok(Dns {
+        record_type,
+        nameserver,
+        protocol,
+        timeout,
+        expected,
+        ..dns
+    }
+    .into())
+}
+
+fn parse_record_type(record_type: &str) -> Result<RecordType> {
+    match record_type.to_ascii_uppercase().parse::<RecordType>() {
+        Ok(record_type) if RECORD_TYPES.contains(&record_type) => Ok(record_type),
+        _ => {
+            error!(
+                "Unsupported DNS record type '{}', expected one of {:?}",
+                record_type, RECORD_TYPES
+            );
+            Err(InquestError::ConfigurationError)
+        }
+    }
+}
+
+/// The port of a nameserver is optional, e.g. `10.0.0.53` or `10.0.0.53:5353`
+fn parse_nameserver(nameserver: &str) -> Result<SocketAddr> {
+    nameserver
+        .parse::<SocketAddr>()
+        .or_else(|_| {
+            nameserver
+                .parse::<IpAddr>()
+                .map(|ip| SocketAddr::new(ip, 53))
+        })
+        .map_err(|e| {
+            error!("Invalid nameserver '{}': {}", nameserver, e);
+            InquestError::ConfigurationError
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use trust_dns_resolver::config::Protocol;
+    use trust_dns_resolver::proto::rr::RecordType;
+
+    use crate::input::parser::tests::match_content;
+    use crate::{Config, Dns};
+
+    #[test]
+    fn parse_dns() {
+        let content = r#"
+            probe-specification {
+                my-service {
+                    dns = [{
+                        name = "_ldap._tcp.corp"
+                        type = "srv"
+                        nameserver = "10.0.0.53"
+                        protocol = "tcp"
+                        timeout = 2s
+                        expected = ["0 100 389 ldap.corp"]
+                    }]
+                }
+            }"#;
+        match_content(content, |config| match config {
+            Config::Dns(Dns {
+                name,
+                record_type,
+                nameserver,
+                protocol,
+                timeout,
+                expected,
+            }) => {
+                assert_eq!("_ldap._tcp.corp", name);
+                assert_eq!(RecordType::SRV, *record_type);
+                assert_eq!(Some("10.0.0.53:53".parse().unwrap()), *nameserver);
+                assert_eq!(Protocol::Tcp, *protocol);
+                assert_eq!(Duration::from_secs(2), *timeout);
+                assert_eq!(vec!["0 100 389 ldap.corp".to_string()], *expected);
+            }
+            _ => panic!("did not match DNS probe"),
+        });
+    }
+}
@@ -0,0 +1,198 @@
+use hocon::Hocon;
+use log::error;
+use secrecy::SecretString;
+use url::Url;
+
+use crate::error::InquestError;
+use crate::input::parser::{parse_timeout, parse_tls_test};
+use crate::{Certificates, Config, FileTransfer, Ftp};
+use crate::{Result, GO};
+
+pub(crate) fn parse_ftp(hocon: &Hocon, certs: Option<Certificates>) -> Result<Vec<Config>> {
+    if let Hocon::Array(ftps) = &hocon {
+        Ok(ftps
+            .iter()
+            .flat_map(|ftp| parse(ftp, certs.clone()))
+            .collect())
+    } else {
+        Err(InquestError::ConfigurationError)
+    }
+}
+
+fn parse(hocon: &Hocon, certs: Option<Certificates>) -> Result<Config> {
+    let url = hocon["url"]
+        .as_string()
+        .ok_or(InquestError::ConfigurationError)?;
+    let explicit_tls = hocon["explicit-tls"].as_bool().unwrap_or(false);
+    let parsed = Url::parse(&url)
+        .ok()
+        .filter(|parsed| parsed.host().is_some());
+    let protocol = match parsed.as_ref().map(Url::scheme) {
+        Some("ftp") if explicit_tls => FileTransfer::ExplicitFtps,
+        Some("ftp") => FileTransfer::Ftp,
+        Some("ftps") if !explicit_tls => FileTransfer::ImplicitFtps,
+        Some("sftp") if !explicit_tls => FileTransfer::Sftp,
+        _ => {
+            error!(
+                "Invalid file transfer url '{}', expected 'ftp://', 'ftps://' or 'sftp://', \
+                'explicit-tls' only applies to 'ftp://'",
+                url
+            );
+            return Err(InquestError::ConfigurationError);
+        }
+    };
+    let (Some(parsed), Some(user), Some(password)) = (
+        parsed,
+        hocon["user"].as_string(),
+        hocon["password"].as_string(),
+    ) else {
+        error!("Invalid file transfer configuration. 'user' and 'password' are required");
+        return Err(InquestError::ConfigurationError);
+    };
+    let ftp = Ftp::new(
+        // IPv6 addresses are given in brackets
+        parsed
+            .host_str()
+            .unwrap_or_default()
+            .trim_matches(['[', ']'])
+            .to_string(),
+        protocol,
+        user,
+        SecretString::new(password),
+        certs,
+        &GO,
+    );
+    let fingerprint = hocon["fingerprint"].as_string().map(|fingerprint| {
+        match fingerprint.starts_with("SHA256:") {
+            true => fingerprint,
+            false => format!("SHA256:{}", fingerprint),
+        }
+    });
+    if fingerprint.is_some() && protocol != FileTransfer::Sftp {
+        error!(
+            "Invalid file transfer configuration. 'fingerprint' only applies to 'sftp://' in '{}'",
+            url
+        );
+        return Err(InquestError::ConfigurationError);
+    }
+    let tls_test = parse_tls_test(hocon)?;
+    if tls_test.is_some() && matches!(protocol, FileTransfer::Ftp | FileTransfer::Sftp) {
+        error!(
+            "Invalid file transfer configuration. 'tls' only applies to FTPS in '{}'",
+            url
+        );
+        return Err(InquestError::ConfigurationError);
+    }
+    let timeout = parse_timeout(hocon)?.unwrap_or(ftp.timeout);
+
+    Ok(Ftp {
+        port: parsed.port().unwrap_or(ftp.port),
+        directory: hocon["directory"].as_string(),
+        upload: hocon["upload"].as_bool().unwrap_or(false),
+        fingerprint,
+        tls_test,
+        timeout,
+        ..ftp
+    }
+    .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use secrecy::ExposeSecret;
+
+    use crate::input::parser::ftp::parse;
+    use crate::input::parser::tests::match_content;
+    use crate::{Config, FileTransfer, Ftp};
+
+    #[test]
+    fn parse_ftp() {
+        let content = r#"
+            probe-specification {
+                my-service {
+                    ftp = [{
+                        url = "ftp://files.partner.com"
+                        explicit-tls = true
+                        tls {
+                            expected-issuer = "CN=Partner CA"
+                        }
+                        user = "exchange"
+                        password = "hX8AgBVOd/GvecheybpEPA==" # 'changeit'
+                        directory = "/outgoing"
+                        upload = true
+                    }, {
+                        url = "sftp://files.partner.com:2222"
+                        user = "exchange"
+                        password = "hX8AgBVOd/GvecheybpEPA=="
+                        fingerprint = "uNiVztksCsDhcc0u9e8BujQXVUpKZIDTMczCvj3tD2s"
+                    }]
+                }
+            }"#;
+        let configs = RefCell::new(Vec::new());
+        match_content(content, |config| match config {
+            Config::Ftp(Ftp {
+                host,
+                port,
+                protocol,
+                user,
+                password,
+                directory,
+                upload,
+                fingerprint,
+                tls_test,
+                ..
+            }) => {
+                assert_eq!("files.partner.com", host);
+                assert_eq!("exchange", user);
+                assert_eq!("hX8AgBVOd/GvecheybpEPA==", password.expose_secret());
+                configs.borrow_mut().push((
+                    *port,
+                    *protocol,
+                    directory.clone(),
+                    *upload,
+                    fingerprint.clone(),
+                    tls_test
+                        .as_ref()
+                        .and_then(|tls_test| tls_test.expected_issuer.clone()),
+                ));
+            }
+            _ => panic!("did not match file transfer probe"),
+        });
+        assert_eq!(
+            vec![
+                (
+                    21,
+                    FileTransfer::ExplicitFtps,
+                    Some("/outgoing".to_string()),
+                    true,
+                    None,
+                    Some("CN=Partner CA".to_string())
+                ),
+                (
+                    2222,
+                    FileTransfer::Sftp,
+                    None,
+                    false,
+                    Some("SHA256:uNiVztksCsDhcc0u9e8BujQXVUpKZIDTMczCvj3tD2s".to_string()),
+                    None
+                )
+            ],
+            configs.into_inner()
+        );
+    }
+
+    #[test]
+    fn ipv6_host_is_unbracketed() {
+        let hocon = hocon::HoconLoader::new()
+            .load_str(r#"url = "ftp://[::1]:2121", user = "exchange", password = "changeit""#)
+            .unwrap()
+            .hocon()
+            .unwrap();
+        assert_matches!(
+            parse(&hocon, None),
+            Ok(Config::Ftp(Ftp { host, port: 2121, .. })) if host == "::1"
+        );
+    }
+}
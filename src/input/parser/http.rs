@@ -1,15 +1,31 @@
+use std::net::IpAddr;
+use std::str::FromStr;
+
 use hocon::Hocon;
+use log::error;
+use regex::Regex;
+use reqwest::{Method, Version};
+use secrecy::SecretString;
+use serde_json::{Map, Number, Value};
 use url::Url;
 
 use crate::error::InquestError;
-use crate::{Certificates, Config, Http};
+use crate::input::parser::{parse_certificate_test, parse_timeout, parse_tls_test};
+use crate::{
+    Capture, CaptureSource, Certificates, Config, DownloadTest, Health, Http, HttpProtocol,
+    HttpScenario, HttpStep, HttpVersion, Proxy,
+};
 use crate::{Result, GO};
 
-pub(crate) fn parse_http(hocon: &Hocon, certs: Option<Certificates>) -> Result<Vec<Config>> {
+pub(crate) fn parse_http(
+    hocon: &Hocon,
+    certs: Option<Certificates>,
+    proxy: Option<Proxy>,
+) -> Result<Vec<Config>> {
     if let Hocon::Array(http_specs) = &hocon {
         Ok(http_specs
             .into_iter()
-            .flat_map(|hocon| parse_get(hocon, certs.clone()))
+            .flat_map(|hocon| parse_get(hocon, certs.clone(), proxy.clone()))
             .map(|parsed| parsed.into())
             .collect())
     } else {
@@ -17,27 +33,453 @@ pub(crate) fn parse_http(hocon: &Hocon, certs: Option<Certificates>) -> Result<V
     }
 }
 
-fn parse_get(hocon: &Hocon, certs: Option<Certificates>) -> Result<Http> {
+fn parse_get(hocon: &Hocon, certs: Option<Certificates>, proxy: Option<Proxy>) -> Result<Http> {
     let url = hocon["url"]
         .as_string()
         .ok_or(InquestError::ConfigurationError)?;
     let status = hocon["status"].as_i64().map(|number| number as u16);
     let name = hocon["name"].as_string();
-    Ok(Http::new(
-        Url::parse(url.as_str())?,
-        status,
+    // a probe-specific proxy replaces the global one
+    let proxy = parse_proxy(&hocon["proxy"])?.or(proxy);
+    let certificate = parse_certificate_test(&hocon["certificate"])?;
+    let tls = parse_tls_test(hocon)?;
+    let resolve = parse_ip_address(&hocon["resolve"])?;
+    let local_address = parse_ip_address(&hocon["local-address"])?;
+    let protocol = parse_protocol(hocon)?;
+    let download = parse_download_test(&hocon["download"])?;
+    let http_version = match hocon["http-version"].as_string().as_deref() {
+        None => None,
+        Some("1.1") => Some(HttpVersion::Http11),
+        Some("2") if Url::parse(&url).is_ok_and(|url| url.scheme() == "https") => {
+            Some(HttpVersion::Http2)
+        }
+        Some("2") => {
+            error!(
+                "HTTP/2 via ALPN requires https, use '2-prior-knowledge' for '{}'",
+                url
+            );
+            return Err(InquestError::ConfigurationError);
+        }
+        Some("2-prior-knowledge") => Some(HttpVersion::Http2PriorKnowledge),
+        Some(other) => {
+            error!(
+                "Invalid 'http-version' '{}'. Supported are 1.1, 2 and 2-prior-knowledge",
+                other
+            );
+            return Err(InquestError::ConfigurationError);
+        }
+    };
+    let body_preview_size = match &hocon["body-preview-size"] {
+        Hocon::BadValue(_) => None,
+        value => Some(
+            value
+                .as_i64()
+                .and_then(|size| usize::try_from(size).ok())
+                .ok_or(InquestError::ConfigurationError)?,
+        ),
+    };
+    let expected_version = match hocon["expected-http-version"].as_string() {
+        Some(version) => Some(parse_version(&version)?),
+        None => None,
+    };
+    let method = match hocon["method"].as_string() {
+        Some(method) => Method::from_str(method.to_uppercase().as_str()).map_err(|_| {
+            error!("Invalid HTTP method '{}'", method);
+            InquestError::ConfigurationError
+        })?,
+        None if protocol.is_some() => Method::POST,
+        None => Method::GET,
+    };
+    let mut headers: Vec<(String, String)> = match &hocon["headers"] {
+        Hocon::Hash(headers) => headers
+            .iter()
+            .filter_map(|(name, value)| value.as_string().map(|value| (name.clone(), value)))
+            .collect(),
+        _ => Vec::new(),
+    };
+    let mut body = hocon["body"].as_string();
+    let protocol = match protocol {
+        Some((protocol, protocol_headers, protocol_body)) => {
+            if body.is_some() {
+                error!("Invalid HTTP probe. 'body' is built by 'graphql' or 'soap'");
+                return Err(InquestError::ConfigurationError);
+            }
+            // configured headers take precedence, e.g. a specific Content-Type
+            for (name, value) in protocol_headers {
+                if !headers.iter().any(|(n, _)| n.eq_ignore_ascii_case(&name)) {
+                    headers.push((name, value));
+                }
+            }
+            body = Some(protocol_body);
+            Some(protocol)
+        }
+        None => None,
+    };
+    headers.sort();
+    let http = Http {
+        proxy,
+        certificate,
+        tls,
+        resolve,
+        local_address,
+        method,
+        headers,
+        body,
+        protocol,
+        download,
+        http_version,
+        expected_version,
+        ..Http::new(Url::parse(url.as_str())?, status, name, &GO, certs)
+    };
+    Ok(Http {
+        body_preview_size: body_preview_size.unwrap_or(http.body_preview_size),
+        ..http
+    })
+}
+
+/// A health probe accepts the same settings as a HTTP probe
+pub(crate) fn parse_health(
+    hocon: &Hocon,
+    certs: Option<Certificates>,
+    proxy: Option<Proxy>,
+) -> Result<Vec<Config>> {
+    if let Hocon::Array(health_specs) = &hocon {
+        Ok(health_specs
+            .iter()
+            .flat_map(|hocon| parse_get(hocon, certs.clone(), proxy.clone()))
+            .map(|http| Health { http }.into())
+            .collect())
+    } else {
+        Err(InquestError::ConfigurationError)
+    }
+}
+
+pub(crate) fn parse_http_scenario(
+    hocon: &Hocon,
+    certs: Option<Certificates>,
+    proxy: Option<Proxy>,
+) -> Result<Vec<Config>> {
+    if let Hocon::Array(scenario_specs) = &hocon {
+        Ok(scenario_specs
+            .iter()
+            .flat_map(|hocon| parse_scenario(hocon, certs.clone(), proxy.clone()))
+            .map(|parsed| parsed.into())
+            .collect())
+    } else {
+        Err(InquestError::ConfigurationError)
+    }
+}
+
+fn parse_scenario(
+    hocon: &Hocon,
+    certs: Option<Certificates>,
+    proxy: Option<Proxy>,
+) -> Result<HttpScenario> {
+    let name = hocon["name"].as_string();
+    let mut variables = match &hocon["variables"] {
+        Hocon::Hash(variables) => variables
+            .iter()
+            .filter_map(|(name, value)| {
+                value
+                    .as_string()
+                    .map(|value| (name.clone(), SecretString::new(value)))
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    variables.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let steps = match &hocon["steps"] {
+        Hocon::Array(steps) if !steps.is_empty() => steps
+            .iter()
+            .map(|step| parse_step(step, certs.clone(), proxy.clone()))
+            .collect::<Result<Vec<HttpStep>>>()?,
+        _ => {
+            error!("Invalid HTTP scenario. At least one entry in 'steps' is required");
+            return Err(InquestError::ConfigurationError);
+        }
+    };
+
+    // a placeholder must refer to a variable or to a value captured in a previous step
+    let mut known = variables
+        .iter()
+        .map(|(name, _)| name.clone())
+        .collect::<Vec<_>>();
+    for (index, step) in steps.iter().enumerate() {
+        if let Some(unknown) = step
+            .placeholders()
+            .into_iter()
+            .find(|placeholder| !known.contains(placeholder))
+        {
+            error!(
+                "Invalid HTTP scenario. Unknown variable '{}' in step {}",
+                unknown,
+                index + 1
+            );
+            return Err(InquestError::ConfigurationError);
+        }
+        known.extend(step.captures.iter().map(|capture| capture.name.clone()));
+    }
+
+    Ok(HttpScenario {
         name,
-        &GO,
-        certs,
+        variables,
+        steps,
+    })
+}
+
+fn parse_step(
+    hocon: &Hocon,
+    certs: Option<Certificates>,
+    proxy: Option<Proxy>,
+) -> Result<HttpStep> {
+    let http = parse_get(hocon, certs, proxy)?;
+    let mut captures = match &hocon["capture"] {
+        Hocon::Hash(captures) => captures
+            .iter()
+            .map(|(name, source)| parse_capture(name, source))
+            .collect::<Result<Vec<Capture>>>()?,
+        _ => Vec::new(),
+    };
+    captures.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(HttpStep { http, captures })
+}
+
+/// Parses a single capture like `token = { json = "/access_token" }`, `id = { header = "X-Id" }`
+/// or `csrf = { regex = "name=\"csrf\" value=\"(\\w+)\"" }`.
+fn parse_capture(name: &str, hocon: &Hocon) -> Result<Capture> {
+    let source = if let Some(pointer) = hocon["json"].as_string() {
+        if !pointer.starts_with('/') {
+            error!(
+                "Invalid capture '{}'. '{}' is not a JSON pointer like '/data/token'",
+                name, pointer
+            );
+            return Err(InquestError::ConfigurationError);
+        }
+        CaptureSource::Json(pointer)
+    } else if let Some(header) = hocon["header"].as_string() {
+        CaptureSource::Header(header)
+    } else if let Some(regex) = hocon["regex"].as_string() {
+        CaptureSource::Regex(Regex::new(regex.as_str()).map_err(|e| {
+            error!("Invalid capture '{}'. {}", name, e);
+            InquestError::ConfigurationError
+        })?)
+    } else {
+        error!(
+            "Invalid capture '{}'. Either 'json', 'header' or 'regex' is required",
+            name
+        );
+        return Err(InquestError::ConfigurationError);
+    };
+    Ok(Capture {
+        name: name.to_string(),
+        source,
+    })
+}
+
+/// The request of the GraphQL or SOAP sub-mode, as protocol with its headers and body
+type ProtocolRequest = (HttpProtocol, Vec<(String, String)>, String);
+
+fn parse_protocol(hocon: &Hocon) -> Result<Option<ProtocolRequest>> {
+    match (&hocon["graphql"], &hocon["soap"]) {
+        (Hocon::BadValue(_), Hocon::BadValue(_)) => Ok(None),
+        (graphql, Hocon::BadValue(_)) => parse_graphql(graphql).map(Some),
+        (Hocon::BadValue(_), soap) => parse_soap(soap).map(Some),
+        _ => {
+            error!("Invalid HTTP probe. Either 'graphql' or 'soap' can be configured");
+            Err(InquestError::ConfigurationError)
+        }
+    }
+}
+
+/// Parses `graphql { query, variables {}, operation-name }` into the JSON request
+fn parse_graphql(hocon: &Hocon) -> Result<ProtocolRequest> {
+    let query = hocon["query"].as_string().ok_or_else(|| {
+        error!("Invalid GraphQL configuration. 'query' is required");
+        InquestError::ConfigurationError
+    })?;
+    let mut request = Map::new();
+    request.insert("query".to_string(), Value::String(query));
+    if let Some(operation_name) = hocon["operation-name"].as_string() {
+        request.insert("operationName".to_string(), Value::String(operation_name));
+    }
+    if let Hocon::Hash(_) = &hocon["variables"] {
+        request.insert("variables".to_string(), to_json(&hocon["variables"]));
+    }
+    Ok((
+        HttpProtocol::GraphQl,
+        vec![("Content-Type".to_string(), "application/json".to_string())],
+        Value::Object(request).to_string(),
     ))
 }
 
+/// Parses `soap { action, body, version }` into an envelope. The `body` is the content of the
+/// SOAP body, the SOAPAction is sent as header (1.1) or as part of the Content-Type (1.2).
+fn parse_soap(hocon: &Hocon) -> Result<ProtocolRequest> {
+    let content = hocon["body"].as_string().ok_or_else(|| {
+        error!("Invalid SOAP configuration. 'body' is required");
+        InquestError::ConfigurationError
+    })?;
+    let action = hocon["action"].as_string().unwrap_or_default();
+    let (namespace, headers) = match hocon["version"].as_string().as_deref() {
+        None | Some("1.1") => (
+            "http://schemas.xmlsoap.org/soap/envelope/",
+            vec![
+                (
+                    "Content-Type".to_string(),
+                    "text/xml; charset=utf-8".to_string(),
+                ),
+                ("SOAPAction".to_string(), format!("\"{}\"", action)),
+            ],
+        ),
+        Some("1.2") => (
+            "http://www.w3.org/2003/05/soap-envelope",
+            vec![(
+                "Content-Type".to_string(),
+                format!("application/soap+xml; charset=utf-8; action=\"{}\"", action),
+            )],
+        ),
+        Some(other) => {
+            error!(
+                "Invalid SOAP version '{}'. Supported are 1.1 and 1.2",
+                other
+            );
+            return Err(InquestError::ConfigurationError);
+        }
+    };
+    let envelope = format!(
+        r#"<?xml version="1.0" encoding="utf-8"?><soap:Envelope xmlns:soap="{}"><soap:Body>{}</soap:Body></soap:Envelope>"#,
+        namespace, content
+    );
+    Ok((HttpProtocol::Soap, headers, envelope))
+}
+
+fn to_json(hocon: &Hocon) -> Value {
+    match hocon {
+        Hocon::Real(number) => Number::from_f64(*number).map_or(Value::Null, Value::Number),
+        Hocon::Integer(number) => Value::from(*number),
+        Hocon::String(text) => Value::String(text.clone()),
+        Hocon::Boolean(value) => Value::Bool(*value),
+        Hocon::Array(values) => Value::Array(values.iter().map(to_json).collect()),
+        Hocon::Hash(values) => Value::Object(
+            values
+                .iter()
+                .map(|(key, value)| (key.clone(), to_json(value)))
+                .collect(),
+        ),
+        Hocon::Null | Hocon::BadValue(_) => Value::Null,
+    }
+}
+
+/// Accepts the version with or without prefix, e.g. `2` or `HTTP/2`
+fn parse_version(version: &str) -> Result<Version> {
+    match version.trim_start_matches("HTTP/") {
+        "1.0" => Ok(Version::HTTP_10),
+        "1.1" => Ok(Version::HTTP_11),
+        "2" | "2.0" => Ok(Version::HTTP_2),
+        "3" | "3.0" => Ok(Version::HTTP_3),
+        _ => {
+            error!("Invalid HTTP version '{}'", version);
+            Err(InquestError::ConfigurationError)
+        }
+    }
+}
+
+fn parse_ip_address(hocon: &Hocon) -> Result<Option<IpAddr>> {
+    match hocon.as_string() {
+        Some(address) => match IpAddr::from_str(address.trim()) {
+            Ok(address) => Ok(Some(address)),
+            Err(_) => {
+                error!("Invalid IP address '{}'", address);
+                Err(InquestError::ConfigurationError)
+            }
+        },
+        None => Ok(None),
+    }
+}
+
+/// Parses `download { sha256 = "...", min-throughput = 10.5, timeout = 10m }`, the throughput in
+/// MB/s.
+fn parse_download_test(hocon: &Hocon) -> Result<Option<DownloadTest>> {
+    if let Hocon::BadValue(_) = hocon {
+        return Ok(None);
+    };
+
+    let sha256 = hocon["sha256"].as_string();
+    if let Some(digest) = &sha256 {
+        if digest.len() != 64 || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+            error!(
+                "Invalid download configuration. '{}' is not a hex encoded SHA-256",
+                digest
+            );
+            return Err(InquestError::ConfigurationError);
+        }
+    }
+    let min_throughput = match &hocon["min-throughput"] {
+        Hocon::BadValue(_) => None,
+        value => Some(
+            value
+                .as_f64()
+                .filter(|throughput| *throughput > 0.0)
+                .ok_or(InquestError::ConfigurationError)?,
+        ),
+    };
+    Ok(Some(DownloadTest {
+        sha256,
+        min_throughput,
+        timeout: parse_timeout(hocon)?,
+    }))
+}
+
+/// Parses a `proxy { url, user, password, no-proxy, ignore-environment }` block.
+/// `no-proxy` can either be a list or a comma-separated string like the NO_PROXY variable.
+pub(crate) fn parse_proxy(hocon: &Hocon) -> Result<Option<Proxy>> {
+    if let Hocon::BadValue(_) = hocon {
+        return Ok(None);
+    };
+
+    let url = match hocon["url"].as_string() {
+        Some(url) => Some(Url::parse(url.as_str())?),
+        None => None,
+    };
+    let user = hocon["user"].as_string();
+    let password = hocon["password"].as_string().map(SecretString::new);
+    if password.is_some() && user.is_none() {
+        error!("Invalid proxy configuration. 'password' without 'user'");
+        return Err(InquestError::ConfigurationError);
+    }
+    let no_proxy = match &hocon["no-proxy"] {
+        Hocon::Array(hosts) => hosts.iter().filter_map(|host| host.as_string()).collect(),
+        Hocon::String(hosts) => hosts
+            .split(',')
+            .map(|host| host.trim().to_string())
+            .filter(|host| !host.is_empty())
+            .collect(),
+        _ => Vec::new(),
+    };
+    let ignore_environment = hocon["ignore-environment"].as_bool().unwrap_or(false);
+
+    Ok(Some(Proxy {
+        url,
+        user,
+        password,
+        no_proxy,
+        ignore_environment,
+    }))
+}
+
 #[cfg(test)]
 mod tests {
     use std::ops::Deref;
 
-    use crate::input::parser::tests::match_content;
-    use crate::{Config, Http};
+    use secrecy::ExposeSecret;
+
+    use crate::error::InquestError;
+    use crate::input::parser::http::parse_get;
+    use crate::input::parser::tests::{match_content, setup};
+    use crate::{
+        Capture, CaptureSource, CertificateTest, Config, Health, Http, HttpProtocol, HttpScenario,
+        HttpStep, HttpVersion, Proxy, TlsTest,
+    };
 
     #[test]
     fn parse_http() {
@@ -68,4 +510,371 @@ mod tests {
             _ => panic!("did not match HTTP probe"),
         });
     }
+
+    #[test]
+    fn parse_http_with_proxy() {
+        let content = r#"
+            probe-specification {
+                proxy {
+                    url = "http://global-proxy:3128"
+                }
+
+                my-service {
+                    http = [
+                        {
+                            url = "https://httpbin.org/get"
+                            proxy {
+                                url = "http://proxy.corp:8080"
+                                user = "proxy-user"
+                                password = "secret"
+                                no-proxy = "localhost, .internal"
+                            }
+                        }
+                    ]
+                }
+            }"#;
+        match_content(content, |config| match config {
+            Config::Http(Http {
+                proxy:
+                    Some(Proxy {
+                        url,
+                        user,
+                        password,
+                        no_proxy,
+                        ignore_environment,
+                    }),
+                ..
+            }) => {
+                assert_eq!("http://proxy.corp:8080/", url.as_ref().unwrap().as_str());
+                assert_eq!("proxy-user", user.as_ref().unwrap());
+                assert_eq!("secret", password.as_ref().unwrap().expose_secret());
+                assert_eq!(&vec!["localhost", ".internal"], no_proxy);
+                assert!(!ignore_environment);
+            }
+            _ => panic!("did not match HTTP probe with proxy"),
+        });
+    }
+
+    #[test]
+    fn parse_http_uses_global_proxy() {
+        let content = r#"
+            probe-specification {
+                proxy {
+                    url = "http://global-proxy:3128"
+                    no-proxy = ["localhost"]
+                    ignore-environment = true
+                }
+
+                my-service {
+                    http = [
+                        {
+                            url = "https://httpbin.org/get"
+                        }
+                    ]
+                }
+            }"#;
+        match_content(content, |config| match config {
+            Config::Http(Http {
+                proxy:
+                    Some(Proxy {
+                        url,
+                        no_proxy,
+                        ignore_environment,
+                        ..
+                    }),
+                ..
+            }) => {
+                assert_eq!("http://global-proxy:3128/", url.as_ref().unwrap().as_str());
+                assert_eq!(&vec!["localhost"], no_proxy);
+                assert!(ignore_environment);
+            }
+            _ => panic!("did not match HTTP probe with global proxy"),
+        });
+    }
+
+    #[test]
+    fn parse_http_with_certificate_test() {
+        let content = r#"
+            probe-specification {
+                my-service {
+                    http = [
+                        {
+                            url = "https://httpbin.org/get"
+                            certificate.min-days-valid = 21
+                        }
+                    ]
+                }
+            }"#;
+        match_content(content, |config| match config {
+            Config::Http(Http {
+                certificate: Some(CertificateTest { min_days_valid }),
+                ..
+            }) => {
+                assert_eq!(Some(21), *min_days_valid);
+            }
+            _ => panic!("did not match HTTP probe with certificate test"),
+        });
+    }
+
+    #[test]
+    fn parse_http_with_tls_test() {
+        let content = r#"
+            probe-specification {
+                my-service {
+                    http = [
+                        {
+                            url = "https://httpbin.org/get"
+                            tls {
+                                pin-sha256 = ["sha256//ZJaE2Dk5vSL3Pu+co2Jk8t4wTQHBlzyz0E6i7BBB2EQ="]
+                                expected-issuer = "O=Amazon"
+                            }
+                        }
+                    ]
+                }
+            }"#;
+        match_content(content, |config| match config {
+            Config::Http(Http {
+                tls:
+                    Some(TlsTest {
+                        pin_sha256,
+                        expected_issuer,
+                    }),
+                ..
+            }) => {
+                assert_eq!(
+                    &vec!["sha256//ZJaE2Dk5vSL3Pu+co2Jk8t4wTQHBlzyz0E6i7BBB2EQ="],
+                    pin_sha256
+                );
+                assert_eq!("O=Amazon", expected_issuer.as_ref().unwrap());
+            }
+            _ => panic!("did not match HTTP probe with TLS test"),
+        });
+    }
+
+    #[test]
+    fn parse_http_with_resolve_and_local_address() {
+        let content = r#"
+            probe-specification {
+                my-service {
+                    http = [
+                        {
+                            url = "https://my-service.corp/health"
+                            resolve = "10.0.0.5"
+                            local-address = "192.168.1.10"
+                        }
+                    ]
+                }
+            }"#;
+        match_content(content, |config| match config {
+            Config::Http(Http {
+                resolve,
+                local_address,
+                ..
+            }) => {
+                assert_eq!("10.0.0.5", resolve.unwrap().to_string());
+                assert_eq!("192.168.1.10", local_address.unwrap().to_string());
+            }
+            _ => panic!("did not match HTTP probe"),
+        });
+    }
+
+    #[test]
+    fn parse_http_scenario() {
+        let content = r#"
+            probe-specification {
+                my-service {
+                    http-scenario = [
+                        {
+                            name = "Login and list orders"
+                            variables { user = "admin" }
+                            steps = [
+                                {
+                                    name = "login"
+                                    method = "post"
+                                    url = "https://my-service.corp/login"
+                                    headers { Content-Type = "application/json" }
+                                    body = """{"user": "{{user}}"}"""
+                                    capture {
+                                        token = { json = "/access_token" }
+                                        request = { header = "X-Request-Id" }
+                                    }
+                                },
+                                {
+                                    url = "https://my-service.corp/orders/{{request}}"
+                                    headers { Authorization = "Bearer {{token}}" }
+                                    status = 204
+                                }
+                            ]
+                        }
+                    ]
+                }
+            }"#;
+        match_content(content, |config| match config {
+            Config::HttpScenario(HttpScenario {
+                name,
+                variables,
+                steps,
+            }) => {
+                assert_eq!(Some("Login and list orders".to_string()), *name);
+                assert_eq!("admin", variables[0].1.expose_secret());
+                match &steps[..] {
+                    [HttpStep {
+                        http: login,
+                        captures,
+                    }, HttpStep { http: orders, .. }] => {
+                        assert_eq!("POST", login.method.as_str());
+                        assert_eq!(Some(r#"{"user": "{{user}}"}"#.to_string()), login.body);
+                        assert_matches!(&captures[..], [
+                            Capture { source: CaptureSource::Header(header), .. },
+                            Capture { source: CaptureSource::Json(pointer), .. }
+                        ] if header == "X-Request-Id" && pointer == "/access_token");
+                        assert_eq!(204, orders.status);
+                        assert_eq!(
+                            vec![("Authorization".to_string(), "Bearer {{token}}".to_string())],
+                            orders.headers
+                        );
+                    }
+                    _ => panic!("expected two steps"),
+                }
+            }
+            _ => panic!("did not match HTTP scenario"),
+        });
+    }
+
+    #[test]
+    fn parse_http_scenario_with_unknown_variable() {
+        let content = r#"
+            probe-specification {
+                my-service {
+                    http-scenario = [
+                        {
+                            steps = [
+                                { url = "https://my-service.corp/orders/{{token}}" }
+                            ]
+                        }
+                    ]
+                }
+            }"#;
+        assert!(setup(content)[0].probe_configs.is_empty());
+    }
+
+    #[test]
+    fn parse_health() {
+        let content = r#"
+            probe-specification {
+                my-service {
+                    health = [
+                        {
+                            url = "https://my-service.corp/actuator/health"
+                            headers { Accept = "application/vnd.spring-boot.actuator.v3+json" }
+                        }
+                    ]
+                }
+            }"#;
+        match_content(content, |config| match config {
+            Config::Health(Health { http }) => {
+                assert_eq!("https://my-service.corp/actuator/health", http.url.as_str());
+                assert_eq!(200, http.status);
+                assert_eq!(1, http.headers.len());
+            }
+            _ => panic!("did not match health probe"),
+        });
+    }
+
+    #[test]
+    fn parse_http_with_graphql_and_soap() {
+        let content = r#"
+            probe-specification {
+                my-service {
+                    http = [
+                        {
+                            url = "https://partner.corp/graphql"
+                            graphql {
+                                query = "query($id: ID!) { order(id: $id) { id } }"
+                                variables { id = 42 }
+                            }
+                        },
+                        {
+                            url = "https://partner.corp/soap"
+                            soap {
+                                action = "urn:GetStatus"
+                                body = "<GetStatus xmlns=\"urn:partner\"/>"
+                            }
+                        }
+                    ]
+                }
+            }"#;
+        match_content(content, |config| match config {
+            Config::Http(Http {
+                url,
+                method,
+                headers,
+                body: Some(body),
+                protocol: Some(protocol),
+                ..
+            }) => {
+                assert_eq!("POST", method.as_str());
+                match protocol {
+                    HttpProtocol::GraphQl => {
+                        assert_eq!("https://partner.corp/graphql", url.as_str());
+                        assert_eq!(
+                            r#"{"query":"query($id: ID!) { order(id: $id) { id } }","variables":{"id":42}}"#,
+                            body
+                        );
+                    }
+                    HttpProtocol::Soap => {
+                        assert_eq!("https://partner.corp/soap", url.as_str());
+                        assert!(body.contains(
+                            r#"<soap:Body><GetStatus xmlns="urn:partner"/></soap:Body>"#
+                        ));
+                        assert!(headers.contains(&(
+                            "SOAPAction".to_string(),
+                            "\"urn:GetStatus\"".to_string()
+                        )));
+                    }
+                }
+            }
+            _ => panic!("did not match HTTP probe with protocol"),
+        });
+    }
+
+    #[test]
+    fn parse_http_with_version() {
+        let content = r#"
+            probe-specification {
+                my-service {
+                    http = [
+                        {
+                            url = "HTTPS://my-service.corp/health"
+                            http-version = "2"
+                            expected-http-version = "HTTP/2"
+                        }
+                    ]
+                }
+            }"#;
+        match_content(content, |config| match config {
+            Config::Http(Http {
+                http_version,
+                expected_version,
+                ..
+            }) => {
+                assert_eq!(Some(HttpVersion::Http2), *http_version);
+                assert_eq!(Some(reqwest::Version::HTTP_2), *expected_version);
+            }
+            _ => panic!("did not match HTTP probe"),
+        });
+    }
+
+    #[test]
+    fn http2_requires_https() {
+        let hocon = hocon::HoconLoader::new()
+            .load_str(r#"url = "http://my-service.corp/health", http-version = "2""#)
+            .unwrap()
+            .hocon()
+            .unwrap();
+        assert_matches!(
+            parse_get(&hocon, None, None),
+            Err(InquestError::ConfigurationError)
+        );
+    }
 }
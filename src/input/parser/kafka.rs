@@ -0,0 +1,179 @@
+use hocon::Hocon;
+use log::error;
+use secrecy::SecretString;
+
+use crate::error::InquestError;
+use crate::input::parser::{parse_timeout, parse_tls_switch};
+use crate::{Certificates, Config, Kafka, KafkaSasl, KafkaTopic, SaslMechanism};
+use crate::{Result, GO};
+
+pub(crate) fn parse_kafka(hocon: &Hocon, certs: Option<Certificates>) -> Result<Vec<Config>> {
+    if let Hocon::Array(kafkas) = &hocon {
+        Ok(kafkas
+            .iter()
+            .flat_map(|kafka| parse(kafka, certs.clone()))
+            .collect())
+    } else {
+        Err(InquestError::ConfigurationError)
+    }
+}
+
+fn parse(hocon: &Hocon, certs: Option<Certificates>) -> Result<Config> {
+    let bootstrap_servers = match &hocon["bootstrap-servers"] {
+        Hocon::Array(servers) => servers
+            .iter()
+            .map(|server| server.as_string().ok_or(InquestError::ConfigurationError))
+            .collect::<Result<Vec<_>>>()?,
+        // the format of the Kafka clients, e.g. 'kafka-1:9092,kafka-2:9092'
+        Hocon::String(servers) => servers.split(',').map(|s| s.trim().to_string()).collect(),
+        _ => Vec::new(),
+    }
+    .iter()
+    .map(|server| parse_server(server))
+    .collect::<Result<Vec<_>>>()?;
+    if bootstrap_servers.is_empty() {
+        error!("Invalid Kafka configuration. At least one of 'bootstrap-servers' is required");
+        return Err(InquestError::ConfigurationError);
+    }
+    let kafka = Kafka::new(bootstrap_servers, certs, &GO);
+    let timeout = parse_timeout(hocon)?.unwrap_or(kafka.timeout);
+    let sasl = match &hocon["sasl"] {
+        Hocon::BadValue(_) => None,
+        sasl => Some(parse_sasl(sasl)?),
+    };
+    let (tls, tls_test) = parse_tls_switch(hocon, "tls")?;
+    let topic = match &hocon["topic"] {
+        Hocon::BadValue(_) => None,
+        Hocon::String(name) => Some(KafkaTopic {
+            name: name.to_string(),
+            partitions: None,
+        }),
+        topic => Some(KafkaTopic {
+            name: topic["name"]
+                .as_string()
+                .ok_or(InquestError::ConfigurationError)?,
+            partitions: topic["partitions"]
+                .as_i64()
+                .map(|partitions| {
+                    usize::try_from(partitions).map_err(|_| InquestError::ConfigurationError)
+                })
+                .transpose()?,
+        }),
+    };
+
+    Ok(Kafka {
+        tls,
+        tls_test,
+        sasl,
+        timeout,
+        topic,
+        ..kafka
+    }
+    .into())
+}
+
+fn parse_server(server: &str) -> Result<(String, u16)> {
+    server
+        .rsplit_once(':')
+        .and_then(|(host, port)| Some((host.to_string(), port.parse().ok()?)))
+        .ok_or_else(|| {
+            error!("Invalid Kafka server '{}', expected 'host:port'", server);
+            InquestError::ConfigurationError
+        })
+}
+
+fn parse_sasl(hocon: &Hocon) -> Result<KafkaSasl> {
+    let mechanism = match hocon["mechanism"].as_string().as_deref() {
+        Some("PLAIN") => SaslMechanism::Plain,
+        Some("SCRAM-SHA-256") => SaslMechanism::ScramSha256,
+        Some("SCRAM-SHA-512") => SaslMechanism::ScramSha512,
+        other => {
+            error!(
+                "Invalid SASL mechanism '{}', expected PLAIN, SCRAM-SHA-256 or SCRAM-SHA-512",
+                other.unwrap_or_default()
+            );
+            return Err(InquestError::ConfigurationError);
+        }
+    };
+    Ok(KafkaSasl {
+        mechanism,
+        user: hocon["user"]
+            .as_string()
+            .ok_or(InquestError::ConfigurationError)?,
+        password: SecretString::new(
+            hocon["password"]
+                .as_string()
+                .ok_or(InquestError::ConfigurationError)?,
+        ),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use secrecy::ExposeSecret;
+
+    use crate::input::parser::tests::match_content;
+    use crate::{Config, Kafka, KafkaSasl, KafkaTopic, SaslMechanism, TlsTest};
+
+    #[test]
+    fn parse_kafka() {
+        let content = r#"
+            probe-specification {
+                my-service {
+                    kafka = [{
+                        bootstrap-servers = "kafka-1.corp:9093, kafka-2.corp:9093"
+                        tls {
+                            expected-issuer = "CN=Corp CA"
+                        }
+                        sasl {
+                            mechanism = "SCRAM-SHA-512"
+                            user = "probe"
+                            password = "hX8AgBVOd/GvecheybpEPA==" # 'changeit'
+                        }
+                        timeout = 5s
+                        topic {
+                            name = "orders"
+                            partitions = 6
+                        }
+                    }]
+                }
+            }"#;
+        match_content(content, |config| match config {
+            Config::Kafka(Kafka {
+                bootstrap_servers,
+                tls,
+                tls_test: Some(TlsTest {
+                    expected_issuer, ..
+                }),
+                sasl:
+                    Some(KafkaSasl {
+                        mechanism,
+                        user,
+                        password,
+                    }),
+                timeout,
+                topic: Some(KafkaTopic { name, partitions }),
+                ..
+            }) => {
+                assert_eq!(
+                    vec![
+                        ("kafka-1.corp".to_string(), 9093),
+                        ("kafka-2.corp".to_string(), 9093)
+                    ],
+                    *bootstrap_servers
+                );
+                assert!(*tls);
+                assert_eq!(Some("CN=Corp CA".to_string()), *expected_issuer);
+                assert_eq!(SaslMechanism::ScramSha512, *mechanism);
+                assert_eq!("probe", user);
+                assert_eq!("hX8AgBVOd/GvecheybpEPA==", password.expose_secret());
+                assert_eq!(Duration::from_secs(5), *timeout);
+                assert_eq!("orders", name);
+                assert_eq!(Some(6), *partitions);
+            }
+            _ => panic!("did not match Kafka probe"),
+        });
+    }
+}
@@ -0,0 +1,174 @@
+use hocon::Hocon;
+use log::error;
+use secrecy::SecretString;
+use url::Url;
+
+use crate::error::InquestError;
+use crate::input::parser::{parse_timeout, parse_tls_switch, parse_tls_test};
+use crate::{Certificates, Config, Ldap, LdapSearch};
+use crate::{Result, GO};
+
+pub(crate) fn parse_ldap(hocon: &Hocon, certs: Option<Certificates>) -> Result<Vec<Config>> {
+    if let Hocon::Array(ldaps) = &hocon {
+        Ok(ldaps
+            .iter()
+            .flat_map(|ldap| parse(ldap, certs.clone()))
+            .collect())
+    } else {
+        Err(InquestError::ConfigurationError)
+    }
+}
+
+fn parse(hocon: &Hocon, certs: Option<Certificates>) -> Result<Config> {
+    let url = hocon["url"]
+        .as_string()
+        .ok_or(InquestError::ConfigurationError)?;
+    let (host, port, tls) = match Url::parse(&url) {
+        Ok(parsed) if matches!(parsed.scheme(), "ldap" | "ldaps") && parsed.host().is_some() => {
+            let tls = parsed.scheme() == "ldaps";
+            let port = parsed.port().unwrap_or(if tls { 636 } else { 389 });
+            (parsed.host_str().unwrap_or_default().to_string(), port, tls)
+        }
+        _ => {
+            error!(
+                "Invalid LDAP url '{}', expected 'ldap://' or 'ldaps://'",
+                url
+            );
+            return Err(InquestError::ConfigurationError);
+        }
+    };
+    let (starttls, starttls_test) = parse_tls_switch(hocon, "starttls")?;
+    if tls && starttls {
+        error!("Invalid LDAP configuration. StartTLS requires an 'ldap://' url");
+        return Err(InquestError::ConfigurationError);
+    }
+    let tls_test = parse_tls_test(hocon)?.or(starttls_test);
+    if tls_test.is_some() && !tls && !starttls {
+        error!("Invalid LDAP configuration. 'tls' requires an 'ldaps://' url or 'starttls'");
+        return Err(InquestError::ConfigurationError);
+    }
+    let bind_dn = hocon["bind-dn"].as_string();
+    let password = hocon["password"].as_string();
+    if bind_dn.is_some() && password.as_deref().unwrap_or_default().is_empty() {
+        error!("Invalid LDAP configuration. 'bind-dn' requires a 'password', an empty one is an unauthenticated bind");
+        return Err(InquestError::ConfigurationError);
+    }
+    let ldap = Ldap::new(host, certs, &GO);
+    let timeout = parse_timeout(hocon)?.unwrap_or(ldap.timeout);
+    let search = match &hocon["search"] {
+        Hocon::BadValue(_) => None,
+        search => Some(parse_search(search)?),
+    };
+
+    Ok(Ldap {
+        port,
+        tls,
+        starttls,
+        tls_test,
+        bind_dn,
+        password: password.map(SecretString::new),
+        timeout,
+        search,
+        ..ldap
+    }
+    .into())
+}
+
+fn parse_search(hocon: &Hocon) -> Result<LdapSearch> {
+    let attributes = match &hocon["attributes"] {
+        Hocon::Array(attributes) => attributes
+            .iter()
+            .map(|attribute| {
+                attribute
+                    .as_string()
+                    .ok_or(InquestError::ConfigurationError)
+            })
+            .collect::<Result<Vec<_>>>()?,
+        Hocon::BadValue(_) => Vec::new(),
+        _ => return Err(InquestError::ConfigurationError),
+    };
+    Ok(LdapSearch {
+        base: hocon["base"]
+            .as_string()
+            .ok_or(InquestError::ConfigurationError)?,
+        filter: hocon["filter"]
+            .as_string()
+            .unwrap_or_else(|| "(objectClass=*)".to_string()),
+        attributes,
+        limit: hocon["limit"].as_i64().unwrap_or(100),
+        min_results: hocon["min-results"]
+            .as_i64()
+            .map(|min| usize::try_from(min).map_err(|_| InquestError::ConfigurationError))
+            .transpose()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use secrecy::ExposeSecret;
+
+    use crate::input::parser::tests::match_content;
+    use crate::{Config, Ldap, LdapSearch, TlsTest};
+
+    #[test]
+    fn parse_ldap() {
+        let content = r#"
+            probe-specification {
+                my-service {
+                    ldap = [{
+                        url = "ldaps://dc.corp"
+                        tls {
+                            expected-issuer = "CN=Corp CA"
+                        }
+                        bind-dn = "cn=probe,dc=corp"
+                        password = "hX8AgBVOd/GvecheybpEPA==" # 'changeit'
+                        search {
+                            base = "ou=people,dc=corp"
+                            filter = "(uid=probe)"
+                            attributes = ["cn", "mail"]
+                            min-results = 1
+                        }
+                    }]
+                }
+            }"#;
+        match_content(content, |config| match config {
+            Config::Ldap(Ldap {
+                host,
+                port,
+                tls,
+                starttls,
+                tls_test: Some(TlsTest {
+                    expected_issuer, ..
+                }),
+                bind_dn,
+                password,
+                search:
+                    Some(LdapSearch {
+                        base,
+                        filter,
+                        attributes,
+                        limit,
+                        min_results,
+                    }),
+                ..
+            }) => {
+                assert_eq!("dc.corp", host);
+                assert_eq!(636, *port);
+                assert!(*tls);
+                assert!(!*starttls);
+                assert_eq!(Some("CN=Corp CA".to_string()), *expected_issuer);
+                assert_eq!(Some("cn=probe,dc=corp".to_string()), *bind_dn);
+                assert_eq!(
+                    "hX8AgBVOd/GvecheybpEPA==",
+                    password.as_ref().unwrap().expose_secret()
+                );
+                assert_eq!("ou=people,dc=corp", base);
+                assert_eq!("(uid=probe)", filter);
+                assert_eq!(vec!["cn".to_string(), "mail".to_string()], *attributes);
+                assert_eq!(100, *limit);
+                assert_eq!(Some(1), *min_results);
+            }
+            _ => panic!("did not match LDAP probe"),
+        });
+    }
+}
@@ -1,48 +1,90 @@
+use std::time::Duration;
+
 use hocon::Hocon;
 use log::{error, warn};
+use secrecy::SecretString;
 
 use crate::error::InquestError;
-use crate::input::parser::http::parse_http;
+use crate::input::parser::amqp::parse_amqp;
+use crate::input::parser::dns::parse_dns;
+use crate::input::parser::ftp::parse_ftp;
+use crate::input::parser::http::{parse_health, parse_http, parse_http_scenario, parse_proxy};
+use crate::input::parser::kafka::parse_kafka;
+use crate::input::parser::ldap::parse_ldap;
+use crate::input::parser::mongodb::parse_mongodb;
 use crate::input::parser::mssql::parse_mssql;
+use crate::input::parser::mysql::parse_mysql;
+use crate::input::parser::ntp::parse_ntp;
 use crate::input::parser::oracle::parse_oracle;
 use crate::input::parser::postgres::parse_postgres;
-use crate::{Certificates, Result};
-use crate::{Config, ServiceSpecification, SqlTest};
+use crate::input::parser::redis::parse_redis;
+use crate::input::parser::smtp::parse_smtp;
+use crate::input::parser::ssh::parse_ssh;
+use crate::input::parser::tcp::parse_tcp;
+use crate::input::parser::tls::parse_tls;
+use crate::input::parser::udp::parse_udp;
+use crate::{CertificateTest, Config, ServiceSpecification, SqlTest, TlsTest};
+use crate::{Certificates, Proxy, Result};
 
+mod amqp;
+mod dns;
+mod ftp;
 mod http;
+mod kafka;
+mod ldap;
+mod mongodb;
 mod mssql;
+mod mysql;
+mod ntp;
 mod oracle;
 mod postgres;
+mod redis;
+mod smtp;
+mod ssh;
+mod tcp;
+mod tls;
+mod udp;
+
+/// The keys within a service that configure a list of probes
+const PROBE_TYPES: &[&str] = &[
+    "http",
+    "http-scenario",
+    "health",
+    "tcp",
+    "udp",
+    "dns",
+    "tls",
+    "oracle",
+    "postgres",
+    "mssql",
+    "mysql",
+    "redis",
+    "mongodb",
+    "kafka",
+    "amqp",
+    "smtp",
+    "ldap",
+    "ssh",
+    "ftp",
+    "ntp",
+];
 
 pub fn parse(hocon: &Hocon) -> Result<Vec<ServiceSpecification>> {
     let root = &hocon["probe-specification"];
     // let options =
 
     let certs = parse_global_certificates(root)?;
+    let proxy = parse_proxy(&root["proxy"])?;
 
     let result = match root {
         Hocon::Hash(service) => service
             .into_iter()
             .filter(|(_, v)| {
-                let http_present = match v["http"] {
-                    Hocon::Array(_) => true,
-                    _ => false,
-                };
-                let oracle_present = match v["oracle"] {
-                    Hocon::Array(_) => true,
-                    _ => false,
-                };
-                let postgres_present = match v["postgres"] {
-                    Hocon::Array(_) => true,
-                    _ => false,
-                };
-                let mssql_present = match v["mssql"] {
-                    Hocon::Array(_) => true,
-                    _ => false,
-                };
-                http_present || oracle_present || postgres_present || mssql_present
+                PROBE_TYPES
+                    .iter()
+                    .any(|probe| matches!(v[*probe], Hocon::Array(_)))
             })
-            .filter_map(|(k, v)| parse_service(k, v, certs.clone()).ok())
+            .filter_map(|(k, v)| parse_service(k, v, certs.clone(), proxy.clone()).ok())
             .collect::<Vec<ServiceSpecification>>(),
         _ => Default::default(),
     };
@@ -54,6 +96,10 @@ fn parse_global_certificates(root: &Hocon) -> Result<Option<Certificates>> {
     let client_cert = root["tls-client-certificate"].as_string();
     let client_key = root["tls-client-certificate-key"].as_string();
     let client_pem = root["tls-client-certificate-pem"].as_string();
+    let client_pkcs12 = root["tls-client-certificate-pkcs12"].as_string();
+    let client_pkcs12_password = root["tls-client-certificate-pkcs12-password"]
+        .as_string()
+        .map(SecretString::new);
     let ca_cert = root["tls-ca"].as_string();
 
     if client_cert.as_ref().xor(client_key.as_ref()).is_some() {
@@ -64,11 +110,20 @@ fn parse_global_certificates(root: &Hocon) -> Result<Option<Certificates>> {
             error!("Invalid TLS configuration. 'tls-client-certificate-key' without 'tls-client-certificate'");
         }
         return Err(InquestError::ConfigurationError);
-    } else if client_cert.as_ref().and(client_key.as_ref()).is_some() {
+    } else if client_pkcs12_password.is_some() && client_pkcs12.is_none() {
+        error!("Invalid TLS configuration. 'tls-client-certificate-pkcs12-password' without 'tls-client-certificate-pkcs12'");
+        Err(InquestError::ConfigurationError)
+    } else if client_cert.is_some()
+        || client_pem.is_some()
+        || client_pkcs12.is_some()
+        || ca_cert.is_some()
+    {
         Ok(Some(Certificates::new(
             client_cert,
             client_key,
             client_pem,
+            client_pkcs12,
+            client_pkcs12_password,
             ca_cert,
         )))
     } else {
@@ -80,15 +135,32 @@ fn parse_service(
     service: &String,
     hocon: &Hocon,
     certs: Option<Certificates>,
+    proxy: Option<Proxy>,
 ) -> Result<ServiceSpecification> {
     let probe_configs = match &hocon {
         Hocon::Hash(s) => s
             .iter()
             .map(|(k, v)| match k.as_str() {
-                "http" => parse_http(v, certs.clone()),
+                "http" => parse_http(v, certs.clone(), proxy.clone()),
+                "http-scenario" => parse_http_scenario(v, certs.clone(), proxy.clone()),
+                "health" => parse_health(v, certs.clone(), proxy.clone()),
                 "postgres" => parse_postgres(v, certs.clone()),
                 "oracle" => parse_oracle(v),
                 "mssql" => parse_mssql(v, certs.clone()),
+                "mysql" => parse_mysql(v, certs.clone()),
+                "redis" => parse_redis(v, certs.clone()),
+                "mongodb" => parse_mongodb(v, certs.clone()),
+                "kafka" => parse_kafka(v, certs.clone()),
+                "amqp" => parse_amqp(v, certs.clone()),
+                "smtp" => parse_smtp(v, certs.clone()),
+                "ldap" => parse_ldap(v, certs.clone()),
+                "ssh" => parse_ssh(v),
+                "ftp" => parse_ftp(v, certs.clone()),
+                "ntp" => parse_ntp(v),
+                "tcp" => parse_tcp(v),
+                "udp" => parse_udp(v),
+                "dns" => parse_dns(v),
+                "tls" => parse_tls(v, certs.clone()),
                 other => {
                     warn!("Unrecognized Probe '{}' in '{}'", other, service);
                     Err(InquestError::ConfigurationError)
@@ -109,6 +181,20 @@ fn parse_service(
     })
 }
 
+/// Parses an optional `timeout` in the HOCON duration format, e.g. `5s` or `500ms`
+fn parse_timeout(hocon: &Hocon) -> Result<Option<Duration>> {
+    match &hocon["timeout"] {
+        Hocon::BadValue(_) => Ok(None),
+        value => match value.as_duration() {
+            Some(timeout) if !timeout.is_zero() => Ok(Some(timeout)),
+            _ => {
+                error!("Invalid timeout '{:?}'", value);
+                Err(InquestError::ConfigurationError)
+            }
+        },
+    }
+}
+
 fn parse_sql(hocon: &Hocon) -> Result<Option<SqlTest>> {
     if let Hocon::BadValue(_) = hocon["sql"] {
         return Ok(None);
@@ -120,10 +206,82 @@ fn parse_sql(hocon: &Hocon) -> Result<Option<SqlTest>> {
     }
 }
 
+/// Parses the assertions on the server certificate, e.g. `certificate.min-days-valid = 21`.
+fn parse_certificate_test(hocon: &Hocon) -> Result<Option<CertificateTest>> {
+    if let Hocon::BadValue(_) = hocon {
+        return Ok(None);
+    };
+
+    let min_days_valid = match &hocon["min-days-valid"] {
+        Hocon::BadValue(_) => None,
+        value => Some(
+            value
+                .as_i64()
+                .and_then(|days| u32::try_from(days).ok())
+                .ok_or(InquestError::ConfigurationError)?,
+        ),
+    };
+    Ok(Some(CertificateTest { min_days_valid }))
+}
+
+/// Parses the expectations on the certificate chain, e.g.
+/// `tls { pin-sha256 = ["..."], expected-issuer = "CN=My CA" }`.
+fn parse_tls_test(hocon: &Hocon) -> Result<Option<TlsTest>> {
+    if let Hocon::BadValue(_) = hocon["tls"] {
+        return Ok(None);
+    };
+    parse_chain_expectations(&hocon["tls"]).map(Some)
+}
+
+/// Parses the switch of probes which optionally speak TLS, e.g. `tls = true`. A block with the
+/// expectations on the certificate chain like for `parse_tls_test` switches TLS on as well.
+fn parse_tls_switch(hocon: &Hocon, key: &str) -> Result<(bool, Option<TlsTest>)> {
+    match &hocon[key] {
+        Hocon::BadValue(_) => Ok((false, None)),
+        Hocon::Hash(_) => Ok((true, Some(parse_chain_expectations(&hocon[key])?))),
+        value => match value.as_bool() {
+            Some(tls) => Ok((tls, None)),
+            None => {
+                error!(
+                    "Invalid '{}' '{:?}', expected a boolean or a block with 'pin-sha256' or \
+                    'expected-issuer'",
+                    key, value
+                );
+                Err(InquestError::ConfigurationError)
+            }
+        },
+    }
+}
+
+fn parse_chain_expectations(hocon: &Hocon) -> Result<TlsTest> {
+    let pin_sha256 = match &hocon["pin-sha256"] {
+        Hocon::Array(pins) => pins
+            .iter()
+            .map(|pin| pin.as_string().ok_or(InquestError::ConfigurationError))
+            .collect::<Result<Vec<String>>>()?,
+        Hocon::String(pin) => vec![pin.to_string()],
+        Hocon::BadValue(_) => Vec::new(),
+        _ => return Err(InquestError::ConfigurationError),
+    };
+    let expected_issuer = hocon["expected-issuer"].as_string();
+    if pin_sha256.is_empty() && expected_issuer.is_none() {
+        error!("Invalid TLS configuration. Either 'pin-sha256' or 'expected-issuer' is needed");
+        return Err(InquestError::ConfigurationError);
+    }
+
+    Ok(TlsTest {
+        pin_sha256,
+        expected_issuer,
+    })
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::input::parser::parse;
-    use crate::{Config, ServiceSpecification};
+    use secrecy::ExposeSecret;
+
+    use crate::error::InquestError;
+    use crate::input::parser::{parse, parse_global_certificates, parse_tls_switch};
+    use crate::{Certificates, Config, ServiceSpecification, TlsTest};
 
     pub fn setup(content: &str) -> Vec<ServiceSpecification> {
         let root = hocon::HoconLoader::new()
@@ -152,4 +310,62 @@ mod tests {
             panic!("basic parsing failed")
         }
     }
+
+    #[test]
+    fn parse_global_certificates_without_client_certificate_pair() {
+        let root = hocon::HoconLoader::new()
+            .load_str(
+                r#"
+                tls-ca = "ca.crt"
+                tls-client-certificate-pkcs12 = "client.p12"
+                tls-client-certificate-pkcs12-password = "changeit"
+                "#,
+            )
+            .unwrap()
+            .hocon()
+            .unwrap();
+
+        assert_matches!(
+            parse_global_certificates(&root),
+            Ok(Some(Certificates {
+                client_cert: None,
+                client_pkcs12: Some(pkcs12),
+                client_pkcs12_password: Some(password),
+                ca_cert: Some(ca),
+                ..
+            })) => {
+                assert_eq!("client.p12", pkcs12);
+                assert_eq!("changeit", password.expose_secret());
+                assert_eq!("ca.crt", ca);
+            }
+        );
+    }
+
+    #[test]
+    fn tls_switch_is_a_boolean_or_a_block() {
+        let switch = |content: &str| {
+            let root = hocon::HoconLoader::new()
+                .load_str(content)
+                .unwrap()
+                .hocon()
+                .unwrap();
+            parse_tls_switch(&root, "tls")
+        };
+
+        assert_matches!(switch("port = 6379"), Ok((false, None)));
+        assert_matches!(switch("tls = true"), Ok((true, None)));
+        assert_matches!(
+            switch(r#"tls { expected-issuer = "CN=My CA" }"#),
+            Ok((true, Some(TlsTest { expected_issuer: Some(issuer), .. }))) if issuer == "CN=My CA"
+        );
+        assert_matches!(
+            switch("tls = [true]"),
+            Err(InquestError::ConfigurationError)
+        );
+        // a block without expectations is no switch either
+        assert_matches!(
+            switch("tls { enabled = true }"),
+            Err(InquestError::ConfigurationError)
+        );
+    }
 }
@@ -0,0 +1,174 @@
+use hocon::Hocon;
+use log::error;
+use mongodb::bson::Document;
+use secrecy::SecretString;
+
+use crate::error::InquestError;
+use crate::input::parser::{parse_timeout, parse_tls_switch};
+use crate::{Certificates, Config, MongoDb, MongoFind};
+use crate::{Result, GO};
+
+/// Only the first documents are shown when no limit is configured
+const DEFAULT_LIMIT: i64 = 10;
+
+pub(crate) fn parse_mongodb(hocon: &Hocon, certs: Option<Certificates>) -> Result<Vec<Config>> {
+    if let Hocon::Array(mongodbs) = &hocon {
+        Ok(mongodbs
+            .iter()
+            .flat_map(|mongodb| parse(mongodb, certs.clone()))
+            .collect())
+    } else {
+        Err(InquestError::ConfigurationError)
+    }
+}
+
+fn parse(hocon: &Hocon, certs: Option<Certificates>) -> Result<Config> {
+    let uri = match (hocon["uri"].as_string(), &hocon["hosts"]) {
+        (Some(uri), Hocon::BadValue(_)) => uri,
+        (None, Hocon::Array(hosts)) => {
+            let hosts = hosts
+                .iter()
+                .map(|host| host.as_string().ok_or(InquestError::ConfigurationError))
+                .collect::<Result<Vec<_>>>()?;
+            match hocon["replica-set"].as_string() {
+                Some(replica_set) => {
+                    format!("mongodb://{}/?replicaSet={}", hosts.join(","), replica_set)
+                }
+                None => format!("mongodb://{}", hosts.join(",")),
+            }
+        }
+        _ => {
+            error!("Invalid MongoDB configuration. Either 'uri' or 'hosts' is required");
+            return Err(InquestError::ConfigurationError);
+        }
+    };
+    let mongodb = MongoDb::new(uri, certs, &GO);
+    let timeout = parse_timeout(hocon)?.unwrap_or(mongodb.timeout);
+    // the driver verifies the chain on its own, so there is nothing to pin against
+    let tls = match parse_tls_switch(hocon, "tls")? {
+        (tls, None) => tls,
+        (_, Some(_)) => {
+            error!("Invalid MongoDB configuration. 'tls' is a boolean, pinning is not supported");
+            return Err(InquestError::ConfigurationError);
+        }
+    };
+    let find = match &hocon["find"] {
+        Hocon::BadValue(_) => None,
+        find => Some(parse_find(find)?),
+    };
+
+    Ok(MongoDb {
+        user: hocon["user"].as_string(),
+        password: hocon["password"].as_string().map(SecretString::new),
+        auth_source: hocon["auth-source"].as_string(),
+        tls,
+        timeout,
+        find,
+        ..mongodb
+    }
+    .into())
+}
+
+/// The filter is given as JSON string, since HOCON would not keep the order of the keys
+fn parse_find(hocon: &Hocon) -> Result<MongoFind> {
+    let database = hocon["database"]
+        .as_string()
+        .ok_or(InquestError::ConfigurationError)?;
+    let collection = hocon["collection"]
+        .as_string()
+        .ok_or(InquestError::ConfigurationError)?;
+    let filter = match hocon["filter"].as_string() {
+        Some(filter) => serde_json::from_str::<Document>(&filter).map_err(|e| {
+            error!("Invalid MongoDB filter '{}': {}", filter, e);
+            InquestError::ConfigurationError
+        })?,
+        None => Document::new(),
+    };
+    Ok(MongoFind {
+        database,
+        collection,
+        filter,
+        limit: hocon["limit"].as_i64().unwrap_or(DEFAULT_LIMIT),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use mongodb::bson::doc;
+    use secrecy::ExposeSecret;
+
+    use crate::error::InquestError;
+    use crate::input::parser::mongodb::parse;
+    use crate::input::parser::tests::match_content;
+    use crate::{Config, MongoDb, MongoFind};
+
+    #[test]
+    fn parse_mongodb() {
+        let content = r#"
+            probe-specification {
+                my-service {
+                    mongodb = [{
+                        hosts = ["db1.corp:27017", "db2.corp:27017"]
+                        replica-set = "rs0"
+                        user = "probe"
+                        password = "hX8AgBVOd/GvecheybpEPA==" # 'changeit'
+                        auth-source = "orders"
+                        tls = true
+                        timeout = 5s
+                        find {
+                            database = "orders"
+                            collection = "outbox"
+                            filter = """{"status": "pending", "retries": {"$gt": 3}}"""
+                            limit = 5
+                        }
+                    }]
+                }
+            }"#;
+        match_content(content, |config| match config {
+            Config::MongoDb(MongoDb {
+                uri,
+                user,
+                password,
+                auth_source,
+                tls,
+                timeout,
+                find: Some(find),
+                ..
+            }) => {
+                assert_eq!(
+                    "mongodb://db1.corp:27017,db2.corp:27017/?replicaSet=rs0",
+                    uri
+                );
+                assert_eq!(Some("probe".to_string()), *user);
+                assert_eq!(
+                    "hX8AgBVOd/GvecheybpEPA==",
+                    password.as_ref().unwrap().expose_secret()
+                );
+                assert_eq!(Some("orders".to_string()), *auth_source);
+                assert!(*tls);
+                assert_eq!(Duration::from_secs(5), *timeout);
+                assert_matches!(find, MongoFind { database, collection, filter, limit: 5 } => {
+                    assert_eq!("orders", database);
+                    assert_eq!("outbox", collection);
+                    assert_eq!(
+                        &doc! { "status": "pending", "retries": { "$gt": 3 } },
+                        filter
+                    );
+                });
+            }
+            _ => panic!("did not match MongoDB probe"),
+        });
+    }
+
+    #[test]
+    fn tls_block_is_rejected() {
+        let hocon = hocon::HoconLoader::new()
+            .load_str(r#"hosts = ["db1.corp"], tls { pin-sha256 = "Bgc+1/BgEXozqmfTBGHIdHB+nLhoWxJFa8BAkxv3cxA=" }"#)
+            .unwrap()
+            .hocon()
+            .unwrap();
+        assert_matches!(parse(&hocon, None), Err(InquestError::ConfigurationError));
+    }
+}
@@ -0,0 +1,89 @@
+use hocon::Hocon;
+use secrecy::SecretString;
+
+use crate::error::InquestError;
+use crate::input::parser::parse_sql;
+use crate::{Certificates, Config, MySql};
+use crate::{Result, GO};
+
+pub(crate) fn parse_mysql(hocon: &Hocon, certs: Option<Certificates>) -> Result<Vec<Config>> {
+    if let Hocon::Array(mysqls) = &hocon {
+        Ok(mysqls
+            .iter()
+            .flat_map(|mysql| parse(mysql, certs.clone()))
+            .collect())
+    } else {
+        Err(InquestError::ConfigurationError)
+    }
+}
+
+fn parse(hocon: &Hocon, certs: Option<Certificates>) -> Result<Config> {
+    let host = hocon["host"].as_string();
+    let port = hocon["port"]
+        .as_i64()
+        .map(|port| u16::try_from(port).map_err(|_| InquestError::ConfigurationError))
+        .transpose()?;
+    let database = hocon["database"].as_string();
+    let user = hocon["user"]
+        .as_string()
+        .ok_or(InquestError::ConfigurationError)?;
+    let password = SecretString::new(
+        hocon["password"]
+            .as_string()
+            .ok_or(InquestError::ConfigurationError)?,
+    );
+    let sql = parse_sql(hocon)?;
+    Ok(MySql::new(host, port, database, user, password, sql, &GO, certs).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use secrecy::ExposeSecret;
+
+    use crate::input::parser::tests::match_content;
+    use crate::{Config, MySql};
+
+    #[test]
+    fn parse_mysql() {
+        let content = r#"
+            probe-specification {
+                tls-ca = "certs/customCA.crt"
+                my-service {
+                    mysql = [{
+                        host = "mariadb.corp"
+                        port = 3307 # can be omitted when 3306
+                        user = "admin"
+                        password = "hX8AgBVOd/GvecheybpEPA==" # 'changeit'
+                        database = "test"
+                        sql {
+                            query = "SELECT * FROM testdata;"
+                        }
+                    }]
+                }
+            }"#;
+        match_content(content, |config| match config {
+            Config::MySql(MySql {
+                host,
+                port,
+                database,
+                user,
+                password,
+                sql,
+                certs,
+                ..
+            }) => {
+                assert_eq!("mariadb.corp", host);
+                assert_eq!(3307, *port);
+                assert_eq!(Some("test".to_string()), *database);
+                assert_eq!("admin", user);
+                assert_eq!("hX8AgBVOd/GvecheybpEPA==", password.expose_secret());
+                assert_eq!(
+                    "SELECT * FROM testdata;",
+                    sql.as_ref().unwrap().query.as_str()
+                );
+                assert!(certs.is_some());
+            }
+            _ => panic!("did not match MySQL probe"),
+        });
+    }
+}
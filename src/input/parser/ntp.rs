@@ -0,0 +1,101 @@
+use hocon::Hocon;
+use log::error;
+
+use crate::error::InquestError;
+use crate::input::parser::parse_timeout;
+use crate::{Config, Ntp};
+use crate::{Result, GO};
+
+const DEFAULT_PORT: u16 = 123;
+
+pub(crate) fn parse_ntp(hocon: &Hocon) -> Result<Vec<Config>> {
+    if let Hocon::Array(ntps) = &hocon {
+        Ok(ntps.iter().flat_map(parse).collect())
+    } else {
+        Err(InquestError::ConfigurationError)
+    }
+}
+
+fn parse(hocon: &Hocon) -> Result<Config> {
+    let servers = match &hocon["servers"] {
+        Hocon::Array(servers) if !servers.is_empty() => servers
+            .iter()
+            .map(|server| server.as_string().ok_or(InquestError::ConfigurationError))
+            .map(|server| server.and_then(|server| parse_server(&server)))
+            .collect::<Result<Vec<_>>>()?,
+        _ => {
+            error!("Invalid NTP configuration. 'servers' must list at least one host");
+            return Err(InquestError::ConfigurationError);
+        }
+    };
+    let ntp = Ntp::new(servers, &GO);
+    let max_offset = match &hocon["max-offset"] {
+        Hocon::BadValue(_) => ntp.max_offset,
+        value => value.as_duration().ok_or_else(|| {
+            error!("Invalid NTP 'max-offset' '{:?}'", value);
+            InquestError::ConfigurationError
+        })?,
+    };
+    let timeout = parse_timeout(hocon)?.unwrap_or(ntp.timeout);
+
+    Ok(Ntp {
+        max_offset,
+        timeout,
+        ..ntp
+    }
+    .into())
+}
+
+/// Either `host` or `host:port`, IPv6 addresses need brackets with a port
+fn parse_server(server: &str) -> Result<(String, u16)> {
+    match server.rsplit_once(':') {
+        Some((host, port)) if !host.contains(':') || host.ends_with(']') => {
+            let port = port.parse().map_err(|_| {
+                error!("Invalid port in NTP server '{}'", server);
+                InquestError::ConfigurationError
+            })?;
+            Ok((host.trim_matches(['[', ']']).to_string(), port))
+        }
+        _ => Ok((server.trim_matches(['[', ']']).to_string(), DEFAULT_PORT)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::input::parser::tests::match_content;
+    use crate::{Config, Ntp};
+
+    #[test]
+    fn parse_ntp() {
+        let content = r#"
+            probe-specification {
+                my-service {
+                    ntp = [{
+                        servers = ["ntp1.corp", "ntp2.corp:1123", "[fd00::1]:123", "fd00::2"]
+                        max-offset = 250ms
+                    }]
+                }
+            }"#;
+        match_content(content, |config| match config {
+            Config::Ntp(Ntp {
+                servers,
+                max_offset,
+                ..
+            }) => {
+                assert_eq!(
+                    vec![
+                        ("ntp1.corp".to_string(), 123),
+                        ("ntp2.corp".to_string(), 1123),
+                        ("fd00::1".to_string(), 123),
+                        ("fd00::2".to_string(), 123)
+                    ],
+                    *servers
+                );
+                assert_eq!(Duration::from_millis(250), *max_offset);
+            }
+            _ => panic!("did not match NTP probe"),
+        });
+    }
+}
@@ -2,7 +2,7 @@ use hocon::Hocon;
 use secrecy::SecretString;
 
 use crate::error::InquestError;
-use crate::input::parser::parse_sql;
+use crate::input::parser::{parse_sql, parse_tls_test};
 use crate::{Certificates, Config, Postgres};
 use crate::{Result, GO};
 
@@ -32,7 +32,12 @@ fn parse(hocon: &Hocon, certs: Option<Certificates>) -> Result<Config> {
             .ok_or(InquestError::ConfigurationError)?,
     );
     let sql = parse_sql(&hocon)?;
-    Ok(Postgres::new(host, port, database, user, password, sql, &GO, certs).into())
+    let tls = parse_tls_test(hocon)?;
+    Ok(Postgres {
+        tls,
+        ..Postgres::new(host, port, database, user, password, sql, &GO, certs)
+    }
+    .into())
 }
 
 #[cfg(test)]
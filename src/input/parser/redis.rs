@@ -0,0 +1,156 @@
+use hocon::Hocon;
+use log::error;
+use regex::Regex;
+use secrecy::SecretString;
+
+use crate::error::InquestError;
+use crate::input::parser::{parse_timeout, parse_tls_switch};
+use crate::{Certificates, Config, Redis, RedisCommand};
+use crate::{Result, GO};
+
+pub(crate) fn parse_redis(hocon: &Hocon, certs: Option<Certificates>) -> Result<Vec<Config>> {
+    if let Hocon::Array(redises) = &hocon {
+        Ok(redises
+            .iter()
+            .flat_map(|redis| parse(redis, certs.clone()))
+            .collect())
+    } else {
+        Err(InquestError::ConfigurationError)
+    }
+}
+
+fn parse(hocon: &Hocon, certs: Option<Certificates>) -> Result<Config> {
+    let host = hocon["host"]
+        .as_string()
+        .ok_or(InquestError::ConfigurationError)?;
+    let port = hocon["port"]
+        .as_i64()
+        .map(|port| u16::try_from(port).map_err(|_| InquestError::ConfigurationError))
+        .transpose()?;
+    let redis = Redis::new(host, port, certs, &GO);
+    let (tls, tls_test) = parse_tls_switch(hocon, "tls")?;
+    let timeout = parse_timeout(hocon)?.unwrap_or(redis.timeout);
+    let commands = match &hocon["commands"] {
+        Hocon::Array(commands) => commands
+            .iter()
+            .map(parse_command)
+            .collect::<Result<Vec<_>>>()?,
+        Hocon::BadValue(_) => Vec::new(),
+        _ => return Err(InquestError::ConfigurationError),
+    };
+
+    Ok(Redis {
+        user: hocon["user"].as_string(),
+        password: hocon["password"].as_string().map(SecretString::new),
+        tls,
+        tls_test,
+        timeout,
+        commands,
+        ..redis
+    }
+    .into())
+}
+
+/// A command is either given as string like `"GET key"`, or as object with an expected reply.
+/// The arguments are separated by whitespace, or given as array when they contain whitespace.
+fn parse_command(hocon: &Hocon) -> Result<RedisCommand> {
+    let (command, expected) = match hocon {
+        Hocon::Hash(_) => (&hocon["command"], hocon["expected"].as_string()),
+        _ => (hocon, None),
+    };
+    let arguments = match command {
+        Hocon::String(command) => command.split_whitespace().map(String::from).collect(),
+        Hocon::Array(arguments) => arguments
+            .iter()
+            .map(|argument| argument.as_string().ok_or(InquestError::ConfigurationError))
+            .collect::<Result<Vec<_>>>()?,
+        _ => Vec::new(),
+    };
+    if arguments.is_empty() {
+        error!("Invalid Redis configuration. Every entry of 'commands' needs a command");
+        return Err(InquestError::ConfigurationError);
+    }
+    let expected = match expected {
+        Some(expected) => Some(Regex::new(&expected).map_err(|e| {
+            error!("Invalid expected pattern '{}': {}", expected, e);
+            InquestError::ConfigurationError
+        })?),
+        None => None,
+    };
+    Ok(RedisCommand {
+        arguments,
+        expected,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use secrecy::ExposeSecret;
+
+    use crate::input::parser::tests::match_content;
+    use crate::{Config, Redis};
+
+    #[test]
+    fn parse_redis() {
+        let content = r#"
+            probe-specification {
+                tls-ca = "certs/customCA.crt"
+                my-service {
+                    redis = [{
+                        host = "cache.corp"
+                        port = 6380
+                        tls {
+                            pin-sha256 = "Bgc+1/BgEXozqmfTBGHIdHB+nLhoWxJFa8BAkxv3cxA="
+                        }
+                        user = "probe"
+                        password = "hX8AgBVOd/GvecheybpEPA==" # 'changeit'
+                        timeout = 2s
+                        commands = [
+                            "GET feature-flags",
+                            { command = "INFO replication", expected = "connected_slaves:[1-9]" },
+                            { command = ["SET", "probe", "a value"] }
+                        ]
+                    }]
+                }
+            }"#;
+        match_content(content, |config| match config {
+            Config::Redis(Redis {
+                host,
+                port,
+                user,
+                password,
+                tls,
+                tls_test,
+                timeout,
+                certs,
+                commands,
+            }) => {
+                assert_eq!("cache.corp", host);
+                assert_eq!(6380, *port);
+                assert_eq!(Some("probe".to_string()), *user);
+                assert_eq!(
+                    "hX8AgBVOd/GvecheybpEPA==",
+                    password.as_ref().unwrap().expose_secret()
+                );
+                assert!(*tls);
+                assert_eq!(
+                    vec!["Bgc+1/BgEXozqmfTBGHIdHB+nLhoWxJFa8BAkxv3cxA=".to_string()],
+                    tls_test.as_ref().unwrap().pin_sha256
+                );
+                assert_eq!(Duration::from_secs(2), *timeout);
+                assert!(certs.is_some());
+                assert_eq!(vec!["GET", "feature-flags"], commands[0].arguments);
+                assert!(commands[0].expected.is_none());
+                assert!(commands[1]
+                    .expected
+                    .as_ref()
+                    .unwrap()
+                    .is_match("connected_slaves:2"));
+                assert_eq!(vec!["SET", "probe", "a value"], commands[2].arguments);
+            }
+            _ => panic!("did not match Redis probe"),
+        });
+    }
+}
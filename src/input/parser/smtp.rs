@@ -0,0 +1,131 @@
+use hocon::Hocon;
+use log::error;
+use secrecy::SecretString;
+
+use crate::error::InquestError;
+use crate::input::parser::{parse_timeout, parse_tls_switch};
+use crate::{Certificates, Config, Smtp, SmtpMail};
+use crate::{Result, GO};
+
+pub(crate) fn parse_smtp(hocon: &Hocon, certs: Option<Certificates>) -> Result<Vec<Config>> {
+    if let Hocon::Array(smtps) = &hocon {
+        Ok(smtps
+            .iter()
+            .flat_map(|smtp| parse(smtp, certs.clone()))
+            .collect())
+    } else {
+        Err(InquestError::ConfigurationError)
+    }
+}
+
+fn parse(hocon: &Hocon, certs: Option<Certificates>) -> Result<Config> {
+    let host = hocon["host"]
+        .as_string()
+        .ok_or(InquestError::ConfigurationError)?;
+    let smtp = Smtp::new(host, certs, &GO);
+    let (tls, tls_test) = parse_tls_switch(hocon, "tls")?;
+    let (starttls, starttls_test) = match &hocon["starttls"] {
+        Hocon::BadValue(_) => (smtp.starttls, None),
+        _ => parse_tls_switch(hocon, "starttls")?,
+    };
+    let port = match hocon["port"].as_i64() {
+        Some(port) => u16::try_from(port).map_err(|_| InquestError::ConfigurationError)?,
+        None if tls => 465,
+        None => smtp.port,
+    };
+    let user = hocon["user"].as_string();
+    let password = hocon["password"].as_string().map(SecretString::new);
+    if user.is_some() != password.is_some() {
+        error!("Invalid SMTP configuration. 'user' and 'password' are required together");
+        return Err(InquestError::ConfigurationError);
+    }
+    let timeout = parse_timeout(hocon)?.unwrap_or(smtp.timeout);
+    let mail = match &hocon["mail"] {
+        Hocon::BadValue(_) => None,
+        mail => Some(SmtpMail {
+            from: mail["from"]
+                .as_string()
+                .ok_or(InquestError::ConfigurationError)?,
+            to: mail["to"]
+                .as_string()
+                .ok_or(InquestError::ConfigurationError)?,
+        }),
+    };
+
+    Ok(Smtp {
+        port,
+        tls,
+        // with implicit TLS there is nothing to upgrade
+        starttls: !tls && starttls,
+        tls_test: match tls {
+            true => tls_test,
+            false => starttls_test,
+        },
+        user,
+        password,
+        plaintext_auth: hocon["plaintext-auth"].as_bool().unwrap_or(false),
+        timeout,
+        mail,
+        ..smtp
+    }
+    .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use secrecy::ExposeSecret;
+
+    use crate::input::parser::tests::match_content;
+    use crate::{Config, Smtp, SmtpMail, TlsTest};
+
+    #[test]
+    fn parse_smtp() {
+        let content = r#"
+            probe-specification {
+                my-service {
+                    smtp = [{
+                        host = "mail.corp"
+                        port = 587
+                        starttls {
+                            expected-issuer = "CN=Corp CA"
+                        }
+                        user = "probe"
+                        password = "hX8AgBVOd/GvecheybpEPA==" # 'changeit'
+                        mail {
+                            from = "inquest@corp"
+                            to = "postmaster@corp"
+                        }
+                    }]
+                }
+            }"#;
+        match_content(content, |config| match config {
+            Config::Smtp(Smtp {
+                host,
+                port,
+                tls,
+                starttls,
+                tls_test: Some(TlsTest {
+                    expected_issuer, ..
+                }),
+                user,
+                password,
+                mail: Some(SmtpMail { from, to }),
+                ..
+            }) => {
+                assert_eq!("mail.corp", host);
+                assert_eq!(587, *port);
+                assert!(!*tls);
+                assert!(*starttls);
+                assert_eq!(Some("CN=Corp CA".to_string()), *expected_issuer);
+                assert_eq!(Some("probe".to_string()), *user);
+                assert_eq!(
+                    "hX8AgBVOd/GvecheybpEPA==",
+                    password.as_ref().unwrap().expose_secret()
+                );
+                assert_eq!("inquest@corp", from);
+                assert_eq!("postmaster@corp", to);
+            }
+            _ => panic!("did not match SMTP probe"),
+        });
+    }
+}
@@ -0,0 +1,101 @@
+use hocon::Hocon;
+use log::error;
+use secrecy::SecretString;
+
+use crate::error::InquestError;
+use crate::input::parser::parse_timeout;
+use crate::{Config, Ssh};
+use crate::{Result, GO};
+
+pub(crate) fn parse_ssh(hocon: &Hocon) -> Result<Vec<Config>> {
+    if let Hocon::Array(sshs) = &hocon {
+        Ok(sshs.iter().flat_map(parse).collect())
+    } else {
+        Err(InquestError::ConfigurationError)
+    }
+}
+
+fn parse(hocon: &Hocon) -> Result<Config> {
+    let host = hocon["host"]
+        .as_string()
+        .ok_or(InquestError::ConfigurationError)?;
+    let ssh = Ssh::new(host, &GO);
+    let port = hocon["port"]
+        .as_i64()
+        .map(|port| u16::try_from(port).map_err(|_| InquestError::ConfigurationError))
+        .transpose()?
+        .unwrap_or(ssh.port);
+    let user = hocon["user"].as_string();
+    let password = hocon["password"].as_string().map(SecretString::new);
+    let private_key = hocon["private-key"].as_string();
+    if user.is_none() && (password.is_some() || private_key.is_some()) {
+        error!("Invalid SSH configuration. 'password' and 'private-key' require a 'user'");
+        return Err(InquestError::ConfigurationError);
+    }
+    // copied from `ssh-keygen -l`, with or without the prefix
+    let fingerprint = hocon["fingerprint"].as_string().map(|fingerprint| {
+        match fingerprint.starts_with("SHA256:") {
+            true => fingerprint,
+            false => format!("SHA256:{}", fingerprint),
+        }
+    });
+    let timeout = parse_timeout(hocon)?.unwrap_or(ssh.timeout);
+
+    Ok(Ssh {
+        port,
+        fingerprint,
+        user,
+        password,
+        private_key,
+        command: hocon["command"].as_string().unwrap_or(ssh.command.clone()),
+        timeout,
+        ..ssh
+    }
+    .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::input::parser::tests::match_content;
+    use crate::{Config, Ssh};
+
+    #[test]
+    fn parse_ssh() {
+        let content = r#"
+            probe-specification {
+                my-service {
+                    ssh = [{
+                        host = "bastion.corp"
+                        port = 2222
+                        fingerprint = "uNiVztksCsDhcc0u9e8BujQXVUpKZIDTMczCvj3tD2s"
+                        user = "probe"
+                        private-key = "/etc/inquest/id_ed25519"
+                    }]
+                }
+            }"#;
+        match_content(content, |config| match config {
+            Config::Ssh(Ssh {
+                host,
+                port,
+                fingerprint,
+                user,
+                password,
+                private_key,
+                command,
+                ..
+            }) => {
+                assert_eq!("bastion.corp", host);
+                assert_eq!(2222, *port);
+                assert_eq!(
+                    Some("SHA256:uNiVztksCsDhcc0u9e8BujQXVUpKZIDTMczCvj3tD2s".to_string()),
+                    *fingerprint
+                );
+                assert_eq!(Some("probe".to_string()), *user);
+                assert!(password.is_none());
+                assert_eq!(Some("/etc/inquest/id_ed25519".to_string()), *private_key);
+                assert_eq!("true", command);
+            }
+            _ => panic!("did not match SSH probe"),
+        });
+    }
+}
@@ -0,0 +1,84 @@
+use hocon::Hocon;
+use log::error;
+use regex::Regex;
+
+use crate::error::InquestError;
+use crate::input::parser::parse_timeout;
+use crate::{Config, Tcp};
+use crate::{Result, GO};
+
+pub(crate) fn parse_tcp(hocon: &Hocon) -> Result<Vec<Config>> {
+    if let Hocon::Array(tcps) = &hocon {
+        Ok(tcps.iter().flat_map(parse).collect())
+    } else {
+        Err(InquestError::ConfigurationError)
+    }
+}
+
+fn parse(hocon: &Hocon) -> Result<Config> {
+    let host = hocon["host"]
+        .as_string()
+        .ok_or(InquestError::ConfigurationError)?;
+    let port = hocon["port"]
+        .as_i64()
+        .and_then(|port| u16::try_from(port).ok())
+        .ok_or(InquestError::ConfigurationError)?;
+    let tcp = Tcp::new(host, port, &GO);
+    let timeout = parse_timeout(hocon)?.unwrap_or(tcp.timeout);
+    let payload = hocon["payload"].as_string();
+    let banner = match hocon["banner"].as_string() {
+        Some(banner) => Some(Regex::new(&banner).map_err(|e| {
+            error!("Invalid banner '{}': {}", banner, e);
+            InquestError::ConfigurationError
+        })?),
+        None => None,
+    };
+
+    Ok(Tcp {
+        timeout,
+        payload,
+        banner,
+        ..tcp
+    }
+    .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::input::parser::tests::match_content;
+    use crate::{Config, Tcp};
+
+    #[test]
+    fn parse_tcp() {
+        let content = r#"
+            probe-specification {
+                my-service {
+                    tcp = [{
+                        host = "my-database.corp"
+                        port = 5432
+                        timeout = 5s
+                        payload = "PING\r\n"
+                        banner = "^\\+PONG"
+                    }]
+                }
+            }"#;
+        match_content(content, |config| match config {
+            Config::Tcp(Tcp {
+                host,
+                port,
+                timeout,
+                payload,
+                banner,
+            }) => {
+                assert_eq!("my-database.corp", host);
+                assert_eq!(5432, *port);
+                assert_eq!(Duration::from_secs(5), *timeout);
+                assert_eq!(Some("PING\r\n".to_string()), *payload);
+                assert!(banner.as_ref().unwrap().is_match("+PONG"));
+            }
+            _ => panic!("did not match TCP probe"),
+        });
+    }
+}
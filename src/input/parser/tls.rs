@@ -0,0 +1,102 @@
+use hocon::Hocon;
+
+use crate::error::InquestError;
+use crate::input::parser::{parse_certificate_test, parse_timeout, parse_tls_test};
+use crate::{Certificates, Config, Tls};
+use crate::{Result, GO};
+
+pub(crate) fn parse_tls(hocon: &Hocon, certs: Option<Certificates>) -> Result<Vec<Config>> {
+    if let Hocon::Array(handshakes) = &hocon {
+        Ok(handshakes
+            .iter()
+            .flat_map(|handshake| parse(handshake, certs.clone()))
+            .collect())
+    } else {
+        Err(InquestError::ConfigurationError)
+    }
+}
+
+fn parse(hocon: &Hocon, certs: Option<Certificates>) -> Result<Config> {
+    let host = hocon["host"]
+        .as_string()
+        .ok_or(InquestError::ConfigurationError)?;
+    let port = hocon["port"]
+        .as_i64()
+        .and_then(|port| u16::try_from(port).ok())
+        .ok_or(InquestError::ConfigurationError)?;
+    let tls = Tls::new(host, port, certs, &GO);
+    let timeout = parse_timeout(hocon)?.unwrap_or(tls.timeout);
+    let server_name = hocon["server-name"].as_string();
+    let alpn = match &hocon["alpn"] {
+        Hocon::Array(protocols) => protocols
+            .iter()
+            .map(|protocol| protocol.as_string().ok_or(InquestError::ConfigurationError))
+            .collect::<Result<Vec<String>>>()?,
+        Hocon::String(protocol) => vec![protocol.to_string()],
+        Hocon::BadValue(_) => Vec::new(),
+        _ => return Err(InquestError::ConfigurationError),
+    };
+    let certificate = parse_certificate_test(&hocon["certificate"])?;
+
+    Ok(Tls {
+        server_name,
+        alpn,
+        timeout,
+        certificate,
+        tls: parse_tls_test(hocon)?,
+        ..tls
+    }
+    .into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::input::parser::tests::match_content;
+    use crate::{CertificateTest, Config, Tls};
+
+    #[test]
+    fn parse_tls() {
+        let content = r#"
+            probe-specification {
+                tls-ca = "certs/customCA.crt"
+                my-service {
+                    tls = [{
+                        host = "10.0.0.5"
+                        port = 636
+                        server-name = "ldap.corp"
+                        alpn = ["imap"]
+                        timeout = 5s
+                        certificate.min-days-valid = 21
+                        tls.expected-issuer = "CN=Corp CA"
+                    }]
+                }
+            }"#;
+        match_content(content, |config| match config {
+            Config::Tls(Tls {
+                host,
+                port,
+                server_name,
+                alpn,
+                timeout,
+                certs,
+                certificate: Some(CertificateTest { min_days_valid }),
+                tls: Some(tls),
+            }) => {
+                assert_eq!("10.0.0.5", host);
+                assert_eq!(636, *port);
+                assert_eq!(Some("ldap.corp".to_string()), *server_name);
+                assert_eq!(vec!["imap".to_string()], *alpn);
+                assert_eq!(Duration::from_secs(5), *timeout);
+                assert_eq!(
+                    Some("certs/customCA.crt".to_string()),
+                    certs.as_ref().and_then(|certs| certs.ca_cert.clone())
+                );
+                assert_eq!(Some(21), *min_days_valid);
+                assert_eq!(Some("CN=Corp CA".to_string()), tls.expected_issuer);
+            }
+            _ => panic!("did not match TLS probe"),
+        });
+    }
+}
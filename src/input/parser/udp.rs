@@ -0,0 +1,111 @@
+use hocon::Hocon;
+use log::error;
+use regex::Regex;
+
+use crate::error::InquestError;
+use crate::input::parser::parse_timeout;
+use crate::{Config, Udp};
+use crate::{Result, GO};
+
+pub(crate) fn parse_udp(hocon: &Hocon) -> Result<Vec<Config>> {
+    if let Hocon::Array(udps) = &hocon {
+        Ok(udps.iter().flat_map(parse).collect())
+    } else {
+        Err(InquestError::ConfigurationError)
+    }
+}
+
+fn parse(hocon: &Hocon) -> Result<Config> {
+    let host = hocon["host"]
+        .as_string()
+        .ok_or(InquestError::ConfigurationError)?;
+    let port = hocon["port"]
+        .as_i64()
+        .and_then(|port| u16::try_from(port).ok())
+        .ok_or(InquestError::ConfigurationError)?;
+    let udp = Udp::new(host, port, &GO);
+    let timeout = parse_timeout(hocon)?.unwrap_or(udp.timeout);
+    let payload = match (
+        hocon["payload"].as_string(),
+        hocon["payload-hex"].as_string(),
+    ) {
+        (Some(_), Some(_)) => {
+            error!("Invalid UDP configuration. Either 'payload' or 'payload-hex' is allowed");
+            return Err(InquestError::ConfigurationError);
+        }
+        (Some(text), None) => text.into_bytes(),
+        (None, Some(hex)) => parse_hex(&hex)?,
+        (None, None) => Vec::new(),
+    };
+    let response = match hocon["response"].as_string() {
+        Some(response) => Some(Regex::new(&response).map_err(|e| {
+            error!("Invalid response pattern '{}': {}", response, e);
+            InquestError::ConfigurationError
+        })?),
+        None => None,
+    };
+
+    Ok(Udp {
+        timeout,
+        payload,
+        response,
+        ..udp
+    }
+    .into())
+}
+
+/// Whitespace between the bytes is allowed, e.g. `ca fe 01`
+fn parse_hex(hex: &str) -> Result<Vec<u8>> {
+    let digits = hex
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>();
+    if digits.len() % 2 != 0 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        error!("Invalid 'payload-hex' '{}'", hex);
+        return Err(InquestError::ConfigurationError);
+    }
+    Ok((0..digits.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(&digits[index..index + 2], 16).unwrap())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::input::parser::tests::match_content;
+    use crate::{Config, Udp};
+
+    #[test]
+    fn parse_udp() {
+        let content = r#"
+            probe-specification {
+                my-service {
+                    udp = [{
+                        host = "statsd.corp"
+                        port = 8125
+                        timeout = 2s
+                        payload-hex = "ca fe 01"
+                        response = "^OK"
+                    }]
+                }
+            }"#;
+        match_content(content, |config| match config {
+            Config::Udp(Udp {
+                host,
+                port,
+                timeout,
+                payload,
+                response,
+            }) => {
+                assert_eq!("statsd.corp", host);
+                assert_eq!(8125, *port);
+                assert_eq!(Duration::from_secs(2), *timeout);
+                assert_eq!(vec![0xca, 0xfe, 0x01], *payload);
+                assert!(response.as_ref().unwrap().is_match("OK"));
+            }
+            _ => panic!("did not match UDP probe"),
+        });
+    }
+}
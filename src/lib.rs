@@ -2,11 +2,17 @@
 #[macro_use]
 extern crate assert_matches;
 
+use std::net::{IpAddr, SocketAddr};
 use std::path::Path;
 use std::result;
 use std::time::Duration;
 
+use mongodb::bson::Document;
+use regex::Regex;
+use reqwest::{Method, Version};
 use secrecy::SecretString;
+use trust_dns_resolver::config::Protocol;
+use trust_dns_resolver::proto::rr::RecordType;
 use url::Url;
 
 use crate::crypto::decrypt_secret;
@@ -39,6 +45,8 @@ pub type Result<T> = result::Result<T, InquestError>;
 pub struct ProbeReport {
     pub probe_identifier: String,
     pub data: Data,
+    /// the response-body of probes which received one, boxed to keep errors small
+    pub body: Option<Box<BodyPreview>>,
 }
 
 impl ProbeReport {
@@ -46,21 +54,60 @@ impl ProbeReport {
         ProbeReport {
             probe_identifier,
             data: Default::default(),
+            body: None,
         }
     }
 }
 
+/// A preview of a response-body with credentials redacted, truncated to the configured size
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BodyPreview {
+    pub content_type: Option<String>,
+    pub format: BodyFormat,
+    pub content: String,
+    /// size of the whole body in bytes
+    pub size: usize,
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum BodyFormat {
+    /// pretty-printed
+    Json,
+    Text,
+    /// for binary content
+    Hex,
+}
+
 /// We define a ADT (sum type) which we can use for iterating the configured probes.
 /// The enum wraps dedicated structs which also implement the Probe trait. By doing so we have a
 /// compile-time-check that each probe has the proper configuration. If we would have used an
 /// enum-struct there is no way to pass Config::Http as a type (it is  only a variant).
 /// The approach taken here combines the best of both worlds.
+// only a handful of configs exist per run, so their size does not matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
 pub(crate) enum Config {
     Http(Http),
+    HttpScenario(HttpScenario),
+    Health(Health),
+    Tcp(Tcp),
+    Udp(Udp),
+    Dns(Dns),
+    Tls(Tls),
     Postgres(Postgres),
+    MySql(MySql),
     Oracle(Oracle),
     MSSql(MSSql),
+    Redis(Redis),
+    MongoDb(MongoDb),
+    Kafka(Kafka),
+    Amqp(Amqp),
+    Smtp(Smtp),
+    Ldap(Ldap),
+    Ssh(Ssh),
+    Ftp(Ftp),
+    Ntp(Ntp),
 }
 
 #[derive(Debug)]
@@ -73,17 +120,155 @@ pub(crate) struct Certificates {
     pub(crate) client_cert: Option<String>,
     pub(crate) client_key: Option<String>,
     pub(crate) client_pem: Option<String>,
+    pub(crate) client_pkcs12: Option<String>,
+    pub(crate) client_pkcs12_password: Option<SecretString>,
     pub(crate) ca_cert: Option<String>,
 }
 
+/// Forward-proxy settings used by HTTP probes. Can be set globally and per probe.
+/// Without an explicit url, the proxy is taken from the environment (HTTPS_PROXY, HTTP_PROXY,
+/// ALL_PROXY) unless `ignore_environment` is set.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Proxy {
+    pub(crate) url: Option<Url>,
+    pub(crate) user: Option<String>,
+    pub(crate) password: Option<SecretString>,
+    pub(crate) no_proxy: Vec<String>,
+    pub(crate) ignore_environment: bool,
+}
+
 /// Configuration options for a HTTP probe
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct Http {
     pub(crate) options: &'static GlobalOptions,
     pub(crate) url: Url,
     pub(crate) status: u16,
     pub(crate) name: Option<String>,
     pub(crate) certs: Option<Certificates>,
+    pub(crate) proxy: Option<Proxy>,
+    pub(crate) certificate: Option<CertificateTest>,
+    pub(crate) tls: Option<TlsTest>,
+    /// connect to this address instead of resolving the host, while keeping Host-header and SNI
+    pub(crate) resolve: Option<IpAddr>,
+    /// source address used for outgoing connections
+    pub(crate) local_address: Option<IpAddr>,
+    pub(crate) method: Method,
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) body: Option<String>,
+    pub(crate) protocol: Option<HttpProtocol>,
+    pub(crate) download: Option<DownloadTest>,
+    pub(crate) http_version: Option<HttpVersion>,
+    /// asserted protocol version of the response
+    pub(crate) expected_version: Option<Version>,
+    /// bytes of the response-body kept in the report, 0 disables it
+    pub(crate) body_preview_size: usize,
+}
+
+/// Forces a HTTP version, otherwise HTTP/2 and HTTP/1.1 are offered via ALPN
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum HttpVersion {
+    Http11,
+    /// offered via ALPN only, so it requires TLS
+    Http2,
+    Http2PriorKnowledge,
+}
+
+/// Protocols on top of HTTP, which report their errors within a response with status 200
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum HttpProtocol {
+    GraphQl,
+    Soap,
+}
+
+/// Configuration options for a sequence of HTTP requests sharing a cookie-jar. Values captured
+/// from a response can be used in the following steps as `{{name}}`.
+#[derive(Debug)]
+pub(crate) struct HttpScenario {
+    pub(crate) name: Option<String>,
+    /// initial values for substitution, like credentials for a login
+    pub(crate) variables: Vec<(String, SecretString)>,
+    pub(crate) steps: Vec<HttpStep>,
+}
+
+/// Configuration options for a HTTP probe evaluating a JSON health document with nested
+/// component statuses, like `/actuator/health` of Spring Boot
+#[derive(Debug)]
+pub(crate) struct Health {
+    pub(crate) http: Http,
+}
+
+/// Configuration options for a TCP connect probe, checking every resolved address
+#[derive(Debug)]
+pub(crate) struct Tcp {
+    pub(crate) host: String,
+    pub(crate) port: u16,
+    pub(crate) timeout: Duration,
+    /// sent after connecting, e.g. to provoke a response from the server
+    pub(crate) payload: Option<String>,
+    /// asserted on the data read back after connecting
+    pub(crate) banner: Option<Regex>,
+}
+
+/// Configuration options for a UDP probe, sending a datagram and optionally awaiting a response
+#[derive(Debug)]
+pub(crate) struct Udp {
+    pub(crate) host: String,
+    pub(crate) port: u16,
+    pub(crate) timeout: Duration,
+    pub(crate) payload: Vec<u8>,
+    /// when given, a response is awaited and asserted
+    pub(crate) response: Option<Regex>,
+}
+
+/// Configuration options for a TLS handshake probe, for services which do not speak HTTP
+#[derive(Debug)]
+pub(crate) struct Tls {
+    pub(crate) host: String,
+    pub(crate) port: u16,
+    /// sent as SNI and verified against the certificate instead of the host
+    pub(crate) server_name: Option<String>,
+    /// protocols offered via ALPN
+    pub(crate) alpn: Vec<String>,
+    pub(crate) timeout: Duration,
+    pub(crate) certs: Option<Certificates>,
+    pub(crate) certificate: Option<CertificateTest>,
+    pub(crate) tls: Option<TlsTest>,
+}
+
+/// Configuration options for a DNS probe, querying the system resolver or a given nameserver
+#[derive(Debug)]
+pub(crate) struct Dns {
+    pub(crate) name: String,
+    pub(crate) record_type: RecordType,
+    /// the nameservers of the system configuration are used when missing
+    pub(crate) nameserver: Option<SocketAddr>,
+    pub(crate) protocol: Protocol,
+    pub(crate) timeout: Duration,
+    /// values which must be part of the answer, like `10.0.0.5` or `10 mail.corp`
+    pub(crate) expected: Vec<String>,
+}
+
+/// A single request within a `HttpScenario`
+#[derive(Debug)]
+pub(crate) struct HttpStep {
+    pub(crate) http: Http,
+    pub(crate) captures: Vec<Capture>,
+}
+
+/// Extracts a value from a response and stores it under the given name
+#[derive(Debug)]
+pub(crate) struct Capture {
+    pub(crate) name: String,
+    pub(crate) source: CaptureSource,
+}
+
+#[derive(Debug)]
+pub(crate) enum CaptureSource {
+    /// JSON-pointer into the response-body, e.g. `/data/token`
+    Json(String),
+    Header(String),
+    /// the first capture-group (or the whole match) within the response-body
+    Regex(Regex),
 }
 
 /// Configuration options for a probe targeting a Postgres database
@@ -97,6 +282,21 @@ pub(crate) struct Postgres {
     pub(crate) password: SecretString,
     pub(crate) sql: Option<SqlTest>,
     pub(crate) certs: Option<Certificates>,
+    pub(crate) tls: Option<TlsTest>,
+}
+
+/// Configuration options for a probe targeting a MySQL or MariaDB database
+#[derive(Debug)]
+pub(crate) struct MySql {
+    pub(crate) options: &'static GlobalOptions,
+    pub(crate) host: String,
+    pub(crate) port: u16,
+    pub(crate) user: String,
+    pub(crate) database: Option<String>,
+    pub(crate) password: SecretString,
+    pub(crate) sql: Option<SqlTest>,
+    /// the connection is encrypted when given
+    pub(crate) certs: Option<Certificates>,
 }
 
 /// Configuration options for a probe targeting a Oracle database
@@ -123,17 +323,247 @@ pub(crate) struct MSSql {
     pub(crate) certs: Option<Certificates>,
 }
 
+/// Configuration options for a Redis probe, which sends `PING` followed by the configured
+/// commands
+#[derive(Debug)]
+pub(crate) struct Redis {
+    pub(crate) host: String,
+    pub(crate) port: u16,
+    /// the ACL user, the password authenticates the `default` user when missing
+    pub(crate) user: Option<String>,
+    pub(crate) password: Option<SecretString>,
+    /// connects with TLS, trusting the CA of the certificates
+    pub(crate) tls: bool,
+    /// expectations on the certificate chain, given with a `tls` block
+    pub(crate) tls_test: Option<TlsTest>,
+    pub(crate) timeout: Duration,
+    pub(crate) certs: Option<Certificates>,
+    pub(crate) commands: Vec<RedisCommand>,
+}
+
+/// Configuration options for a MongoDB probe, which runs `hello` and `ping` against a replica set
+/// or a single server
+#[derive(Debug)]
+pub(crate) struct MongoDb {
+    /// a connection string without credentials, like `mongodb://db1.corp,db2.corp/?replicaSet=rs0`
+    pub(crate) uri: String,
+    pub(crate) user: Option<String>,
+    pub(crate) password: Option<SecretString>,
+    /// the database holding the user, `admin` by default
+    pub(crate) auth_source: Option<String>,
+    /// connects with TLS, presenting the combined PEM of the certificates as client identity
+    pub(crate) tls: bool,
+    pub(crate) timeout: Duration,
+    pub(crate) certs: Option<Certificates>,
+    pub(crate) find: Option<MongoFind>,
+}
+
+/// A query whose documents are shown in the report
+#[derive(Debug)]
+pub(crate) struct MongoFind {
+    pub(crate) database: String,
+    pub(crate) collection: String,
+    pub(crate) filter: Document,
+    pub(crate) limit: i64,
+}
+
+/// Configuration options for a Kafka probe, which fetches the cluster metadata from the bootstrap
+/// servers and connects to every advertised broker
+#[derive(Debug)]
+pub(crate) struct Kafka {
+    /// host and port of the servers, tried in order until one answers
+    pub(crate) bootstrap_servers: Vec<(String, u16)>,
+    /// connects with TLS, trusting the CA of the certificates
+    pub(crate) tls: bool,
+    /// expectations on the certificate chain of every broker, given with a `tls` block
+    pub(crate) tls_test: Option<TlsTest>,
+    pub(crate) sasl: Option<KafkaSasl>,
+    pub(crate) timeout: Duration,
+    pub(crate) certs: Option<Certificates>,
+    pub(crate) topic: Option<KafkaTopic>,
+}
+
+#[derive(Debug)]
+pub(crate) struct KafkaSasl {
+    pub(crate) mechanism: SaslMechanism,
+    pub(crate) user: String,
+    pub(crate) password: SecretString,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum SaslMechanism {
+    Plain,
+    ScramSha256,
+    ScramSha512,
+}
+
+/// A topic which must exist, optionally with the given number of partitions
+#[derive(Debug)]
+pub(crate) struct KafkaTopic {
+    pub(crate) name: String,
+    pub(crate) partitions: Option<usize>,
+}
+
+/// Configuration options for an AMQP 0-9-1 probe like for RabbitMQ, which opens a connection to
+/// the virtual host
+#[derive(Debug)]
+pub(crate) struct Amqp {
+    pub(crate) host: String,
+    pub(crate) port: u16,
+    pub(crate) vhost: String,
+    pub(crate) user: String,
+    pub(crate) password: SecretString,
+    /// connects with TLS, trusting the CA of the certificates
+    pub(crate) tls: bool,
+    /// expectations on the certificate chain, given with a `tls` block
+    pub(crate) tls_test: Option<TlsTest>,
+    pub(crate) timeout: Duration,
+    pub(crate) certs: Option<Certificates>,
+    /// checked passively, the queue is never created
+    pub(crate) queue: Option<String>,
+}
+
+/// Configuration options for an SMTP probe, which walks through the dialog with a mail server up
+/// to an optional test message
+#[derive(Debug)]
+pub(crate) struct Smtp {
+    pub(crate) host: String,
+    pub(crate) port: u16,
+    /// connects with implicit TLS like on port 465, trusting the CA of the certificates
+    pub(crate) tls: bool,
+    /// upgrades the plain connection, fails when the server does not offer STARTTLS
+    pub(crate) starttls: bool,
+    /// expectations on the certificate chain, given with a `tls` or `starttls` block
+    pub(crate) tls_test: Option<TlsTest>,
+    pub(crate) user: Option<String>,
+    pub(crate) password: Option<SecretString>,
+    /// sends the credentials even though the connection is not encrypted
+    pub(crate) plaintext_auth: bool,
+    pub(crate) timeout: Duration,
+    pub(crate) certs: Option<Certificates>,
+    pub(crate) mail: Option<SmtpMail>,
+}
+
+/// The envelope of a test message
+#[derive(Debug)]
+pub(crate) struct SmtpMail {
+    pub(crate) from: String,
+    pub(crate) to: String,
+}
+
+/// Configuration options for an LDAP probe, which binds and optionally searches the directory
+#[derive(Debug)]
+pub(crate) struct Ldap {
+    pub(crate) host: String,
+    pub(crate) port: u16,
+    /// connects with TLS right away like for `ldaps://`, trusting the CA of the certificates
+    pub(crate) tls: bool,
+    /// upgrades the plain connection with the StartTLS extended operation
+    pub(crate) starttls: bool,
+    /// expectations on the certificate chain, given with a `tls` or `starttls` block
+    pub(crate) tls_test: Option<TlsTest>,
+    /// binds anonymously when missing
+    pub(crate) bind_dn: Option<String>,
+    pub(crate) password: Option<SecretString>,
+    pub(crate) timeout: Duration,
+    pub(crate) certs: Option<Certificates>,
+    pub(crate) search: Option<LdapSearch>,
+}
+
+/// A search in the subtree of the base, whose entries are shown in the report
+#[derive(Debug)]
+pub(crate) struct LdapSearch {
+    pub(crate) base: String,
+    /// a filter like `(&(objectClass=person)(uid=probe))`
+    pub(crate) filter: String,
+    /// all user attributes are returned when empty
+    pub(crate) attributes: Vec<String>,
+    pub(crate) limit: i64,
+    pub(crate) min_results: Option<usize>,
+}
+
+/// Configuration options for an SSH probe, which inspects the algorithms and host key of the
+/// server and optionally runs a command
+#[derive(Debug)]
+pub(crate) struct Ssh {
+    pub(crate) host: String,
+    pub(crate) port: u16,
+    /// the SHA256 fingerprint like `ssh-keygen -l` shows it, required to authenticate
+    pub(crate) fingerprint: Option<String>,
+    /// authenticates with the password or the private key when given
+    pub(crate) user: Option<String>,
+    /// the passphrase of the private key when both are given
+    pub(crate) password: Option<SecretString>,
+    pub(crate) private_key: Option<String>,
+    /// executed after the authentication, it must exit with status 0
+    pub(crate) command: String,
+    pub(crate) timeout: Duration,
+}
+
+/// Configuration options for a file transfer probe, which logs in, lists a directory and
+/// optionally uploads and deletes a test file
+#[derive(Debug)]
+pub(crate) struct Ftp {
+    pub(crate) host: String,
+    pub(crate) port: u16,
+    pub(crate) protocol: FileTransfer,
+    pub(crate) user: String,
+    pub(crate) password: SecretString,
+    /// the directory after the login when missing
+    pub(crate) directory: Option<String>,
+    /// uploads a small file into the directory, which is deleted right away
+    pub(crate) upload: bool,
+    /// the expected SHA256 fingerprint of the host key, required for SFTP
+    pub(crate) fingerprint: Option<String>,
+    /// expectations on the certificate chain, only for FTPS
+    pub(crate) tls_test: Option<TlsTest>,
+    pub(crate) timeout: Duration,
+    pub(crate) certs: Option<Certificates>,
+}
+
+/// Configuration options for an NTP probe, which compares the local clock with the given servers
+#[derive(Debug)]
+pub(crate) struct Ntp {
+    /// host names, with an optional port
+    pub(crate) servers: Vec<(String, u16)>,
+    /// the largest tolerated difference between the local clock and any server
+    pub(crate) max_offset: Duration,
+    pub(crate) timeout: Duration,
+}
+
+/// The protocols of a file transfer probe
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum FileTransfer {
+    Ftp,
+    /// upgrades the control connection with `AUTH TLS`
+    ExplicitFtps,
+    /// connects with TLS right away, usually on port 990
+    ImplicitFtps,
+    Sftp,
+}
+
+/// A command sent to Redis, its reply is matched when a pattern is given
+#[derive(Debug)]
+pub(crate) struct RedisCommand {
+    pub(crate) arguments: Vec<String>,
+    pub(crate) expected: Option<Regex>,
+}
+
 impl Certificates {
     pub(crate) fn new(
         client_cert: Option<String>,
         client_key: Option<String>,
         client_pem: Option<String>,
+        client_pkcs12: Option<String>,
+        client_pkcs12_password: Option<SecretString>,
         ca_cert: Option<String>,
     ) -> Certificates {
         Certificates {
             client_cert,
             client_key,
             client_pem,
+            client_pkcs12,
+            client_pkcs12_password,
             ca_cert,
         }
     }
@@ -147,11 +577,90 @@ impl Config {
     where
         F: Fn(SecretString) -> SecretString,
     {
-        if let Config::Postgres(Postgres { password, .. })
-        | Config::Oracle(Oracle { password, .. })
-        | Config::MSSql(MSSql { password, .. }) = &mut self
-        {
-            let _old = std::mem::replace(password, decrypt(password.to_owned()));
+        fn http_secrets(http: &mut Http) -> Vec<&mut SecretString> {
+            let proxy_password = http.proxy.as_mut().and_then(|p| p.password.as_mut());
+            let pkcs12_password = http
+                .certs
+                .as_mut()
+                .and_then(|c| c.client_pkcs12_password.as_mut());
+            proxy_password.into_iter().chain(pkcs12_password).collect()
+        }
+
+        let secrets = match &mut self {
+            Config::Http(http) | Config::Health(Health { http }) => http_secrets(http),
+            Config::HttpScenario(HttpScenario {
+                variables, steps, ..
+            }) => variables
+                .iter_mut()
+                .map(|(_, value)| value)
+                .chain(
+                    steps
+                        .iter_mut()
+                        .flat_map(|step| http_secrets(&mut step.http)),
+                )
+                .collect(),
+            Config::Postgres(Postgres {
+                password, certs, ..
+            })
+            | Config::MySql(MySql {
+                password, certs, ..
+            })
+            | Config::MSSql(MSSql {
+                password, certs, ..
+            })
+            | Config::Amqp(Amqp {
+                password, certs, ..
+            })
+            | Config::Ftp(Ftp {
+                password, certs, ..
+            }) => std::iter::once(password)
+                .chain(
+                    certs
+                        .as_mut()
+                        .and_then(|c| c.client_pkcs12_password.as_mut()),
+                )
+                .collect(),
+            Config::Oracle(Oracle { password, .. }) => vec![password],
+            Config::Ssh(Ssh { password, .. }) => password.as_mut().into_iter().collect(),
+            Config::Tls(Tls { certs, .. }) => certs
+                .as_mut()
+                .and_then(|c| c.client_pkcs12_password.as_mut())
+                .into_iter()
+                .collect(),
+            Config::Redis(Redis {
+                password, certs, ..
+            })
+            | Config::Smtp(Smtp {
+                password, certs, ..
+            })
+            | Config::Ldap(Ldap {
+                password, certs, ..
+            })
+            | Config::MongoDb(MongoDb {
+                password, certs, ..
+            }) => password
+                .as_mut()
+                .into_iter()
+                .chain(
+                    certs
+                        .as_mut()
+                        .and_then(|c| c.client_pkcs12_password.as_mut()),
+                )
+                .collect(),
+            Config::Kafka(Kafka { sasl, certs, .. }) => sasl
+                .as_mut()
+                .map(|sasl| &mut sasl.password)
+                .into_iter()
+                .chain(
+                    certs
+                        .as_mut()
+                        .and_then(|c| c.client_pkcs12_password.as_mut()),
+                )
+                .collect(),
+            Config::Tcp(_) | Config::Udp(_) | Config::Dns(_) | Config::Ntp(_) => Vec::new(),
+        };
+        for secret in secrets {
+            let _old = std::mem::replace(secret, decrypt(secret.to_owned()));
         }
         self
     }
@@ -169,6 +678,48 @@ impl From<Http> for Config {
     }
 }
 
+impl From<Health> for Config {
+    fn from(config: Health) -> Self {
+        Config::Health(config)
+    }
+}
+
+impl From<Tcp> for Config {
+    fn from(config: Tcp) -> Self {
+        Config::Tcp(config)
+    }
+}
+
+impl From<Udp> for Config {
+    fn from(config: Udp) -> Self {
+        Config::Udp(config)
+    }
+}
+
+impl From<Tls> for Config {
+    fn from(config: Tls) -> Self {
+        Config::Tls(config)
+    }
+}
+
+impl From<Dns> for Config {
+    fn from(config: Dns) -> Self {
+        Config::Dns(config)
+    }
+}
+
+impl From<HttpScenario> for Config {
+    fn from(config: HttpScenario) -> Self {
+        Config::HttpScenario(config)
+    }
+}
+
+impl From<MySql> for Config {
+    fn from(config: MySql) -> Self {
+        Config::MySql(config)
+    }
+}
+
 impl From<Postgres> for Config {
     fn from(config: Postgres) -> Self {
         Config::Postgres(config)
@@ -181,6 +732,60 @@ impl From<MSSql> for Config {
     }
 }
 
+impl From<Redis> for Config {
+    fn from(config: Redis) -> Self {
+        Config::Redis(config)
+    }
+}
+
+impl From<MongoDb> for Config {
+    fn from(config: MongoDb) -> Self {
+        Config::MongoDb(config)
+    }
+}
+
+impl From<Kafka> for Config {
+    fn from(config: Kafka) -> Self {
+        Config::Kafka(config)
+    }
+}
+
+impl From<Amqp> for Config {
+    fn from(config: Amqp) -> Self {
+        Config::Amqp(config)
+    }
+}
+
+impl From<Smtp> for Config {
+    fn from(config: Smtp) -> Self {
+        Config::Smtp(config)
+    }
+}
+
+impl From<Ldap> for Config {
+    fn from(config: Ldap) -> Self {
+        Config::Ldap(config)
+    }
+}
+
+impl From<Ssh> for Config {
+    fn from(config: Ssh) -> Self {
+        Config::Ssh(config)
+    }
+}
+
+impl From<Ftp> for Config {
+    fn from(config: Ftp) -> Self {
+        Config::Ftp(config)
+    }
+}
+
+impl From<Ntp> for Config {
+    fn from(config: Ntp) -> Self {
+        Config::Ntp(config)
+    }
+}
+
 #[derive(Debug)]
 pub struct SqlTest {
     pub(crate) query: String,
@@ -189,6 +794,33 @@ pub struct SqlTest {
 #[derive(Debug)]
 pub struct SqlTestData {}
 
+/// Assertions on the certificate chain presented by a TLS server
+#[derive(Debug, Clone)]
+pub(crate) struct CertificateTest {
+    pub(crate) min_days_valid: Option<u32>,
+}
+
+/// Assertions on a downloaded response-body, which is streamed instead of kept in memory
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DownloadTest {
+    /// hex encoded SHA-256 digest of the body
+    pub(crate) sha256: Option<String>,
+    /// in MB/s (1 MB = 1,000,000 bytes)
+    pub(crate) min_throughput: Option<f64>,
+    /// of the whole transfer, instead of the timeout of the probe
+    pub(crate) timeout: Option<Duration>,
+}
+
+/// Expectations on the certificate chain presented by a TLS server. A mismatch indicates that
+/// something like an interception proxy re-signs the traffic.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TlsTest {
+    /// base64 encoded SHA-256 hashes of a public key (SPKI) within the chain
+    pub(crate) pin_sha256: Vec<String>,
+    /// must be part of the issuer of the server certificate
+    pub(crate) expected_issuer: Option<String>,
+}
+
 #[derive(Debug)]
 pub struct ServiceSpecification {
     pub(crate) service: String,
@@ -217,9 +849,25 @@ fn prepare_probes_from_spec(specs: Vec<ServiceSpecification>) -> Probes {
                 .map(|config| config.decrypt(|secret| decrypt_secret(secret, None).unwrap()))
                 .map(|config| match config {
                     Config::Http(c) => Box::new(c) as ProbeBox,
+                    Config::HttpScenario(c) => Box::new(c) as ProbeBox,
+                    Config::Health(c) => Box::new(c) as ProbeBox,
+                    Config::Tcp(c) => Box::new(c) as ProbeBox,
+                    Config::Udp(c) => Box::new(c) as ProbeBox,
+                    Config::Dns(c) => Box::new(c) as ProbeBox,
+                    Config::Tls(c) => Box::new(c) as ProbeBox,
                     Config::Postgres(c) => Box::new(c) as ProbeBox,
+                    Config::MySql(c) => Box::new(c) as ProbeBox,
                     Config::Oracle(c) => Box::new(c) as ProbeBox,
                     Config::MSSql(c) => Box::new(c) as ProbeBox,
+                    Config::Redis(c) => Box::new(c) as ProbeBox,
+                    Config::MongoDb(c) => Box::new(c) as ProbeBox,
+                    Config::Kafka(c) => Box::new(c) as ProbeBox,
+                    Config::Amqp(c) => Box::new(c) as ProbeBox,
+                    Config::Smtp(c) => Box::new(c) as ProbeBox,
+                    Config::Ldap(c) => Box::new(c) as ProbeBox,
+                    Config::Ssh(c) => Box::new(c) as ProbeBox,
+                    Config::Ftp(c) => Box::new(c) as ProbeBox,
+                    Config::Ntp(c) => Box::new(c) as ProbeBox,
                 })
         })
         .collect()
@@ -251,6 +899,7 @@ mod tests {
                 Ok(ProbeReport {
                     probe_identifier: self.identifier(),
                     data: vec![],
+                    body: None,
                 })
             }
 
@@ -279,6 +928,7 @@ mod tests {
                     ProbeReport {
                         probe_identifier: self.identifier(),
                         data: vec![],
+                        body: None,
                     },
                 ))
             }
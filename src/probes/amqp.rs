@@ -0,0 +1,594 @@
+use std::io;
+use std::io::{ErrorKind, Read, Write};
+use std::sync::Arc;
+use std::time::Instant;
+
+use secrecy::{ExposeSecret, SecretString};
+
+use crate::error::InquestError::{AssertionMatchingError, FailedExecutionError};
+use crate::probes::stream::{connect, Stream};
+use crate::probes::tls::{certificate_mismatch, client_config, PeerCertificates};
+use crate::{Amqp, Certificates, Data, GlobalOptions, Probe, ProbeReport, Result};
+
+const PROBE_NAME: &str = "AMQP";
+
+const PROTOCOL_HEADER: &[u8] = b"AMQP\x00\x00\x09\x01";
+
+const FRAME_METHOD: u8 = 1;
+const FRAME_HEARTBEAT: u8 = 8;
+const FRAME_END: u8 = 0xCE;
+/// The methods of the probe are small, while an HTTP or TLS answer decodes to gigabytes
+const MAX_FRAME_SIZE: usize = 1024 * 1024;
+
+/// The methods as pairs of class and method id
+const CONNECTION_START: (u16, u16) = (10, 10);
+const CONNECTION_START_OK: (u16, u16) = (10, 11);
+const CONNECTION_TUNE: (u16, u16) = (10, 30);
+const CONNECTION_TUNE_OK: (u16, u16) = (10, 31);
+const CONNECTION_OPEN: (u16, u16) = (10, 40);
+const CONNECTION_OPEN_OK: (u16, u16) = (10, 41);
+const CONNECTION_CLOSE: (u16, u16) = (10, 50);
+const CONNECTION_CLOSE_OK: (u16, u16) = (10, 51);
+const CHANNEL_OPEN: (u16, u16) = (20, 10);
+const CHANNEL_OPEN_OK: (u16, u16) = (20, 11);
+const CHANNEL_CLOSE: (u16, u16) = (20, 40);
+const QUEUE_DECLARE: (u16, u16) = (50, 10);
+const QUEUE_DECLARE_OK: (u16, u16) = (50, 11);
+
+impl Amqp {
+    pub(crate) fn new(
+        host: String,
+        user: String,
+        password: SecretString,
+        certs: Option<Certificates>,
+        options: &'static GlobalOptions,
+    ) -> Amqp {
+        Amqp {
+            host,
+            port: 5672,
+            vhost: "/".to_string(),
+            user,
+            password,
+            tls: false,
+            tls_test: None,
+            timeout: options.timeout,
+            certs,
+            queue: None,
+        }
+    }
+}
+
+/// A method frame received from the broker
+struct Method {
+    id: (u16, u16),
+    arguments: Vec<u8>,
+}
+
+/// Opens a connection to the virtual host, and a channel for the passive declaration of the queue.
+/// Nothing is created on the broker.
+impl Probe for Amqp {
+    fn execute(&self) -> Result<ProbeReport> {
+        let failed = |e: io::Error| FailedExecutionError {
+            probe_identifier: self.identifier(),
+            source: Box::new(e),
+        };
+        let peer_certificates = PeerCertificates::default();
+        let config = match self.tls {
+            true => Some(Arc::new(
+                client_config(
+                    self.certs.as_ref(),
+                    self.tls_test.as_ref(),
+                    peer_certificates.clone(),
+                )
+                .map_err(|e| FailedExecutionError {
+                    probe_identifier: self.identifier(),
+                    source: Box::new(e),
+                })?,
+            )),
+            false => None,
+        };
+
+        let started = Instant::now();
+        let socket = connect(&self.host, self.port, self.timeout).map_err(failed)?;
+        let mut report = ProbeReport::new(self.identifier());
+        report.data.push((
+            "Connection".to_string(),
+            format!(
+                "{} connected in {:.1} ms",
+                socket
+                    .peer_addr()
+                    .map_or("unknown".to_string(), |peer| peer.to_string()),
+                started.elapsed().as_secs_f64() * 1000.0
+            ),
+        ));
+        let mut stream = Stream::Plain(socket);
+        if let Some(config) = config {
+            stream = match stream.upgrade(&self.host, config) {
+                Ok(stream) => stream,
+                Err(e) => {
+                    return Err(certificate_mismatch(
+                        self.identifier(),
+                        self.tls_test.as_ref(),
+                        &peer_certificates,
+                    )
+                    .unwrap_or(AssertionMatchingError(e.to_string(), report)))
+                }
+            };
+            if let Some(tls) = stream.tls_description() {
+                report.data.push(("TLS".to_string(), tls));
+            }
+        }
+
+        match self.handshake(&mut stream, &mut report.data) {
+            Ok(Ok(())) => {}
+            Ok(Err(refusal)) => return Err(AssertionMatchingError(refusal, report)),
+            Err(e) => return Err(failed(e)),
+        }
+        if let Some(queue) = &self.queue {
+            match declare_passive(&mut stream, queue).map_err(failed)? {
+                Ok((messages, consumers)) => report.data.push((
+                    format!("Queue {}", queue),
+                    format!("{} messages, {} consumers", messages, consumers),
+                )),
+                Err(refusal) => {
+                    return Err(AssertionMatchingError(
+                        format!("Queue '{}' is not available: {}", queue, refusal),
+                        report,
+                    ))
+                }
+            }
+        }
+        close(&mut stream);
+        Ok(report)
+    }
+
+    fn identifier(&self) -> String {
+        format!(
+            "{} - {}:{}/{}",
+            PROBE_NAME,
+            self.host,
+            self.port,
+            self.vhost.trim_start_matches('/')
+        )
+    }
+}
+
+impl Amqp {
+    /// Negotiates the connection up to the opened virtual host. A refusal of the broker is
+    /// returned as description.
+    fn handshake(
+        &self,
+        stream: &mut Stream,
+        data: &mut Data,
+    ) -> io::Result<std::result::Result<(), String>> {
+        stream.write_all(PROTOCOL_HEADER)?;
+        let start = read_method(stream)?;
+        if start.id != CONNECTION_START {
+            return Err(invalid_data("expected Connection.Start"));
+        }
+        let mut reader = Reader(&start.arguments);
+        let version = reader.bytes_of(2)?;
+        let properties = reader.table()?;
+        let mechanisms = reader.long_string()?;
+        let property = |name: &str| {
+            properties
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        };
+        data.push((
+            "Server".to_string(),
+            format!(
+                "{} {} ({}), AMQP {}-{}",
+                property("product").unwrap_or("unknown"),
+                property("version").unwrap_or("unknown"),
+                property("platform").unwrap_or("unknown platform"),
+                version[0],
+                version[1]
+            ),
+        ));
+        if let Some(cluster) = property("cluster_name") {
+            data.push(("Cluster".to_string(), cluster.to_string()));
+        }
+        if !mechanisms.split(' ').any(|mechanism| mechanism == "PLAIN") {
+            return Ok(Err(format!(
+                "The broker does not offer PLAIN authentication, but '{}'",
+                mechanisms
+            )));
+        }
+
+        let mut arguments = Vec::new();
+        write_table(&mut arguments, &[("product", "inquest")]);
+        write_short_string(&mut arguments, "PLAIN");
+        write_long_string(
+            &mut arguments,
+            &format!("\0{}\0{}", self.user, self.password.expose_secret()),
+        );
+        write_short_string(&mut arguments, "en_US");
+        write_method(stream, 0, CONNECTION_START_OK, &arguments)?;
+        let tune = match read_method(stream) {
+            Ok(method) if method.id == CONNECTION_TUNE => method,
+            Ok(method) if method.id == CONNECTION_CLOSE => {
+                return Ok(Err(format!(
+                    "Authentication as '{}' failed: {}",
+                    self.user,
+                    close_reason(&method)?
+                )))
+            }
+            // brokers without the capability 'authentication_failure_close' just hang up
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => {
+                return Ok(Err(format!(
+                    "Authentication as '{}' failed, the broker closed the connection",
+                    self.user
+                )))
+            }
+            Ok(_) => return Err(invalid_data("expected Connection.Tune")),
+            Err(e) => return Err(e),
+        };
+        let mut reader = Reader(&tune.arguments);
+        let channel_max = reader.u16()?;
+        let frame_max = reader.u32()?;
+        let mut arguments = Vec::new();
+        arguments.extend(channel_max.to_be_bytes());
+        arguments.extend(frame_max.to_be_bytes());
+        // no heartbeats, the connection is short-lived
+        arguments.extend(0u16.to_be_bytes());
+        write_method(stream, 0, CONNECTION_TUNE_OK, &arguments)?;
+
+        let mut arguments = Vec::new();
+        write_short_string(&mut arguments, &self.vhost);
+        write_short_string(&mut arguments, "");
+        arguments.push(0);
+        write_method(stream, 0, CONNECTION_OPEN, &arguments)?;
+        let open = read_method(stream)?;
+        match open.id {
+            CONNECTION_OPEN_OK => Ok(Ok(())),
+            CONNECTION_CLOSE => Ok(Err(format!(
+                "Access to vhost '{}' refused: {}",
+                self.vhost,
+                close_reason(&open)?
+            ))),
+            _ => Err(invalid_data("expected Connection.Open-Ok")),
+        }
+    }
+}
+
+/// Returns the number of messages and consumers of the queue, or the reason why the broker
+/// closed the channel
+fn declare_passive(
+    stream: &mut Stream,
+    queue: &str,
+) -> io::Result<std::result::Result<(u32, u32), String>> {
+    let mut arguments = Vec::new();
+    write_short_string(&mut arguments, "");
+    write_method(stream, 1, CHANNEL_OPEN, &arguments)?;
+    if read_method(stream)?.id != CHANNEL_OPEN_OK {
+        return Err(invalid_data("expected Channel.Open-Ok"));
+    }
+
+    let mut arguments = 0u16.to_be_bytes().to_vec();
+    write_short_string(&mut arguments, queue);
+    // only the passive flag
+    arguments.push(0b1);
+    write_table(&mut arguments, &[]);
+    write_method(stream, 1, QUEUE_DECLARE, &arguments)?;
+    let declared = read_method(stream)?;
+    match declared.id {
+        QUEUE_DECLARE_OK => {
+            let mut reader = Reader(&declared.arguments);
+            let _queue = reader.short_string()?;
+            Ok(Ok((reader.u32()?, reader.u32()?)))
+        }
+        CHANNEL_CLOSE | CONNECTION_CLOSE => Ok(Err(close_reason(&declared)?)),
+        _ => Err(invalid_data("expected Queue.Declare-Ok")),
+    }
+}
+
+/// Closes the connection gracefully, so that the broker does not log a closed socket
+fn close(stream: &mut Stream) {
+    let mut arguments = 200u16.to_be_bytes().to_vec();
+    write_short_string(&mut arguments, "Goodbye");
+    arguments.extend([0; 4]);
+    if write_method(stream, 0, CONNECTION_CLOSE, &arguments).is_ok() {
+        while let Ok(method) = read_method(stream) {
+            if method.id == CONNECTION_CLOSE_OK {
+                break;
+            }
+        }
+    }
+}
+
+/// The reply code and text of a Connection.Close or Channel.Close
+fn close_reason(method: &Method) -> io::Result<String> {
+    let mut reader = Reader(&method.arguments);
+    let code = reader.u16()?;
+    let text = reader.short_string()?;
+    Ok(format!("{} {}", code, text))
+}
+
+fn write_method(
+    stream: &mut Stream,
+    channel: u16,
+    (class, method): (u16, u16),
+    arguments: &[u8],
+) -> io::Result<()> {
+    let mut frame = vec![FRAME_METHOD];
+    frame.extend(channel.to_be_bytes());
+    frame.extend((arguments.len() as u32 + 4).to_be_bytes());
+    frame.extend(class.to_be_bytes());
+    frame.extend(method.to_be_bytes());
+    frame.extend(arguments);
+    frame.push(FRAME_END);
+    stream.write_all(&frame)?;
+    stream.flush()
+}
+
+fn read_method(stream: &mut impl Read) -> io::Result<Method> {
+    loop {
+        let mut header = [0; 7];
+        stream.read_exact(&mut header)?;
+        let size = u32::from_be_bytes(header[3..7].try_into().unwrap()) as usize;
+        if size > MAX_FRAME_SIZE {
+            return Err(invalid_data("invalid frame, is this an AMQP 0-9-1 broker?"));
+        }
+        let mut payload = vec![0; size + 1];
+        stream.read_exact(&mut payload)?;
+        if payload.pop() != Some(FRAME_END) {
+            return Err(invalid_data("invalid frame, is this an AMQP 0-9-1 broker?"));
+        }
+        match header[0] {
+            FRAME_HEARTBEAT => continue,
+            FRAME_METHOD if payload.len() >= 4 => {
+                let mut reader = Reader(&payload);
+                return Ok(Method {
+                    id: (reader.u16()?, reader.u16()?),
+                    arguments: reader.0.to_vec(),
+                });
+            }
+            _ => return Err(invalid_data("unexpected frame")),
+        }
+    }
+}
+
+fn write_short_string(buffer: &mut Vec<u8>, value: &str) {
+    buffer.push(value.len() as u8);
+    buffer.extend(value.as_bytes());
+}
+
+fn write_long_string(buffer: &mut Vec<u8>, value: &str) {
+    buffer.extend((value.len() as u32).to_be_bytes());
+    buffer.extend(value.as_bytes());
+}
+
+/// Writes a field table of long strings
+fn write_table(buffer: &mut Vec<u8>, entries: &[(&str, &str)]) {
+    let mut table = Vec::new();
+    for (key, value) in entries {
+        write_short_string(&mut table, key);
+        table.push(b'S');
+        write_long_string(&mut table, value);
+    }
+    buffer.extend((table.len() as u32).to_be_bytes());
+    buffer.extend(table);
+}
+
+fn invalid_data(reason: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, reason.to_string())
+}
+
+/// Decodes the types of AMQP 0-9-1 method arguments
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn bytes_of(&mut self, length: usize) -> io::Result<&'a [u8]> {
+        if self.0.len() < length {
+            return Err(invalid_data("truncated method"));
+        }
+        let (value, rest) = self.0.split_at(length);
+        self.0 = rest;
+        Ok(value)
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        Ok(u16::from_be_bytes(self.bytes_of(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        Ok(u32::from_be_bytes(self.bytes_of(4)?.try_into().unwrap()))
+    }
+
+    fn short_string(&mut self) -> io::Result<String> {
+        let length = self.bytes_of(1)?[0] as usize;
+        Ok(String::from_utf8_lossy(self.bytes_of(length)?).to_string())
+    }
+
+    fn long_string(&mut self) -> io::Result<String> {
+        let length = self.u32()? as usize;
+        Ok(String::from_utf8_lossy(self.bytes_of(length)?).to_string())
+    }
+
+    /// Only the string values of a field table are kept, the others are skipped. The types are
+    /// the ones used by RabbitMQ.
+    fn table(&mut self) -> io::Result<Vec<(String, String)>> {
+        let length = self.u32()? as usize;
+        let mut table = Reader(self.bytes_of(length)?);
+        let mut entries = Vec::new();
+        while !table.0.is_empty() {
+            let key = table.short_string()?;
+            let size = match table.bytes_of(1)?[0] {
+                b'S' => {
+                    entries.push((key, table.long_string()?));
+                    continue;
+                }
+                b'F' | b'A' | b'x' => table.u32()? as usize,
+                b't' | b'b' | b'B' => 1,
+                b's' | b'u' => 2,
+                b'I' | b'i' | b'f' => 4,
+                b'D' => 5,
+                b'l' | b'L' | b'd' | b'T' => 8,
+                b'V' => 0,
+                other => {
+                    return Err(invalid_data(&format!(
+                        "unknown field type '{}'",
+                        other as char
+                    )))
+                }
+            };
+            table.bytes_of(size)?;
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{ErrorKind, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use secrecy::SecretString;
+
+    use crate::error::InquestError::AssertionMatchingError;
+    use crate::probes::amqp::{
+        read_method, write_long_string, write_short_string, Reader, CHANNEL_CLOSE, CHANNEL_OPEN_OK,
+        CONNECTION_CLOSE, CONNECTION_CLOSE_OK, CONNECTION_OPEN, CONNECTION_OPEN_OK,
+        CONNECTION_START, CONNECTION_START_OK, CONNECTION_TUNE, QUEUE_DECLARE, QUEUE_DECLARE_OK,
+    };
+    use crate::{Amqp, Probe, GO};
+
+    fn frame(channel: u16, (class, method): (u16, u16), arguments: &[u8]) -> Vec<u8> {
+        let mut frame = vec![1];
+        frame.extend(channel.to_be_bytes());
+        frame.extend((arguments.len() as u32 + 4).to_be_bytes());
+        frame.extend(class.to_be_bytes());
+        frame.extend(method.to_be_bytes());
+        frame.extend(arguments);
+        frame.push(0xCE);
+        frame
+    }
+
+    /// Answers like RabbitMQ with the user 'probe' and password 'secret' on vhost '/', where the
+    /// queue 'orders' exists
+    fn amqp_server() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut header = [0; 8];
+                std::io::Read::read_exact(&mut stream, &mut header).unwrap();
+                let mut start = vec![0, 9];
+                let mut properties = Vec::new();
+                for (key, value) in [("product", "RabbitMQ"), ("version", "3.12.0")] {
+                    write_short_string(&mut properties, key);
+                    properties.push(b'S');
+                    write_long_string(&mut properties, value);
+                }
+                write_short_string(&mut properties, "capabilities");
+                properties.extend(b"F\x00\x00\x00\x0F\x0Aper_consumert\x01");
+                start.extend((properties.len() as u32).to_be_bytes());
+                start.extend(properties);
+                write_long_string(&mut start, "AMQPLAIN PLAIN");
+                write_long_string(&mut start, "en_US");
+                stream
+                    .write_all(&frame(0, CONNECTION_START, &start))
+                    .unwrap();
+
+                while let Ok(method) = read_method(&mut stream) {
+                    let mut reader = Reader(&method.arguments);
+                    let reply = match method.id {
+                        CONNECTION_START_OK => {
+                            reader.table().unwrap();
+                            reader.short_string().unwrap();
+                            if reader.long_string().unwrap() == "\0probe\0secret" {
+                                frame(0, CONNECTION_TUNE, &[0, 0, 0, 2, 0, 0, 0, 60])
+                            } else {
+                                let mut close = 403u16.to_be_bytes().to_vec();
+                                write_short_string(
+                                    &mut close,
+                                    "ACCESS_REFUSED - Login was refused",
+                                );
+                                close.extend([0, 10, 0, 11]);
+                                frame(0, CONNECTION_CLOSE, &close)
+                            }
+                        }
+                        CONNECTION_OPEN => frame(0, CONNECTION_OPEN_OK, &[0]),
+                        (20, 10) => frame(1, CHANNEL_OPEN_OK, &[0, 0, 0, 0]),
+                        QUEUE_DECLARE => {
+                            reader.u16().unwrap();
+                            let queue = reader.short_string().unwrap();
+                            if queue == "orders" {
+                                let mut declared = Vec::new();
+                                write_short_string(&mut declared, &queue);
+                                declared.extend([0, 0, 0, 42, 0, 0, 0, 2]);
+                                frame(1, QUEUE_DECLARE_OK, &declared)
+                            } else {
+                                let mut close = 404u16.to_be_bytes().to_vec();
+                                write_short_string(
+                                    &mut close,
+                                    &format!("NOT_FOUND - no queue '{}' in vhost '/'", queue),
+                                );
+                                close.extend([0, 50, 0, 10]);
+                                frame(1, CHANNEL_CLOSE, &close)
+                            }
+                        }
+                        CONNECTION_CLOSE => frame(0, CONNECTION_CLOSE_OK, &[]),
+                        _ => continue,
+                    };
+                    stream.write_all(&reply).unwrap();
+                }
+            }
+        });
+        port
+    }
+
+    fn probe(port: u16, password: &str, queue: &str) -> Amqp {
+        Amqp {
+            port,
+            queue: Some(queue.to_string()),
+            ..Amqp::new(
+                "127.0.0.1".to_string(),
+                "probe".to_string(),
+                SecretString::new(password.to_string()),
+                None,
+                &GO,
+            )
+        }
+    }
+
+    #[test]
+    fn server_and_queue_are_reported() {
+        let port = amqp_server();
+
+        let report = probe(port, "secret", "orders").execute().unwrap();
+        assert_eq!(
+            "RabbitMQ 3.12.0 (unknown platform), AMQP 0-9",
+            report.data[1].1
+        );
+        assert_eq!(
+            (
+                "Queue orders".to_string(),
+                "42 messages, 2 consumers".to_string()
+            ),
+            report.data[2]
+        );
+
+        assert_matches!(
+            probe(port, "secret", "payments").execute(),
+            Err(AssertionMatchingError(desc, _)) if desc == "Queue 'payments' is not available: \
+                404 NOT_FOUND - no queue 'payments' in vhost '/'"
+        );
+        assert_matches!(
+            probe(port, "guess", "orders").execute(),
+            Err(AssertionMatchingError(desc, _)) if desc == "Authentication as 'probe' failed: \
+                403 ACCESS_REFUSED - Login was refused"
+        );
+    }
+
+    #[test]
+    fn oversized_frame_is_rejected_before_reading() {
+        // 'HTTP/1.' as frame header announces a payload of about 1.3 GB
+        let result = read_method(&mut &b"HTTP/1.1 400 Bad Request\r\n\r\n"[..]);
+
+        assert_eq!(Some(ErrorKind::InvalidData), result.err().map(|e| e.kind()));
+    }
+}
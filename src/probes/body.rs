@@ -0,0 +1,248 @@
+use std::fmt::Write;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::Value;
+
+use crate::{BodyFormat, BodyPreview};
+
+/// Bytes of a response-body kept in the report, unless configured otherwise
+pub(super) const DEFAULT_PREVIEW_SIZE: usize = 4096;
+
+const REDACTED: &str = "***";
+
+/// Keys of JSON-objects and form-fields whose values are redacted
+const SENSITIVE_KEYS: &str =
+    r"(?i)(password|passwd|pwd|secret|token|api[_-]?key|authorization|credential|session|cookie)";
+
+static SENSITIVE: Lazy<Regex> = Lazy::new(|| Regex::new(SENSITIVE_KEYS).unwrap());
+
+/// Key-value pairs like `password=...`, `"token": "..."` or the attribute `secret="..."`
+static PAIRS: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(
+        r#"{}(\w*["']?\s*[:=]\s*["']?)(?:(?:Bearer|Basic)\s+)?[^"'&\s,;<]+"#,
+        SENSITIVE_KEYS
+    ))
+    .unwrap()
+});
+
+/// Elements like `<password>...</password>` or `<wsse:Password Type="...">...</wsse:Password>`
+static ELEMENTS: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(&format!(
+        r#"(<(?:[\w.-]+:)?\w*{}\w*(?:\s[^>]*)?>)[^<]+(<)"#,
+        SENSITIVE_KEYS
+    ))
+    .unwrap()
+});
+
+static SCHEMES: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)\b(Bearer|Basic)\s+[A-Za-z0-9._~+/=-]+").unwrap());
+
+/// Prepares the body for the report. Credentials are redacted before the content is truncated,
+/// so a truncated secret cannot slip through.
+pub(super) fn preview(content_type: Option<&str>, body: &[u8], max_size: usize) -> BodyPreview {
+    let format = format(content_type, body);
+    let content = match format {
+        BodyFormat::Json => serde_json::from_slice::<Value>(body)
+            .map(|mut json| {
+                redact_json(&mut json);
+                serde_json::to_string_pretty(&json).unwrap_or_default()
+            })
+            .unwrap_or_default(),
+        BodyFormat::Text => redact_text(utf8_prefix(body).unwrap_or_default()),
+        BodyFormat::Hex => hex_dump(&body[..body.len().min(max_size)]),
+    };
+    let (content, truncated) = match format {
+        BodyFormat::Hex => (content, body.len() > max_size),
+        _ => truncate(content, max_size),
+    };
+    BodyPreview {
+        content_type: content_type.map(str::to_string),
+        format,
+        content,
+        size: body.len(),
+        truncated,
+    }
+}
+
+fn format(content_type: Option<&str>, body: &[u8]) -> BodyFormat {
+    let content_type = content_type.unwrap_or_default().to_ascii_lowercase();
+    let is_json = content_type.contains("json");
+    // without a content-type, the body is sniffed
+    if (is_json || content_type.is_empty()) && serde_json::from_slice::<Value>(body).is_ok() {
+        BodyFormat::Json
+    } else if utf8_prefix(body).is_none() {
+        BodyFormat::Hex
+    } else if content_type.is_empty()
+        || is_json
+        || content_type.starts_with("text/")
+        || ["xml", "html", "javascript", "x-www-form-urlencoded"]
+            .iter()
+            .any(|textual| content_type.contains(textual))
+    {
+        BodyFormat::Text
+    } else {
+        BodyFormat::Hex
+    }
+}
+
+/// The body as text, without a character cut at the end, since only the beginning of a long body
+/// is read
+fn utf8_prefix(body: &[u8]) -> Option<&str> {
+    match std::str::from_utf8(body) {
+        Ok(text) => Some(text),
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&body[..e.valid_up_to()]).ok(),
+        Err(_) => None,
+    }
+}
+
+fn redact_json(json: &mut Value) {
+    match json {
+        Value::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                if SENSITIVE.is_match(key) && !value.is_object() && !value.is_array() {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_json(value);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(redact_json),
+        Value::String(text) => *text = redact_text(text),
+        _ => {}
+    }
+}
+
+/// Redacts values of key-value pairs like `password=...` or `"token": "..."`, the content of XML
+/// elements like `<password>` as well as the credentials of Authorization-schemes like
+/// `Bearer ...`
+fn redact_text(text: &str) -> String {
+    let text = ELEMENTS.replace_all(text, format!("${{1}}{}${{3}}", REDACTED));
+    let text = PAIRS.replace_all(&text, format!("${{1}}${{2}}{}", REDACTED));
+    SCHEMES
+        .replace_all(&text, format!("${{1}} {}", REDACTED))
+        .to_string()
+}
+
+fn truncate(mut content: String, max_size: usize) -> (String, bool) {
+    if content.len() <= max_size {
+        return (content, false);
+    }
+    let mut end = max_size;
+    while !content.is_char_boundary(end) {
+        end -= 1;
+    }
+    content.truncate(end);
+    (content, true)
+}
+
+/// Lines of 16 bytes like `hexdump -C`
+fn hex_dump(bytes: &[u8]) -> String {
+    let mut dump = String::new();
+    for (index, line) in bytes.chunks(16).enumerate() {
+        let hex = line
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let ascii = line
+            .iter()
+            .map(|byte| match byte {
+                0x20..=0x7e => *byte as char,
+                _ => '.',
+            })
+            .collect::<String>();
+        let _ = writeln!(dump, "{:08x}  {:<47}  |{}|", index * 16, hex, ascii);
+    }
+    dump
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::probes::body::preview;
+    use crate::BodyFormat;
+
+    #[test]
+    fn credentials_are_redacted_before_truncation() {
+        let json = br#"{"user": "admin", "access_token": "abc.def", "nested": {"password": 42}}"#;
+        let preview_json = preview(Some("application/json"), json, 4096);
+        assert_eq!(BodyFormat::Json, preview_json.format);
+        assert!(preview_json.content.contains(r#""access_token": "***""#));
+        assert!(preview_json.content.contains(r#""password": "***""#));
+        assert!(preview_json.content.contains(r#""user": "admin""#));
+
+        let text = b"error for user=admin&password=changeit, header Authorization: Bearer eyJhbGci";
+        let preview_text = preview(Some("text/plain"), text, 4096);
+        assert_eq!(
+            "error for user=admin&password=***, header Authorization: ***",
+            preview_text.content
+        );
+
+        let truncated = preview(None, b"0123456789", 4);
+        assert_eq!(
+            ("0123".to_string(), true, 10),
+            (truncated.content, truncated.truncated, truncated.size)
+        );
+    }
+
+    #[test]
+    fn xml_credentials_are_redacted() {
+        let fault = br#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+  <soap:Header>
+    <wsse:Security>
+      <wsse:UsernameToken>
+        <wsse:Username>probe</wsse:Username>
+        <wsse:Password Type="http://docs.oasis-open.org/wss#PasswordText">changeit</wsse:Password>
+      </wsse:UsernameToken>
+    </wsse:Security>
+  </soap:Header>
+  <soap:Body>
+    <soap:Fault>
+      <faultcode>soap:Client</faultcode>
+      <faultstring>Login failed for <password>changeit</password></faultstring>
+      <detail><login user="probe" password="changeit"/></detail>
+    </soap:Fault>
+  </soap:Body>
+</soap:Envelope>"#;
+        let preview = preview(Some("text/xml; charset=utf-8"), fault, 4096);
+
+        assert!(!preview.content.contains("changeit"));
+        assert!(preview
+            .content
+            .contains(r#"<wsse:Password Type="http://docs.oasis-open.org/wss#PasswordText">***</wsse:Password>"#));
+        assert!(preview.content.contains("<password>***</password>"));
+        assert!(preview.content.contains(r#"password="***""#));
+        assert!(preview
+            .content
+            .contains("<wsse:Username>probe</wsse:Username>"));
+    }
+
+    #[test]
+    fn binary_content_is_shown_as_hex() {
+        let binary = preview(Some("application/octet-stream"), &[0xca, 0xfe, b'A'], 2);
+
+        assert_eq!(BodyFormat::Hex, binary.format);
+        assert!(binary.truncated);
+        assert_eq!(format!("00000000  {:<47}  |..|\n", "ca fe"), binary.content);
+    }
+
+    #[test]
+    fn text_cut_within_a_character_stays_text() {
+        let cut = &"Grüße".as_bytes()[..3];
+        let text = preview(Some("text/plain"), cut, 4096);
+
+        assert_eq!(BodyFormat::Text, text.format);
+        assert_eq!("Gr", text.content);
+        assert_eq!(3, text.size);
+    }
+
+    #[test]
+    fn json_is_formatted_before_truncation() {
+        let json = format!(r#"{{"token": "abc", "items": [{}1]}}"#, "1, ".repeat(4096));
+        let preview = preview(Some("application/json"), json.as_bytes(), 32);
+
+        assert_eq!(BodyFormat::Json, preview.format);
+        assert!(preview.truncated);
+        assert!(preview.content.contains(r#""token": "***""#));
+    }
+}
@@ -0,0 +1,298 @@
+use std::net::IpAddr;
+use std::time::Instant;
+
+use trust_dns_resolver::config::{NameServerConfig, ResolverConfig, ResolverOpts};
+use trust_dns_resolver::error::ResolveErrorKind;
+use trust_dns_resolver::proto::rr::{Name, RecordType};
+use trust_dns_resolver::system_conf::read_system_conf;
+use trust_dns_resolver::Resolver;
+
+use crate::error::InquestError::{AssertionMatchingError, FailedExecutionError};
+use crate::{Dns, GlobalOptions, Probe, ProbeReport, Result};
+
+const PROBE_NAME: &str = "DNS";
+
+impl Dns {
+    pub(crate) fn new(name: String, options: &'static GlobalOptions) -> Dns {
+        Dns {
+            name,
+            record_type: RecordType::A,
+            nameserver: None,
+            protocol: Default::default(),
+            timeout: options.timeout,
+            expected: Vec::new(),
+        }
+    }
+
+    /// A PTR-query accepts the IP address instead of its `in-addr.arpa` name
+    fn query_name(&self) -> std::result::Result<Name, Box<dyn std::error::Error + Send + Sync>> {
+        match self.name.parse::<IpAddr>() {
+            Ok(ip) if self.record_type == RecordType::PTR => Ok(Name::from(ip)),
+            _ => Ok(Name::from_utf8(&self.name)?),
+        }
+    }
+
+    /// Every query is sent exactly once and bypasses the hosts-file, so the report shows what
+    /// the nameserver answered.
+    fn resolver_config(
+        &self,
+    ) -> std::result::Result<(ResolverConfig, ResolverOpts), Box<dyn std::error::Error + Send + Sync>>
+    {
+        let (config, mut options) = match self.nameserver {
+            Some(address) => {
+                let nameserver = NameServerConfig {
+                    trust_nx_responses: true,
+                    ..NameServerConfig::new(address, self.protocol)
+                };
+                let config = ResolverConfig::from_parts(None, Vec::new(), vec![nameserver]);
+                (config, ResolverOpts::default())
+            }
+            None => {
+                let (system, options) = read_system_conf()?;
+                let nameservers = system
+                    .name_servers()
+                    .iter()
+                    .filter(|nameserver| nameserver.protocol == self.protocol)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                let config = ResolverConfig::from_parts(
+                    system.domain().cloned(),
+                    system.search().to_vec(),
+                    nameservers,
+                );
+                (config, options)
+            }
+        };
+        options.timeout = self.timeout;
+        options.attempts = 1;
+        options.use_hosts_file = false;
+        options.preserve_intermediates = true;
+        options.try_tcp_on_error = false;
+        Ok((config, options))
+    }
+}
+
+impl Probe for Dns {
+    fn execute<'a>(&self) -> Result<ProbeReport> {
+        let failed = |source| FailedExecutionError {
+            probe_identifier: self.identifier(),
+            source,
+        };
+        let name = self.query_name().map_err(failed)?;
+        let (config, options) = self.resolver_config().map_err(failed)?;
+        let nameservers = config
+            .name_servers()
+            .iter()
+            .map(|nameserver| format!("{} ({})", nameserver.socket_addr, nameserver.protocol))
+            .collect::<Vec<_>>();
+        let resolver = Resolver::new(config, options).map_err(|e| failed(Box::new(e)))?;
+
+        let mut report = ProbeReport::new(self.identifier());
+        report
+            .data
+            .push(("Nameserver".to_string(), nameservers.join(", ")));
+        let started = Instant::now();
+        let result = resolver.lookup(name.clone(), self.record_type);
+        report.data.push((
+            "Response time".to_string(),
+            format!("{:.1} ms", started.elapsed().as_secs_f64() * 1000.0),
+        ));
+
+        let lookup = match result {
+            Ok(lookup) => lookup,
+            Err(e) => {
+                return match e.kind() {
+                    ResolveErrorKind::NoRecordsFound { response_code, .. } => {
+                        Err(AssertionMatchingError(
+                            format!(
+                                "No {} records found for '{}' ({})",
+                                self.record_type, name, response_code
+                            ),
+                            report,
+                        ))
+                    }
+                    _ => Err(failed(Box::new(e))),
+                };
+            }
+        };
+
+        let mut values = Vec::new();
+        for (index, record) in lookup.record_iter().enumerate() {
+            let value = record.data().map_or(String::new(), |data| data.to_string());
+            report.data.push((
+                format!("Answer #{}", index + 1),
+                format!(
+                    "{} {} {} {}",
+                    record.name(),
+                    record.ttl(),
+                    record.record_type(),
+                    value
+                ),
+            ));
+            values.push(value);
+        }
+
+        let missing = self
+            .expected
+            .iter()
+            .filter(|expected| !values.iter().any(|value| matches(expected, value)))
+            .map(|expected| format!("'{}'", expected))
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            Ok(report)
+        } else {
+            Err(AssertionMatchingError(
+                format!(
+                    "Expected {} in the answer but got '{}'",
+                    missing.join(", "),
+                    values.join(", ")
+                ),
+                report,
+            ))
+        }
+    }
+
+    fn identifier(&self) -> String {
+        format!("{} - {} {}", PROBE_NAME, self.name, self.record_type)
+    }
+}
+
+/// Domain names match with and without the trailing dot and regardless of their case
+fn matches(expected: &str, value: &str) -> bool {
+    expected
+        .trim_end_matches('.')
+        .eq_ignore_ascii_case(value.trim_end_matches('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::{Ipv4Addr, SocketAddr, TcpListener, UdpSocket};
+    use std::str::FromStr;
+    use std::thread;
+
+    use trust_dns_resolver::config::Protocol;
+    use trust_dns_resolver::proto::op::{Message, MessageType, ResponseCode};
+    use trust_dns_resolver::proto::rr::rdata::MX;
+    use trust_dns_resolver::proto::rr::{Name, RData, Record, RecordType};
+
+    use crate::error::InquestError::AssertionMatchingError;
+    use crate::{Dns, Probe, GO};
+
+    /// Answers with all records of the queried name, or NXDOMAIN
+    fn answer(request: &[u8]) -> Vec<u8> {
+        let request = Message::from_vec(request).unwrap();
+        let query = request.queries()[0].clone();
+        let records = [
+            Record::from_rdata(
+                Name::from_str("www.test.").unwrap(),
+                300,
+                RData::CNAME(Name::from_str("web.test.").unwrap()),
+            ),
+            Record::from_rdata(
+                Name::from_str("web.test.").unwrap(),
+                60,
+                RData::A(Ipv4Addr::new(10, 0, 0, 5)),
+            ),
+            Record::from_rdata(
+                Name::from_str("test.").unwrap(),
+                3600,
+                RData::MX(MX::new(10, Name::from_str("mail.test.").unwrap())),
+            ),
+        ];
+        let mut response = Message::new();
+        response
+            .set_id(request.id())
+            .set_message_type(MessageType::Response)
+            .set_recursion_available(true)
+            .add_query(query.clone());
+        if query.name().to_string() == "www.test." || query.query_type() == RecordType::MX {
+            response.add_answers(records.into_iter().filter(|record| {
+                query.query_type() == RecordType::MX || record.record_type() != RecordType::MX
+            }));
+        } else {
+            response.set_response_code(ResponseCode::NXDomain);
+        }
+        response.to_vec().unwrap()
+    }
+
+    fn udp_nameserver() -> SocketAddr {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let address = socket.local_addr().unwrap();
+        thread::spawn(move || {
+            let mut buffer = [0; 512];
+            while let Ok((size, client)) = socket.recv_from(&mut buffer) {
+                let _ = socket.send_to(&answer(&buffer[..size]), client);
+            }
+        });
+        address
+    }
+
+    /// Messages over TCP are prefixed with their length
+    fn tcp_nameserver() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut length = [0; 2];
+                if stream.read_exact(&mut length).is_err() {
+                    continue;
+                }
+                let mut request = vec![0; u16::from_be_bytes(length) as usize];
+                let _ = stream.read_exact(&mut request);
+                let response = answer(&request);
+                let _ = stream.write_all(&(response.len() as u16).to_be_bytes());
+                let _ = stream.write_all(&response);
+            }
+        });
+        address
+    }
+
+    #[test]
+    fn answers_are_reported_and_asserted() {
+        let nameserver = udp_nameserver();
+        let probe = |name: &str, expected: &str| Dns {
+            nameserver: Some(nameserver),
+            expected: vec![expected.to_string()],
+            ..Dns::new(name.to_string(), &GO)
+        };
+
+        let report = probe("www.test", "10.0.0.5").execute().unwrap();
+        assert_eq!(
+            ("Nameserver".to_string(), format!("{} (udp)", nameserver)),
+            report.data[0]
+        );
+        assert!(report.data[1].1.ends_with(" ms"));
+        assert_eq!(
+            vec!["www.test. 300 CNAME web.test.", "web.test. 60 A 10.0.0.5"],
+            report.data[2..]
+                .iter()
+                .map(|(_, answer)| answer.as_str())
+                .collect::<Vec<_>>()
+        );
+
+        assert_matches!(
+            probe("www.test", "10.0.0.6").execute(),
+            Err(AssertionMatchingError(desc, _)) if desc == "Expected '10.0.0.6' in the answer but got 'web.test., 10.0.0.5'"
+        );
+        assert_matches!(
+            probe("unknown.test", "10.0.0.5").execute(),
+            Err(AssertionMatchingError(desc, _)) if desc.starts_with("No A records found for 'unknown.test'")
+        );
+    }
+
+    #[test]
+    fn queries_over_tcp() {
+        let probe = Dns {
+            record_type: RecordType::MX,
+            nameserver: Some(tcp_nameserver()),
+            protocol: Protocol::Tcp,
+            expected: vec!["10 MAIL.test".to_string()],
+            ..Dns::new("test".to_string(), &GO)
+        };
+
+        let report = probe.execute().unwrap();
+
+        assert_eq!("test. 3600 MX 10 mail.test.", report.data[2].1);
+    }
+}
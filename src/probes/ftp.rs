@@ -0,0 +1,534 @@
+use std::io;
+use std::io::{BufReader, ErrorKind, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpStream};
+use std::sync::Arc;
+use std::time::Instant;
+
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use rustls::ClientConfig;
+use secrecy::{ExposeSecret, SecretString};
+
+use crate::error::InquestError::{AssertionMatchingError, FailedExecutionError};
+use crate::probes::sftp;
+use crate::probes::stream::{
+    command_reply, connect, expect, read_reply, step, text, upgrade, Failure, Reply, Stream,
+};
+use crate::probes::tls::{certificate_mismatch, client_config, PeerCertificates};
+use crate::{Certificates, Data, FileTransfer, Ftp, GlobalOptions, Probe, ProbeReport, Result};
+
+/// Longer listings are cut off in the report
+const MAX_LISTED: usize = 20;
+
+impl Ftp {
+    pub(crate) fn new(
+        host: String,
+        protocol: FileTransfer,
+        user: String,
+        password: SecretString,
+        certs: Option<Certificates>,
+        options: &'static GlobalOptions,
+    ) -> Ftp {
+        Ftp {
+            host,
+            port: match protocol {
+                FileTransfer::Ftp | FileTransfer::ExplicitFtps => 21,
+                FileTransfer::ImplicitFtps => 990,
+                FileTransfer::Sftp => 22,
+            },
+            protocol,
+            user,
+            password,
+            directory: None,
+            upload: false,
+            fingerprint: None,
+            tls_test: None,
+            timeout: options.timeout,
+            certs,
+        }
+    }
+
+    /// The name and content of the file to upload, the name is random so that probes running
+    /// in parallel do not interfere
+    pub(super) fn test_file(&self) -> (String, String) {
+        let suffix = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(8)
+            .map(char::from)
+            .collect::<String>();
+        (
+            format!("inquest-{}.txt", suffix),
+            format!("Written by inquest to test the upload to {}\n", self.host),
+        )
+    }
+}
+
+/// Logs in on the control connection, and opens a passive data connection for each transfer.
+/// A failing data connection is reported on its own, since firewalls often only let the control
+/// connection pass.
+impl Probe for Ftp {
+    fn execute(&self) -> Result<ProbeReport> {
+        let peer_certificates = PeerCertificates::default();
+        let config = match self.protocol {
+            FileTransfer::ExplicitFtps | FileTransfer::ImplicitFtps => Some(Arc::new(
+                client_config(
+                    self.certs.as_ref(),
+                    self.tls_test.as_ref(),
+                    peer_certificates.clone(),
+                )
+                .map_err(|e| FailedExecutionError {
+                    probe_identifier: self.identifier(),
+                    source: Box::new(e),
+                })?,
+            )),
+            FileTransfer::Ftp | FileTransfer::Sftp => None,
+        };
+        let mut report = ProbeReport::new(self.identifier());
+        let transfer = match self.protocol {
+            FileTransfer::Sftp => sftp::transfer(self, &mut report.data),
+            _ => self.transfer(config, &mut report.data),
+        };
+        match transfer {
+            Ok(()) => Ok(report),
+            Err(Failure::Refused(desc)) => Err(certificate_mismatch(
+                self.identifier(),
+                self.tls_test.as_ref(),
+                &peer_certificates,
+            )
+            .unwrap_or(AssertionMatchingError(desc, report))),
+            Err(Failure::Io(e)) => Err(FailedExecutionError {
+                probe_identifier: self.identifier(),
+                source: Box::new(e),
+            }),
+        }
+    }
+
+    fn identifier(&self) -> String {
+        let name = match self.protocol {
+            FileTransfer::Ftp => "FTP",
+            FileTransfer::ExplicitFtps | FileTransfer::ImplicitFtps => "FTPS",
+            FileTransfer::Sftp => "SFTP",
+        };
+        format!("{} - {}@{}:{}", name, self.user, self.host, self.port)
+    }
+}
+
+impl Ftp {
+    fn transfer(
+        &self,
+        config: Option<Arc<ClientConfig>>,
+        data: &mut Data,
+    ) -> std::result::Result<(), Failure> {
+        let started = Instant::now();
+        let socket = connect(&self.host, self.port, self.timeout)?;
+        let peer = socket.peer_addr()?;
+        data.push((
+            "Connection".to_string(),
+            format!(
+                "{} connected in {:.1} ms",
+                peer,
+                started.elapsed().as_secs_f64() * 1000.0
+            ),
+        ));
+        let mut stream = Stream::Plain(socket);
+        if let (FileTransfer::ImplicitFtps, Some(config)) = (self.protocol, &config) {
+            stream = upgrade(stream, &self.host, config.clone(), data)?;
+        }
+        let mut control = BufReader::new(stream);
+        let banner = read_reply(&mut control)?;
+        data.push(("Banner".to_string(), banner.to_string()));
+        expect(&banner, &[220], "The connection")?;
+        if let (FileTransfer::ExplicitFtps, Some(config)) = (self.protocol, &config) {
+            step(&mut control, "AUTH TLS", "AUTH TLS", &[234], data)?;
+            let stream = upgrade(control.into_inner(), &self.host, config.clone(), data)?;
+            control = BufReader::new(stream);
+        }
+
+        let reply = command_reply(&mut control, &format!("USER {}", self.user))?;
+        let reply = match reply.code {
+            331 => command_reply(
+                &mut control,
+                &format!("PASS {}", self.password.expose_secret()),
+            )?,
+            _ => reply,
+        };
+        data.push(("Login".to_string(), reply.to_string()));
+        if reply.code != 230 {
+            return Err(Failure::Refused(format!(
+                "Login as '{}' failed: {}",
+                self.user,
+                text(&reply)
+            )));
+        }
+        if config.is_some() {
+            // the data connections are encrypted as well
+            step(&mut control, "PBSZ 0", "PBSZ", &[200], data)?;
+            step(&mut control, "PROT P", "PROT", &[200], data)?;
+        }
+        if let Some(directory) = &self.directory {
+            step(
+                &mut control,
+                &format!("CWD {}", directory),
+                "CWD",
+                &[250],
+                data,
+            )?;
+        }
+        let directory = command_reply(&mut control, "PWD")?
+            .lines
+            .first()
+            .and_then(|line| line.split('"').nth(1).map(str::to_string))
+            .unwrap_or_else(|| ".".to_string());
+
+        let passive = Passive {
+            ip: peer.ip(),
+            config: config.as_ref(),
+        };
+        let listing = self.data_transfer(&mut control, &passive, "LIST", None, data)?;
+        data.push((
+            format!("Listing {}", directory),
+            listed(
+                String::from_utf8_lossy(&listing)
+                    .lines()
+                    .map(str::to_string),
+            ),
+        ));
+
+        if self.upload {
+            let (name, content) = self.test_file();
+            let started = Instant::now();
+            let command = format!("STOR {}", name);
+            let upload = Some(content.as_bytes());
+            self.data_transfer(&mut control, &passive, &command, upload, data)?;
+            data.push((
+                "Upload".to_string(),
+                format!(
+                    "{} with {} bytes in {:.1} ms",
+                    name,
+                    content.len(),
+                    started.elapsed().as_secs_f64() * 1000.0
+                ),
+            ));
+            step(
+                &mut control,
+                &format!("DELE {}", name),
+                "Delete",
+                &[250],
+                data,
+            )?;
+        }
+        let quit = command_reply(&mut control, "QUIT")?;
+        data.push(("QUIT".to_string(), quit.to_string()));
+        Ok(())
+    }
+
+    /// Runs the command over a new passive data connection, which either receives the data or
+    /// uploads the given content
+    fn data_transfer(
+        &self,
+        control: &mut BufReader<Stream>,
+        passive: &Passive,
+        command: &str,
+        upload: Option<&[u8]>,
+        data: &mut Data,
+    ) -> std::result::Result<Vec<u8>, Failure> {
+        let invalid = |command| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid reply to {}", command),
+            )
+        };
+        let reply = command_reply(control, "EPSV")?;
+        // the advertised address of PASV is ignored, since it is often the internal one behind NAT
+        let port = match reply.code {
+            229 => extended_passive_port(&reply).ok_or_else(|| invalid("EPSV"))?,
+            // servers without RFC 2428 only know PASV, which has no form for IPv6
+            _ => {
+                let reply = command_reply(control, "PASV")?;
+                expect(&reply, &[227], "PASV")?;
+                passive_port(&reply).ok_or_else(|| invalid("PASV"))?
+            }
+        };
+        let address = SocketAddr::new(passive.ip, port);
+        let unreachable = |e: io::Error| {
+            Failure::Refused(format!(
+                "Passive data connection to {} failed: {}",
+                address, e
+            ))
+        };
+        let started = Instant::now();
+        let socket = TcpStream::connect_timeout(&address, self.timeout).map_err(unreachable)?;
+        socket.set_read_timeout(Some(self.timeout))?;
+        socket.set_write_timeout(Some(self.timeout))?;
+        data.push((
+            "Data connection".to_string(),
+            format!(
+                "{} connected in {:.1} ms",
+                address,
+                started.elapsed().as_secs_f64() * 1000.0
+            ),
+        ));
+
+        let reply = command_reply(control, command)?;
+        expect(&reply, &[125, 150], command)?;
+        let mut stream = Stream::Plain(socket);
+        if let Some(config) = passive.config {
+            stream = stream
+                .upgrade(&self.host, config.clone())
+                .map_err(unreachable)?;
+        }
+        let mut received = Vec::new();
+        let transferred = match upload {
+            Some(content) => stream.write_all(content).and_then(|_| stream.close()),
+            None => match stream.read_to_end(&mut received) {
+                // some servers close the encrypted connection without notification
+                Err(e) if e.kind() == ErrorKind::UnexpectedEof => Ok(()),
+                read => read.map(|_| ()),
+            },
+        };
+        transferred.map_err(|e| {
+            Failure::Refused(format!("Data transfer over {} failed: {}", address, e))
+        })?;
+        drop(stream);
+        let reply = read_reply(control)?;
+        expect(&reply, &[226, 250], command)?;
+        Ok(received)
+    }
+}
+
+/// Where the data connections go to, and whether they are encrypted
+struct Passive<'a> {
+    ip: IpAddr,
+    config: Option<&'a Arc<ClientConfig>>,
+}
+
+/// The entries of a listing, cut off after a few
+pub(super) fn listed(entries: impl Iterator<Item = String>) -> String {
+    let entries = entries.collect::<Vec<_>>();
+    match entries.len() {
+        0 => "empty".to_string(),
+        count if count > MAX_LISTED => format!(
+            "{}\n... and {} more",
+            entries[..MAX_LISTED].join("\n"),
+            count - MAX_LISTED
+        ),
+        _ => entries.join("\n"),
+    }
+}
+
+/// The port of a reply like `227 Entering Passive Mode (192,168,1,2,195,80).`
+fn passive_port(reply: &Reply) -> Option<u16> {
+    let line = reply.lines.first()?;
+    let (_, address) = line.split_once('(')?;
+    let (address, _) = address.split_once(')')?;
+    let numbers = address
+        .split(',')
+        .map(|number| number.trim().parse::<u8>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    match numbers.as_slice() {
+        [_, _, _, _, high, low] => Some(u16::from(*high) << 8 | u16::from(*low)),
+        _ => None,
+    }
+}
+
+/// The port of a reply like `229 Entering Extended Passive Mode (|||6446|)`, which leaves out the
+/// address
+fn extended_passive_port(reply: &Reply) -> Option<u16> {
+    let line = reply.lines.first()?;
+    let (_, address) = line.split_once('(')?;
+    let (address, _) = address.split_once(')')?;
+    let delimiter = address.chars().next()?;
+    match address.split(delimiter).collect::<Vec<_>>().as_slice() {
+        ["", "", "", port, ""] => port.parse().ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use secrecy::SecretString;
+
+    use crate::error::InquestError::AssertionMatchingError;
+    use crate::probes::ftp::{extended_passive_port, passive_port};
+    use crate::probes::stream::Reply;
+    use crate::{FileTransfer, Ftp, Probe, GO};
+
+    /// Accepts the user 'probe' with the password 'secret'. The passive port is closed when
+    /// `firewalled`, EPSV is only understood when `extended`.
+    fn ftp_server(ip: &str, firewalled: bool, extended: bool) -> u16 {
+        let listener = TcpListener::bind((ip, 0)).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let ip = listener.local_addr().unwrap().ip();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let mut writer = stream.try_clone().unwrap();
+                let mut reader = BufReader::new(stream);
+                writer
+                    .write_all(b"220-Welcome\r\n220 FTP ready\r\n")
+                    .unwrap();
+                let mut passive = None;
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap_or_default() > 0 {
+                    let command = line.trim_end().to_string();
+                    line.clear();
+                    let reply = match command.as_str() {
+                        "USER probe" => "331 Password required".to_string(),
+                        "PASS secret" => "230 Logged in".to_string(),
+                        "EPSV" if extended => {
+                            let data = TcpListener::bind((ip, 0)).unwrap();
+                            let port = data.local_addr().unwrap().port();
+                            passive = Some(data);
+                            format!("229 Entering Extended Passive Mode (|||{}|)", port)
+                        }
+                        "EPSV" => "500 Unknown command".to_string(),
+                        "PASV" if !extended => {
+                            let data = TcpListener::bind((ip, 0)).unwrap();
+                            let port = data.local_addr().unwrap().port();
+                            if !firewalled {
+                                passive = Some(data);
+                            }
+                            format!(
+                                "227 Entering Passive Mode (10,0,0,1,{},{})",
+                                port / 256,
+                                port % 256
+                            )
+                        }
+                        "LIST" => {
+                            let (mut data, _) = passive.take().unwrap().accept().unwrap();
+                            writer
+                                .write_all(b"150 Opening data connection\r\n")
+                                .unwrap();
+                            data.write_all(b"-rw-r--r-- 1 ftp ftp 42 report.csv\r\n")
+                                .unwrap();
+                            "226 Transfer complete".to_string()
+                        }
+                        stor if stor.starts_with("STOR ") => {
+                            let (mut data, _) = passive.take().unwrap().accept().unwrap();
+                            writer
+                                .write_all(b"150 Opening data connection\r\n")
+                                .unwrap();
+                            data.read_to_end(&mut Vec::new()).unwrap();
+                            "226 Transfer complete".to_string()
+                        }
+                        "PWD" => "257 \"/incoming\" is the current directory".to_string(),
+                        dele if dele.starts_with("DELE ") => "250 Deleted".to_string(),
+                        "QUIT" => "221 Goodbye".to_string(),
+                        _ => "530 Login incorrect".to_string(),
+                    };
+                    writer
+                        .write_all(format!("{}\r\n", reply).as_bytes())
+                        .unwrap();
+                }
+            }
+        });
+        port
+    }
+
+    fn probe(host: &str, port: u16) -> Ftp {
+        Ftp {
+            port,
+            upload: true,
+            ..Ftp::new(
+                host.to_string(),
+                FileTransfer::Ftp,
+                "probe".to_string(),
+                SecretString::new("secret".to_string()),
+                None,
+                &GO,
+            )
+        }
+    }
+
+    #[test]
+    fn listing_and_upload_are_reported() {
+        let report = probe("127.0.0.1", ftp_server("127.0.0.1", false, false))
+            .execute()
+            .unwrap();
+        let rows = report
+            .data
+            .iter()
+            .map(|(key, _)| key.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                "Connection",
+                "Banner",
+                "Login",
+                "Data connection",
+                "Listing /incoming",
+                "Data connection",
+                "Upload",
+                "Delete",
+                "QUIT"
+            ],
+            rows
+        );
+        assert_eq!("220 Welcome\nFTP ready", report.data[1].1);
+        assert_eq!("-rw-r--r-- 1 ftp ftp 42 report.csv", report.data[4].1);
+    }
+
+    #[test]
+    fn data_connection_failure_is_reported() {
+        assert_matches!(
+            probe("127.0.0.1", ftp_server("127.0.0.1", true, false)).execute(),
+            Err(AssertionMatchingError(desc, report))
+                if desc.starts_with("Passive data connection to 127.0.0.1:")
+                    && report.data[2].1 == "230 Logged in"
+        );
+    }
+
+    #[test]
+    fn extended_passive_mode_reaches_ipv6_servers() {
+        let report = probe("::1", ftp_server("::1", false, true))
+            .execute()
+            .unwrap();
+        assert_matches!(
+            report.data.iter().find(|(key, _)| key == "Data connection"),
+            Some((_, connection)) if connection.starts_with("[::1]:")
+        );
+        assert_eq!("-rw-r--r-- 1 ftp ftp 42 report.csv", report.data[4].1);
+    }
+
+    #[test]
+    fn malformed_passive_reply_is_rejected() {
+        let port = |line: &str| {
+            passive_port(&Reply {
+                code: 227,
+                lines: vec![line.to_string()],
+            })
+        };
+        assert_eq!(
+            Some(50000),
+            port("Entering Passive Mode (192,168,1,2,195,80).")
+        );
+        assert_eq!(None, port("Entering Passive Mode )192,168,1,2,195,80("));
+        assert_eq!(None, port("Entering Passive Mode (192,168,1,2,256,80)"));
+        assert_eq!(None, port("Entering Passive Mode (192,168,1,2,195)"));
+        assert_eq!(None, port("Entering Passive Mode"));
+
+        let extended = |line: &str| {
+            extended_passive_port(&Reply {
+                code: 229,
+                lines: vec![line.to_string()],
+            })
+        };
+        assert_eq!(
+            Some(6446),
+            extended("Entering Extended Passive Mode (|||6446|)")
+        );
+        assert_eq!(
+            Some(6446),
+            extended("Entering Extended Passive Mode (!!!6446!)")
+        );
+        assert_eq!(None, extended("Entering Extended Passive Mode (|||70000|)"));
+        assert_eq!(
+            None,
+            extended("Entering Extended Passive Mode (||::1|6446|)")
+        );
+        assert_eq!(None, extended("Entering Extended Passive Mode"));
+    }
+}
@@ -0,0 +1,443 @@
+use std::io;
+use std::io::ErrorKind;
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rustls::{AlertDescription, ClientConnection, ProtocolVersion, ServerName};
+
+use crate::error::InquestError::{AssertionMatchingError, FailedExecutionError};
+use crate::probes::stream::connect;
+use crate::probes::tls::{
+    certificate_mismatch, client_config, expiring_certificates, CertificateDetails,
+    PeerCertificates,
+};
+use crate::{Certificates, GlobalOptions, Probe, ProbeReport, Result, Tls};
+
+const PROBE_NAME: &str = "TLS";
+
+/// TLS 1.3 servers verify the client certificate after the handshake completed on the client
+/// side, so a rejection arrives as alert within this time.
+const POST_HANDSHAKE_WAIT: Duration = Duration::from_millis(250);
+
+impl Tls {
+    pub(crate) fn new(
+        host: String,
+        port: u16,
+        certs: Option<Certificates>,
+        options: &'static GlobalOptions,
+    ) -> Tls {
+        Tls {
+            host,
+            port,
+            server_name: None,
+            alpn: Vec::new(),
+            timeout: options.timeout,
+            certs,
+            certificate: None,
+            tls: None,
+        }
+    }
+}
+
+impl Probe for Tls {
+    fn execute<'a>(&self) -> Result<ProbeReport> {
+        let failed = |source| FailedExecutionError {
+            probe_identifier: self.identifier(),
+            source,
+        };
+        let server_name = self.server_name.as_deref().unwrap_or(&self.host);
+        let peer_certificates = PeerCertificates::default();
+        let mut config = client_config(
+            self.certs.as_ref(),
+            self.tls.as_ref(),
+            peer_certificates.clone(),
+        )
+        .map_err(|e| failed(Box::new(e)))?;
+        config.alpn_protocols = self
+            .alpn
+            .iter()
+            .map(|protocol| protocol.as_bytes().to_vec())
+            .collect();
+        let name = ServerName::try_from(server_name).map_err(|e| failed(Box::new(e)))?;
+        let mut connection =
+            ClientConnection::new(Arc::new(config), name).map_err(|e| failed(Box::new(e)))?;
+
+        let started = Instant::now();
+        let mut stream =
+            connect(&self.host, self.port, self.timeout).map_err(|e| failed(Box::new(e)))?;
+        let mut report = ProbeReport::new(self.identifier());
+        report.data.push((
+            "Connection".to_string(),
+            format!(
+                "{} connected in {:.1} ms from {}",
+                stream
+                    .peer_addr()
+                    .map_or("unknown".to_string(), |peer| peer.to_string()),
+                started.elapsed().as_secs_f64() * 1000.0,
+                stream
+                    .local_addr()
+                    .map_or("unknown".to_string(), |local| local.to_string())
+            ),
+        ));
+
+        let started = Instant::now();
+        let result = handshake(&mut connection, &mut stream);
+        let chain = peer_certificates
+            .lock()
+            .map(|chain| {
+                chain
+                    .iter()
+                    .filter_map(|cert| CertificateDetails::try_from(cert).ok())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        if let Err(e) = result {
+            if let Some(mismatch) =
+                certificate_mismatch(self.identifier(), self.tls.as_ref(), &peer_certificates)
+            {
+                return Err(mismatch);
+            }
+            for (index, details) in chain.iter().enumerate() {
+                report
+                    .data
+                    .push((format!("Certificate #{}", index), details.to_string()));
+            }
+            return Err(AssertionMatchingError(
+                format!(
+                    "TLS handshake with '{}' failed: {}",
+                    server_name,
+                    describe(&e, server_name)
+                ),
+                report,
+            ));
+        }
+
+        report.data.push((
+            "Handshake".to_string(),
+            format!(
+                "completed in {:.1} ms",
+                started.elapsed().as_secs_f64() * 1000.0
+            ),
+        ));
+        report.data.push((
+            "Protocol version".to_string(),
+            connection
+                .protocol_version()
+                .map_or("unknown".to_string(), |version| {
+                    format!("{:?}", version).replace('_', ".")
+                }),
+        ));
+        report.data.push((
+            "Cipher suite".to_string(),
+            connection
+                .negotiated_cipher_suite()
+                .map_or("unknown".to_string(), |suite| {
+                    format!("{:?}", suite.suite())
+                }),
+        ));
+        report.data.push((
+            "ALPN".to_string(),
+            connection
+                .alpn_protocol()
+                .map_or("none".to_string(), |protocol| {
+                    String::from_utf8_lossy(protocol).to_string()
+                }),
+        ));
+        for (index, details) in chain.iter().enumerate() {
+            report
+                .data
+                .push((format!("Certificate #{}", index), details.to_string()));
+        }
+
+        let failed_assertions = expiring_certificates(&chain, self.certificate.as_ref());
+        if failed_assertions.is_empty() {
+            Ok(report)
+        } else {
+            Err(AssertionMatchingError(failed_assertions.join("; "), report))
+        }
+    }
+
+    fn identifier(&self) -> String {
+        format!("{} - {}:{}", PROBE_NAME, self.host, self.port)
+    }
+}
+
+/// Completes the handshake, and waits briefly for a rejection of the client certificate with
+/// TLS 1.3, before the connection is closed.
+fn handshake(connection: &mut ClientConnection, stream: &mut TcpStream) -> io::Result<()> {
+    while connection.is_handshaking() {
+        connection.complete_io(stream)?;
+    }
+    // the last flight of the client is still buffered once the handshake completed
+    while connection.wants_write() {
+        connection.write_tls(stream)?;
+    }
+    if connection.protocol_version() == Some(ProtocolVersion::TLSv1_3) {
+        stream.set_read_timeout(Some(POST_HANDSHAKE_WAIT))?;
+        match connection.read_tls(stream) {
+            Ok(0) => {
+                return Err(io::Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "connection closed by the server after the handshake",
+                ))
+            }
+            Ok(_) => {
+                connection
+                    .process_new_packets()
+                    .map_err(|e| io::Error::new(ErrorKind::InvalidData, e))?;
+            }
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    connection.send_close_notify();
+    let _ = connection.complete_io(stream);
+    Ok(())
+}
+
+/// Names the reason of a failed handshake, since webpki and the alerts only give a short code
+pub(super) fn describe(error: &io::Error, server_name: &str) -> String {
+    let Some(error) = error
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<rustls::Error>())
+    else {
+        return error.to_string();
+    };
+    match error {
+        rustls::Error::InvalidCertificateData(reason) => {
+            let code = reason.rsplit(' ').next().unwrap_or_default();
+            let description = match code {
+                "UnknownIssuer" => "certificate is not issued by a trusted CA".to_string(),
+                "CertExpired" => "certificate has expired".to_string(),
+                "CertNotValidYet" => "certificate is not valid yet".to_string(),
+                "CertNotValidForName" => {
+                    format!("certificate is not valid for '{}'", server_name)
+                }
+                "CaUsedAsEndEntity" => "a CA certificate is used as server certificate".to_string(),
+                _ => return reason.to_string(),
+            };
+            format!("{} ({})", description, code)
+        }
+        rustls::Error::AlertReceived(alert) => {
+            let description = match alert {
+                AlertDescription::CertificateRequired => "server requires a client certificate",
+                AlertDescription::BadCertificate
+                | AlertDescription::UnknownCA
+                | AlertDescription::CertificateUnknown
+                | AlertDescription::AccessDenied => "server rejected the client certificate",
+                AlertDescription::HandshakeFailure => "server rejected the offered parameters",
+                AlertDescription::ProtocolVersion => "server does not support the offered versions",
+                AlertDescription::NoApplicationProtocol => {
+                    "server supports none of the offered ALPN protocols"
+                }
+                _ => "server aborted the handshake",
+            };
+            format!("{} (alert {:?})", description, alert)
+        }
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+    use std::sync::Arc;
+    use std::thread;
+
+    use rustls::server::AllowAnyAuthenticatedClient;
+    use rustls::{Certificate, PrivateKey, RootCertStore, ServerConfig, ServerConnection};
+
+    use crate::error::InquestError::{
+        AssertionMatchingError, CertificateMismatchError, FailedExecutionError,
+    };
+    use crate::{Certificates, Probe, Tls, TlsTest, GO};
+
+    const CA: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/tls/ca.crt");
+
+    /// Accepts handshakes with the certificate for 'localhost', offering ALPN 'imap'
+    fn tls_server(client_auth: bool) -> u16 {
+        let chain = rustls_pemfile::certs(
+            &mut include_bytes!("../../testdata/tls/localhost.crt").as_slice(),
+        )
+        .unwrap()
+        .into_iter()
+        .map(Certificate)
+        .collect();
+        let key = rustls_pemfile::pkcs8_private_keys(
+            &mut include_bytes!("../../testdata/tls/localhost.key").as_slice(),
+        )
+        .unwrap()
+        .remove(0);
+        let builder = ServerConfig::builder().with_safe_defaults();
+        let builder = if client_auth {
+            let mut roots = RootCertStore::empty();
+            roots.add_parsable_certificates(
+                &rustls_pemfile::certs(&mut include_bytes!("../../testdata/tls/ca.crt").as_slice())
+                    .unwrap(),
+            );
+            builder.with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots))
+        } else {
+            builder.with_no_client_auth()
+        };
+        let mut config = builder.with_single_cert(chain, PrivateKey(key)).unwrap();
+        config.alpn_protocols = vec![b"imap".to_vec()];
+        let config = Arc::new(config);
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut connection = ServerConnection::new(config.clone()).unwrap();
+                while connection.is_handshaking() {
+                    if connection.complete_io(&mut stream).is_err() {
+                        // sends the alert
+                        let _ = connection.write_tls(&mut stream);
+                        break;
+                    }
+                }
+                let _ = connection.complete_io(&mut stream);
+            }
+        });
+        port
+    }
+
+    fn trusting_ca() -> Option<Certificates> {
+        Some(Certificates::new(
+            None,
+            None,
+            None,
+            None,
+            None,
+            Some(CA.to_string()),
+        ))
+    }
+
+    #[test]
+    fn handshake_is_reported() {
+        let probe = Tls {
+            alpn: vec!["imap".to_string()],
+            ..Tls::new(
+                "localhost".to_string(),
+                tls_server(false),
+                trusting_ca(),
+                &GO,
+            )
+        };
+
+        let report = probe.execute().unwrap();
+
+        let value = |key: &str| {
+            report
+                .data
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, value)| value.as_str())
+        };
+        assert_eq!(Some("TLSv1.3"), value("Protocol version"));
+        assert!(value("Cipher suite").unwrap().starts_with("TLS13_"));
+        assert_eq!(Some("imap"), value("ALPN"));
+        assert!(value("Certificate #0")
+            .unwrap()
+            .starts_with("Subject: CN=localhost"));
+    }
+
+    #[test]
+    fn missing_files_are_attributed_to_the_probe() {
+        let probe = Tls::new(
+            "localhost".to_string(),
+            443,
+            Some(Certificates::new(
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some("does-not-exist.crt".to_string()),
+            )),
+            &GO,
+        );
+
+        assert_matches!(
+            probe.execute(),
+            Err(FailedExecutionError { probe_identifier, .. }) if probe_identifier == "TLS - localhost:443"
+        );
+
+        let probe = Tls::new(
+            "localhost".to_string(),
+            443,
+            Some(Certificates::new(
+                None,
+                None,
+                None,
+                Some("does-not-exist.p12".to_string()),
+                None,
+                None,
+            )),
+            &GO,
+        );
+        assert_matches!(
+            probe.execute(),
+            Err(FailedExecutionError { probe_identifier, source })
+                if probe_identifier == "TLS - localhost:443"
+                    && source.to_string().contains("'does-not-exist.p12'")
+        );
+    }
+
+    #[test]
+    fn root_is_pinned_although_not_presented() {
+        let port = tls_server(false);
+        let pinned = |pin: &str| Tls {
+            tls: Some(TlsTest {
+                pin_sha256: vec![pin.to_string()],
+                expected_issuer: None,
+            }),
+            ..Tls::new("localhost".to_string(), port, trusting_ca(), &GO)
+        };
+
+        let report = pinned("sha256//Bgc+1/BgEXozqmfTBGHIdHB+nLhoWxJFa8BAkxv3cxA=")
+            .execute()
+            .unwrap();
+        assert!(report
+            .data
+            .iter()
+            .any(|(key, value)| key == "Certificate #1"
+                && value.starts_with("Subject: CN=inquest-test-ca")));
+        assert_matches!(
+            pinned("sha256//AAAA1/BgEXozqmfTBGHIdHB+nLhoWxJFa8BAkxv3cxA=").execute(),
+            Err(CertificateMismatchError { .. })
+        );
+    }
+
+    #[test]
+    fn verification_errors_are_named() {
+        let port = tls_server(false);
+        let untrusted = Tls::new("localhost".to_string(), port, None, &GO);
+        let wrong_name = Tls {
+            server_name: Some("ldap.corp".to_string()),
+            ..Tls::new("localhost".to_string(), port, trusting_ca(), &GO)
+        };
+        let without_client_certificate = Tls::new(
+            "localhost".to_string(),
+            tls_server(true),
+            trusting_ca(),
+            &GO,
+        );
+
+        assert_matches!(
+            untrusted.execute(),
+            Err(AssertionMatchingError(desc, report))
+                if desc == "TLS handshake with 'localhost' failed: certificate is not issued by a trusted CA (UnknownIssuer)"
+                    && report.data[1].0 == "Certificate #0"
+        );
+        assert_matches!(
+            wrong_name.execute(),
+            Err(AssertionMatchingError(desc, _)) if desc.ends_with("certificate is not valid for 'ldap.corp' (CertNotValidForName)")
+        );
+        assert_matches!(
+            without_client_certificate.execute(),
+            Err(AssertionMatchingError(desc, _)) if desc.ends_with("server requires a client certificate (alert CertificateRequired)")
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use serde_json::{Map, Value};
+
+use crate::error::InquestError::AssertionMatchingError;
+use crate::probes::http::exchange;
+use crate::{Health, Probe, ProbeReport, Result};
+
+const PROBE_NAME: &str = "Health";
+
+/// Statuses of Spring Boot Actuator which make the endpoint respond with '503 Service Unavailable'
+const FAILED_STATUSES: &[&str] = &["DOWN", "OUT_OF_SERVICE"];
+
+impl Probe for Health {
+    fn execute<'a>(&self) -> Result<ProbeReport> {
+        let exchange = exchange(&self.http, self.identifier(), None, true)?;
+        let body = exchange.text();
+        let mut report = ProbeReport {
+            probe_identifier: self.identifier(),
+            body: exchange.preview(&self.http),
+            data: exchange.data,
+        };
+
+        // the components explain a failed status, so they are reported first
+        let mut failed_assertions = match serde_json::from_str::<Value>(&body) {
+            Ok(document) if document["status"].is_string() => {
+                let (components, failures) = evaluate(&document);
+                report.data.extend(components);
+                failures
+            }
+            _ => vec!["Response is not a health document with a 'status'".to_string()],
+        };
+        failed_assertions.extend(exchange.failed_assertions);
+
+        if failed_assertions.is_empty() {
+            Ok(report)
+        } else {
+            Err(AssertionMatchingError(failed_assertions.join("; "), report))
+        }
+    }
+
+    fn identifier(&self) -> String {
+        format!("{} - {}", PROBE_NAME, self.http.url)
+    }
+}
+
+/// Reports the overall status and every component, nested components are named like
+/// `db.primary`. Returns the report-rows and the failures.
+fn evaluate(document: &Value) -> (Vec<(String, String)>, Vec<String>) {
+    let status = document["status"].as_str().unwrap_or_default();
+    let mut data = vec![("Status".to_string(), status.to_string())];
+    let mut failures = Vec::new();
+    collect_components(None, document, &mut data, &mut failures);
+
+    // without exposed components, only the overall status explains the failure
+    if failures.is_empty() && FAILED_STATUSES.contains(&status) {
+        failures.push(format!("Health status is {}", status));
+    }
+    (data, failures)
+}
+
+fn collect_components(
+    path: Option<&str>,
+    node: &Value,
+    data: &mut Vec<(String, String)>,
+    failures: &mut Vec<String>,
+) {
+    let Some(components) = components(node) else {
+        return;
+    };
+    for (name, component) in components {
+        let name = match path {
+            Some(path) => format!("{}.{}", path, name),
+            None => name.clone(),
+        };
+        let status = component["status"].as_str().unwrap_or_default();
+        let details = match component.get("details") {
+            Some(Value::Object(details)) if components_of(details).is_none() => {
+                details.iter().collect::<Vec<_>>()
+            }
+            _ => Vec::new(),
+        };
+
+        let mut row = status.to_string();
+        for (key, value) in &details {
+            row.push_str(&format!("\n{}: {}", key, plain(value)));
+        }
+        data.push((format!("Component '{}'", name), row));
+
+        // a failing composite is explained by its failing children
+        let failures_before = failures.len();
+        collect_components(Some(&name), component, data, failures);
+        if FAILED_STATUSES.contains(&status) && failures.len() == failures_before {
+            let mut failure = format!("Component '{}' is {}", name, status);
+            if let Some(error) = component["details"]["error"].as_str() {
+                failure.push_str(&format!(": {}", error));
+            }
+            failures.push(failure);
+        }
+    }
+}
+
+/// Nested components are found below `components`, or below `details` before Spring Boot 2.2
+fn components(node: &Value) -> Option<&Map<String, Value>> {
+    match (&node["components"], &node["details"]) {
+        (Value::Object(components), _) => Some(components),
+        (_, Value::Object(details)) => components_of(details),
+        _ => None,
+    }
+}
+
+fn components_of(details: &Map<String, Value>) -> Option<&Map<String, Value>> {
+    let all_components = !details.is_empty()
+        && details
+            .values()
+            .all(|value| value.is_object() && value["status"].is_string());
+    all_components.then_some(details)
+}
+
+fn plain(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::probes::health::evaluate;
+
+    #[test]
+    fn components_are_reported_and_down_fails() {
+        let document = json!({
+            "status": "DOWN",
+            "components": {
+                "db": {
+                    "status": "DOWN",
+                    "details": { "database": "PostgreSQL", "error": "Connection refused" }
+                },
+                "diskSpace": { "status": "UP", "details": { "free": 1024 } },
+                "kafka": {
+                    "status": "DOWN",
+                    "components": { "primary": { "status": "OUT_OF_SERVICE" } }
+                }
+            }
+        });
+
+        let (data, failures) = evaluate(&document);
+
+        assert_eq!(
+            vec![
+                ("Status".to_string(), "DOWN".to_string()),
+                (
+                    "Component 'db'".to_string(),
+                    "DOWN\ndatabase: PostgreSQL\nerror: Connection refused".to_string()
+                ),
+                (
+                    "Component 'diskSpace'".to_string(),
+                    "UP\nfree: 1024".to_string()
+                ),
+                ("Component 'kafka'".to_string(), "DOWN".to_string()),
+                (
+                    "Component 'kafka.primary'".to_string(),
+                    "OUT_OF_SERVICE".to_string()
+                ),
+            ],
+            data
+        );
+        assert_eq!(
+            vec![
+                "Component 'db' is DOWN: Connection refused".to_string(),
+                "Component 'kafka.primary' is OUT_OF_SERVICE".to_string()
+            ],
+            failures
+        );
+    }
+
+    #[test]
+    fn legacy_details_are_components() {
+        let document = json!({
+            "status": "UP",
+            "details": { "db": { "status": "UP", "details": { "database": "H2" } } }
+        });
+
+        let (data, failures) = evaluate(&document);
+
+        assert_eq!(
+            ("Component 'db'".to_string(), "UP\ndatabase: H2".to_string()),
+            data[1]
+        );
+        assert!(failures.is_empty());
+    }
+}
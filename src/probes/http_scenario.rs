@@ -0,0 +1,262 @@
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use std::time::Instant;
+
+use regex::{Captures, Regex};
+use reqwest::cookie::Jar;
+use reqwest::header::HeaderMap;
+use secrecy::{ExposeSecret, SecretString};
+use serde_json::Value;
+use url::Url;
+
+use crate::error::InquestError::{AssertionMatchingError, FailedExecutionError};
+use crate::probes::http::exchange;
+use crate::{Capture, CaptureSource, Http, HttpScenario, HttpStep, Probe, ProbeReport, Result};
+
+const PROBE_NAME: &str = "HTTP Scenario";
+
+/// Matches placeholders like `{{token}}`. Double braces are used, since HOCON itself uses `${}`.
+const PLACEHOLDER: &str = r"\{\{\s*([\w.-]+)\s*\}\}";
+
+impl Probe for HttpScenario {
+    fn execute<'a>(&self) -> Result<ProbeReport> {
+        // all steps share the cookies, so a session created by a login is used in later steps
+        let cookies = Arc::new(Jar::default());
+        let mut variables = self.variables.clone();
+        let mut report = ProbeReport::new(self.identifier());
+
+        for (index, step) in self.steps.iter().enumerate() {
+            let step_name = match &step.http.name {
+                Some(name) => format!("Step {} '{}'", index + 1, name),
+                None => format!("Step {}", index + 1),
+            };
+            let step_identifier = format!("{} ({})", self.identifier(), step_name);
+            let http = render(&step.http, &variables).map_err(|e| FailedExecutionError {
+                probe_identifier: step_identifier.clone(),
+                source: Box::new(e),
+            })?;
+
+            let started = Instant::now();
+            let reads_body = step
+                .captures
+                .iter()
+                .any(|capture| !matches!(capture.source, CaptureSource::Header(_)));
+            let mut exchange = exchange(&http, step_identifier, Some(cookies.clone()), reads_body)?;
+            let body = exchange.text();
+            let mut failed_assertions = std::mem::take(&mut exchange.failed_assertions);
+            // later steps depend on the captured values, so nothing is captured from a failed step
+            let captured = if failed_assertions.is_empty() {
+                capture(&step.captures, &exchange.headers, &body).unwrap_or_else(|failure| {
+                    failed_assertions.push(failure);
+                    Vec::new()
+                })
+            } else {
+                Vec::new()
+            };
+
+            // captured values might be secrets, so only their names are reported
+            let mut summary = format!(
+                "{} {}\n{} in {} ms",
+                step.http.method,
+                template(&step.http.url),
+                exchange.status,
+                started.elapsed().as_millis()
+            );
+            if !captured.is_empty() {
+                let names = captured
+                    .iter()
+                    .map(|(name, _)| name.as_str())
+                    .collect::<Vec<_>>();
+                summary.push_str(&format!("\nCaptured: {}", names.join(", ")));
+            }
+            report.data.push((step_name.clone(), summary));
+
+            if !failed_assertions.is_empty() {
+                report.body = exchange.preview(&http);
+                return Err(AssertionMatchingError(
+                    format!("{}: {}", step_name, failed_assertions.join("; ")),
+                    report,
+                ));
+            }
+            for (name, value) in captured {
+                variables.retain(|(existing, _)| existing != &name);
+                variables.push((name, value));
+            }
+        }
+        Ok(report)
+    }
+
+    fn identifier(&self) -> String {
+        match (&self.name, self.steps.first()) {
+            (Some(name), _) => format!("{} - {}", PROBE_NAME, name),
+            (None, Some(step)) => format!("{} - {}", PROBE_NAME, template(&step.http.url)),
+            (None, None) => PROBE_NAME.to_string(),
+        }
+    }
+}
+
+impl HttpStep {
+    /// The names of all variables used within the url, the headers and the body of the request
+    pub(crate) fn placeholders(&self) -> Vec<String> {
+        let placeholder = Regex::new(PLACEHOLDER).unwrap();
+        let url = template(&self.http.url);
+        let texts = std::iter::once(url.as_str())
+            .chain(self.http.headers.iter().map(|(_, value)| value.as_str()))
+            .chain(self.http.body.as_deref());
+        texts
+            .flat_map(|text| {
+                placeholder
+                    .captures_iter(text)
+                    .map(|captures| captures[1].to_string())
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
+
+/// The url as configured. Parsing percent-encodes the braces of placeholders within the path.
+fn template(url: &Url) -> String {
+    url.as_str().replace("%7B%7B", "{{").replace("%7D%7D", "}}")
+}
+
+/// Replaces the placeholders with the values of the variables. Unknown placeholders are kept,
+/// but cannot occur since the parser only accepts variables that are known at that step.
+fn substitute(text: &str, variables: &[(String, SecretString)]) -> String {
+    let placeholder = Regex::new(PLACEHOLDER).unwrap();
+    placeholder
+        .replace_all(text, |captures: &Captures| {
+            variables
+                .iter()
+                .find(|(name, _)| name == &captures[1])
+                .map_or(captures[0].to_string(), |(_, value)| {
+                    value.expose_secret().to_string()
+                })
+        })
+        .to_string()
+}
+
+fn render(http: &Http, variables: &[(String, SecretString)]) -> Result<Http> {
+    let url = Url::parse(&substitute(&template(&http.url), variables))?;
+    let headers = http
+        .headers
+        .iter()
+        .map(|(name, value)| (name.clone(), substitute(value, variables)))
+        .collect();
+    let body = http.body.as_deref().map(|body| substitute(body, variables));
+    Ok(Http {
+        url,
+        headers,
+        body,
+        ..http.clone()
+    })
+}
+
+fn capture(
+    captures: &[Capture],
+    headers: &HeaderMap,
+    body: &str,
+) -> std::result::Result<Vec<(String, SecretString)>, String> {
+    captures
+        .iter()
+        .map(|capture| {
+            let value = match &capture.source {
+                CaptureSource::Json(pointer) => {
+                    serde_json::from_str::<Value>(body).ok().and_then(|json| {
+                        json.pointer(pointer).map(|value| match value {
+                            Value::String(text) => text.clone(),
+                            other => other.to_string(),
+                        })
+                    })
+                }
+                CaptureSource::Header(name) => headers
+                    .get(name)
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_string),
+                CaptureSource::Regex(regex) => regex
+                    .captures(body)
+                    .and_then(|captures| captures.get(1).or_else(|| captures.get(0)))
+                    .map(|matched| matched.as_str().to_string()),
+            };
+            value
+                .map(|value| (capture.name.clone(), SecretString::new(value)))
+                .ok_or(format!(
+                    "Unable to capture '{}' from {}",
+                    capture.name, capture.source
+                ))
+        })
+        .collect()
+}
+
+impl Display for CaptureSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CaptureSource::Json(pointer) => write!(f, "JSON pointer '{}'", pointer),
+            CaptureSource::Header(name) => write!(f, "header '{}'", name),
+            CaptureSource::Regex(regex) => write!(f, "regex '{}'", regex),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+    use reqwest::header::{HeaderMap, HeaderValue};
+    use secrecy::{ExposeSecret, SecretString};
+    use url::Url;
+
+    use crate::probes::http_scenario::{capture, render};
+    use crate::{Capture, CaptureSource, Http, GO};
+
+    #[test]
+    fn captured_values_are_substituted() {
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Request-Id", HeaderValue::from_static("42"));
+        let body =
+            r#"{"access_token": "s3cr3t", "user": {"id": 7}} <input name="csrf" value="abc">"#;
+        let captures = vec![
+            Capture {
+                name: "id".to_string(),
+                source: CaptureSource::Header("x-request-id".to_string()),
+            },
+            Capture {
+                name: "csrf".to_string(),
+                source: CaptureSource::Regex(Regex::new(r#"name="csrf" value="(\w+)""#).unwrap()),
+            },
+        ];
+        let mut variables = capture(&captures, &headers, body).unwrap();
+        variables.push(("token".to_string(), SecretString::new("s3cr3t".to_string())));
+
+        let http = Http {
+            headers: vec![("Authorization".to_string(), "Bearer {{token}}".to_string())],
+            body: Some("csrf={{ csrf }}".to_string()),
+            ..Http::new(
+                Url::parse("https://foo.bar/orders/{{id}}").unwrap(),
+                None,
+                None,
+                &GO,
+                None,
+            )
+        };
+        let rendered = render(&http, &variables).unwrap();
+
+        assert_eq!("https://foo.bar/orders/42", rendered.url.as_str());
+        assert_eq!("Bearer s3cr3t", rendered.headers[0].1);
+        assert_eq!(Some("csrf=abc".to_string()), rendered.body);
+
+        let json = Capture {
+            name: "user".to_string(),
+            source: CaptureSource::Json("/user/id".to_string()),
+        };
+        let user = capture(&[json], &headers, r#"{"user": {"id": 7}}"#).unwrap();
+        assert_eq!("7", user[0].1.expose_secret());
+
+        let missing = Capture {
+            name: "missing".to_string(),
+            source: CaptureSource::Json("/missing".to_string()),
+        };
+        assert_eq!(
+            Err("Unable to capture 'missing' from JSON pointer '/missing'".to_string()),
+            capture(&[missing], &headers, "{}").map(|_| ())
+        );
+    }
+}
@@ -0,0 +1,659 @@
+use std::io;
+use std::io::{ErrorKind, Read, Write};
+use std::sync::Arc;
+use std::time::Instant;
+
+use rustls::ClientConfig;
+use secrecy::ExposeSecret;
+
+use crate::error::InquestError::{AssertionMatchingError, FailedExecutionError};
+use crate::probes::scram::{ScramClient, ScramHash};
+use crate::probes::stream::{connect, Stream};
+use crate::probes::tls::{certificate_mismatch, client_config, PeerCertificates};
+use crate::{
+    Certificates, Data, GlobalOptions, Kafka, KafkaSasl, KafkaTopic, Probe, ProbeReport, Result,
+    SaslMechanism,
+};
+
+const PROBE_NAME: &str = "Kafka";
+
+const CLIENT_ID: &str = "inquest";
+
+/// Version 4 is the first one which allows to disable the automatic creation of topics, it is
+/// supported since Kafka 1.0
+const METADATA: (i16, i16) = (3, 4);
+const SASL_HANDSHAKE: (i16, i16) = (17, 1);
+const SASL_AUTHENTICATE: (i16, i16) = (36, 0);
+
+const UNKNOWN_TOPIC_OR_PARTITION: i16 = 3;
+const UNSUPPORTED_SASL_MECHANISM: i16 = 33;
+
+/// Responses are small as long as the metadata of a single topic is requested, anything larger
+/// is most likely not spoken by Kafka
+const MAX_RESPONSE_SIZE: usize = 16 * 1024 * 1024;
+
+impl Kafka {
+    pub(crate) fn new(
+        bootstrap_servers: Vec<(String, u16)>,
+        certs: Option<Certificates>,
+        options: &'static GlobalOptions,
+    ) -> Kafka {
+        Kafka {
+            bootstrap_servers,
+            tls: false,
+            tls_test: None,
+            sasl: None,
+            timeout: options.timeout,
+            certs,
+            topic: None,
+        }
+    }
+}
+
+impl SaslMechanism {
+    fn name(self) -> &'static str {
+        match self {
+            SaslMechanism::Plain => "PLAIN",
+            SaslMechanism::ScramSha256 => "SCRAM-SHA-256",
+            SaslMechanism::ScramSha512 => "SCRAM-SHA-512",
+        }
+    }
+}
+
+/// Fetches the metadata from the first bootstrap server which answers, and then connects to every
+/// broker with the address it advertises, since this is what clients do as well.
+impl Probe for Kafka {
+    fn execute<'a>(&self) -> Result<ProbeReport> {
+        let peer_certificates = PeerCertificates::default();
+        let config = match self.tls {
+            true => Some(Arc::new(
+                client_config(
+                    self.certs.as_ref(),
+                    self.tls_test.as_ref(),
+                    peer_certificates.clone(),
+                )
+                .map_err(|e| FailedExecutionError {
+                    probe_identifier: self.identifier(),
+                    source: Box::new(e),
+                })?,
+            )),
+            false => None,
+        };
+        let topics = self
+            .topic
+            .as_ref()
+            .map(|topic| vec![topic.name.as_str()])
+            .unwrap_or_default();
+
+        let mut report = ProbeReport::new(self.identifier());
+        let mut metadata = None;
+        for (host, port) in &self.bootstrap_servers {
+            let started = Instant::now();
+            let result = Connection::open(self, host, *port, config.clone())
+                .and_then(|mut connection| connection.metadata(&topics));
+            let description = match result {
+                Ok(fetched) => {
+                    metadata = Some(fetched);
+                    format!(
+                        "metadata in {:.1} ms",
+                        started.elapsed().as_secs_f64() * 1000.0
+                    )
+                }
+                Err(e) => format!("failed: {}", e),
+            };
+            report
+                .data
+                .push((format!("Bootstrap {}:{}", host, port), description));
+            if metadata.is_some() {
+                break;
+            }
+        }
+        // a chain not matching the expectations fails the handshake
+        let mismatch = || {
+            certificate_mismatch(
+                self.identifier(),
+                self.tls_test.as_ref(),
+                &peer_certificates,
+            )
+        };
+        let Some(metadata) = metadata else {
+            if let Some(mismatch) = mismatch() {
+                return Err(mismatch);
+            }
+            return Err(AssertionMatchingError(
+                "None of the bootstrap servers returned the cluster metadata".to_string(),
+                report,
+            ));
+        };
+        report.data.push((
+            "Cluster".to_string(),
+            format!(
+                "{}, controller #{}, {} brokers",
+                metadata.cluster_id.as_deref().unwrap_or("no id"),
+                metadata.controller_id,
+                metadata.brokers.len()
+            ),
+        ));
+
+        let mut unreachable = Vec::new();
+        for broker in &metadata.brokers {
+            let address = format!("{}:{}", broker.host, broker.port);
+            let started = Instant::now();
+            let result = u16::try_from(broker.port)
+                .map_err(|_| io::Error::new(ErrorKind::InvalidData, "invalid port"))
+                .and_then(|port| Connection::open(self, &broker.host, port, config.clone()))
+                .and_then(|mut connection| connection.metadata(&[]));
+            let rack = broker
+                .rack
+                .as_ref()
+                .map(|rack| format!(" (rack {})", rack))
+                .unwrap_or_default();
+            let description = match result {
+                Ok(_) => format!(
+                    "{}{} reachable in {:.1} ms",
+                    address,
+                    rack,
+                    started.elapsed().as_secs_f64() * 1000.0
+                ),
+                Err(e) => {
+                    unreachable.push(format!("#{} {}", broker.id, address));
+                    format!("{}{} unreachable: {}", address, rack, e)
+                }
+            };
+            report
+                .data
+                .push((format!("Broker #{}", broker.id), description));
+        }
+
+        let mut failures = Vec::new();
+        if !unreachable.is_empty() {
+            if let Some(mismatch) = mismatch() {
+                return Err(mismatch);
+            }
+            failures.push(format!(
+                "Advertised brokers unreachable from this host: {}",
+                unreachable.join(", ")
+            ));
+        }
+        if let Some(expected) = &self.topic {
+            failures.extend(check_topic(expected, &metadata.topics, &mut report.data));
+        }
+
+        if failures.is_empty() {
+            Ok(report)
+        } else {
+            Err(AssertionMatchingError(failures.join("; "), report))
+        }
+    }
+
+    fn identifier(&self) -> String {
+        let servers = self
+            .bootstrap_servers
+            .iter()
+            .map(|(host, port)| format!("{}:{}", host, port))
+            .collect::<Vec<_>>();
+        format!("{} - {}", PROBE_NAME, servers.join(","))
+    }
+}
+
+/// Reports the partitions of the topic, which fails when it is missing, has another number of
+/// partitions than expected or partitions without leader.
+fn check_topic(expected: &KafkaTopic, topics: &[Topic], data: &mut Data) -> Vec<String> {
+    let topic = match topics.iter().find(|topic| topic.name == expected.name) {
+        Some(topic) if topic.error_code == 0 => topic,
+        Some(topic) if topic.error_code != UNKNOWN_TOPIC_OR_PARTITION => {
+            return vec![format!(
+                "Topic '{}' is not available, error code {}",
+                topic.name, topic.error_code
+            )]
+        }
+        _ => return vec![format!("Topic '{}' does not exist", expected.name)],
+    };
+    let partitions = &topic.partitions;
+    let offline = partitions.iter().filter(|p| p.leader < 0).count();
+    let under_replicated = partitions.iter().filter(|p| p.isr < p.replicas).count();
+    data.push((
+        format!("Topic {}", topic.name),
+        format!(
+            "{} partitions, replication factor {}, {} under-replicated, {} without leader",
+            partitions.len(),
+            partitions.first().map_or(0, |partition| partition.replicas),
+            under_replicated,
+            offline
+        ),
+    ));
+
+    let mut failures = Vec::new();
+    match expected.partitions {
+        Some(expected) if expected != partitions.len() => failures.push(format!(
+            "Topic '{}' has {} partitions, expected {}",
+            topic.name,
+            partitions.len(),
+            expected
+        )),
+        _ => {}
+    }
+    if offline > 0 {
+        failures.push(format!(
+            "Topic '{}' has {} partitions without leader",
+            topic.name, offline
+        ));
+    }
+    failures
+}
+
+struct Broker {
+    id: i32,
+    host: String,
+    port: i32,
+    rack: Option<String>,
+}
+
+struct Partition {
+    leader: i32,
+    replicas: usize,
+    isr: usize,
+}
+
+struct Topic {
+    error_code: i16,
+    name: String,
+    partitions: Vec<Partition>,
+}
+
+struct Metadata {
+    brokers: Vec<Broker>,
+    cluster_id: Option<String>,
+    controller_id: i32,
+    topics: Vec<Topic>,
+}
+
+/// A connection to a broker which is authenticated when SASL is configured
+struct Connection {
+    stream: Stream,
+    correlation_id: i32,
+}
+
+impl Connection {
+    fn open(
+        probe: &Kafka,
+        host: &str,
+        port: u16,
+        config: Option<Arc<ClientConfig>>,
+    ) -> io::Result<Connection> {
+        let mut stream = Stream::Plain(connect(host, port, probe.timeout)?);
+        if let Some(config) = config {
+            stream = stream.upgrade(host, config)?;
+        }
+        let mut connection = Connection {
+            stream,
+            correlation_id: 0,
+        };
+        if let Some(sasl) = &probe.sasl {
+            connection.authenticate(sasl)?;
+        }
+        Ok(connection)
+    }
+
+    /// Sends a request and returns the body of its response
+    fn send(&mut self, (api_key, api_version): (i16, i16), body: &[u8]) -> io::Result<Vec<u8>> {
+        self.correlation_id += 1;
+        let mut request = Vec::new();
+        request.extend(api_key.to_be_bytes());
+        request.extend(api_version.to_be_bytes());
+        request.extend(self.correlation_id.to_be_bytes());
+        write_string(&mut request, CLIENT_ID);
+        request.extend(body);
+        self.stream
+            .write_all(&(request.len() as i32).to_be_bytes())?;
+        self.stream.write_all(&request)?;
+        self.stream.flush()?;
+
+        let mut size = [0; 4];
+        self.stream
+            .read_exact(&mut size)
+            .map_err(|e| match e.kind() {
+                ErrorKind::UnexpectedEof => io::Error::new(
+                    ErrorKind::UnexpectedEof,
+                    "connection closed by the broker, does the listener require TLS or SASL?",
+                ),
+                _ => e,
+            })?;
+        let size = i32::from_be_bytes(size) as usize;
+        if !(4..=MAX_RESPONSE_SIZE).contains(&size) {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "invalid response, does the listener use TLS?",
+            ));
+        }
+        let mut response = vec![0; size];
+        self.stream.read_exact(&mut response)?;
+        let mut reader = Reader(&response);
+        if reader.i32()? != self.correlation_id {
+            return Err(invalid_data("response does not match the request"));
+        }
+        Ok(reader.0.to_vec())
+    }
+
+    fn authenticate(&mut self, sasl: &KafkaSasl) -> io::Result<()> {
+        let mut body = Vec::new();
+        write_string(&mut body, sasl.mechanism.name());
+        let response = self.send(SASL_HANDSHAKE, &body)?;
+        let mut reader = Reader(&response);
+        match reader.i16()? {
+            0 => {}
+            UNSUPPORTED_SASL_MECHANISM => {
+                let enabled = reader.array(|reader| reader.string())?;
+                return Err(io::Error::new(
+                    ErrorKind::PermissionDenied,
+                    format!(
+                        "SASL mechanism {} is not enabled, the broker offers {}",
+                        sasl.mechanism.name(),
+                        enabled.join(", ")
+                    ),
+                ));
+            }
+            code => {
+                return Err(io::Error::new(
+                    ErrorKind::PermissionDenied,
+                    format!("SASL handshake failed with error code {}", code),
+                ))
+            }
+        }
+
+        let password = sasl.password.expose_secret();
+        let hash = match sasl.mechanism {
+            SaslMechanism::Plain => {
+                let token = format!("\0{}\0{}", sasl.user, password);
+                self.sasl_authenticate(token.as_bytes())?;
+                return Ok(());
+            }
+            SaslMechanism::ScramSha256 => ScramHash::Sha256,
+            SaslMechanism::ScramSha512 => ScramHash::Sha512,
+        };
+        let mut scram = ScramClient::new(hash, &sasl.user, password);
+        let server_first = self.sasl_authenticate(scram.client_first().as_bytes())?;
+        let client_final = scram.client_final(&String::from_utf8_lossy(&server_first))?;
+        let server_final = self.sasl_authenticate(client_final.as_bytes())?;
+        scram
+            .verify_server_final(&String::from_utf8_lossy(&server_final))
+            .map_err(|e| io::Error::new(ErrorKind::PermissionDenied, e))
+    }
+
+    fn sasl_authenticate(&mut self, token: &[u8]) -> io::Result<Vec<u8>> {
+        let mut body = Vec::new();
+        body.extend((token.len() as i32).to_be_bytes());
+        body.extend(token);
+        let response = self.send(SASL_AUTHENTICATE, &body)?;
+        let mut reader = Reader(&response);
+        let error_code = reader.i16()?;
+        let error_message = reader.nullable_string()?;
+        if error_code != 0 {
+            return Err(io::Error::new(
+                ErrorKind::PermissionDenied,
+                format!(
+                    "SASL authentication failed: {}",
+                    error_message.unwrap_or_else(|| format!("error code {}", error_code))
+                ),
+            ));
+        }
+        reader.bytes()
+    }
+
+    fn metadata(&mut self, topics: &[&str]) -> io::Result<Metadata> {
+        let mut body = Vec::new();
+        body.extend((topics.len() as i32).to_be_bytes());
+        for topic in topics {
+            write_string(&mut body, topic);
+        }
+        // allow_auto_topic_creation
+        body.push(0);
+        let response = self.send(METADATA, &body)?;
+
+        let mut reader = Reader(&response);
+        let _throttle_time = reader.i32()?;
+        let brokers = reader.array(|reader| {
+            Ok(Broker {
+                id: reader.i32()?,
+                host: reader.string()?,
+                port: reader.i32()?,
+                rack: reader.nullable_string()?,
+            })
+        })?;
+        let cluster_id = reader.nullable_string()?;
+        let controller_id = reader.i32()?;
+        let topics = reader.array(|reader| {
+            let error_code = reader.i16()?;
+            let name = reader.string()?;
+            let _is_internal = reader.bytes_of(1)?;
+            let partitions = reader.array(|reader| {
+                let _error_code = reader.i16()?;
+                let _partition_index = reader.i32()?;
+                Ok(Partition {
+                    leader: reader.i32()?,
+                    replicas: reader.array(|reader| reader.i32())?.len(),
+                    isr: reader.array(|reader| reader.i32())?.len(),
+                })
+            })?;
+            Ok(Topic {
+                error_code,
+                name,
+                partitions,
+            })
+        })?;
+        Ok(Metadata {
+            brokers,
+            cluster_id,
+            controller_id,
+            topics,
+        })
+    }
+}
+
+fn write_string(buffer: &mut Vec<u8>, value: &str) {
+    buffer.extend((value.len() as i16).to_be_bytes());
+    buffer.extend(value.as_bytes());
+}
+
+fn invalid_data(reason: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, reason.to_string())
+}
+
+/// Decodes the primitive types of the Kafka protocol
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn bytes_of(&mut self, length: usize) -> io::Result<&'a [u8]> {
+        if self.0.len() < length {
+            return Err(invalid_data("truncated response"));
+        }
+        let (value, rest) = self.0.split_at(length);
+        self.0 = rest;
+        Ok(value)
+    }
+
+    fn i16(&mut self) -> io::Result<i16> {
+        Ok(i16::from_be_bytes(self.bytes_of(2)?.try_into().unwrap()))
+    }
+
+    fn i32(&mut self) -> io::Result<i32> {
+        Ok(i32::from_be_bytes(self.bytes_of(4)?.try_into().unwrap()))
+    }
+
+    fn nullable_string(&mut self) -> io::Result<Option<String>> {
+        match usize::try_from(self.i16()?) {
+            Ok(length) => Ok(Some(
+                String::from_utf8_lossy(self.bytes_of(length)?).to_string(),
+            )),
+            Err(_) => Ok(None),
+        }
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        self.nullable_string()?
+            .ok_or_else(|| invalid_data("unexpected null string"))
+    }
+
+    fn bytes(&mut self) -> io::Result<Vec<u8>> {
+        match usize::try_from(self.i32()?) {
+            Ok(length) => Ok(self.bytes_of(length)?.to_vec()),
+            Err(_) => Ok(Vec::new()),
+        }
+    }
+
+    fn array<T>(&mut self, item: impl Fn(&mut Self) -> io::Result<T>) -> io::Result<Vec<T>> {
+        let count = usize::try_from(self.i32()?).unwrap_or_default();
+        (0..count).map(|_| item(self)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    use secrecy::SecretString;
+
+    use crate::error::InquestError::AssertionMatchingError;
+    use crate::probes::kafka::{write_string, Reader};
+    use crate::{Kafka, KafkaSasl, KafkaTopic, Probe, SaslMechanism, GO};
+
+    /// Answers like a broker with SASL/PLAIN for 'probe' and 'secret', which advertises itself
+    /// as broker #1 and a closed port as broker #2. The topic 'orders' has two partitions.
+    fn kafka_server() -> u16 {
+        let closed = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                thread::spawn(move || serve(stream, port, closed));
+            }
+        });
+        port
+    }
+
+    fn serve(mut stream: TcpStream, port: u16, closed: u16) {
+        let mut size = [0; 4];
+        while stream.read_exact(&mut size).is_ok() {
+            let mut request = vec![0; i32::from_be_bytes(size) as usize];
+            stream.read_exact(&mut request).unwrap();
+            let mut reader = Reader(&request);
+            let api_key = reader.i16().unwrap();
+            let _api_version = reader.i16().unwrap();
+            let correlation_id = reader.i32().unwrap();
+            let _client_id = reader.string().unwrap();
+
+            let mut body = correlation_id.to_be_bytes().to_vec();
+            match api_key {
+                17 => {
+                    body.extend(0i16.to_be_bytes());
+                    body.extend(1i32.to_be_bytes());
+                    write_string(&mut body, "PLAIN");
+                }
+                36 => {
+                    let accepted = reader.bytes().unwrap() == b"\0probe\0secret";
+                    if accepted {
+                        body.extend(0i16.to_be_bytes());
+                        body.extend((-1i16).to_be_bytes());
+                    } else {
+                        body.extend(58i16.to_be_bytes());
+                        write_string(&mut body, "Invalid username or password");
+                    }
+                    body.extend(0i32.to_be_bytes());
+                }
+                _ => {
+                    let topics = reader.array(|reader| reader.string()).unwrap();
+                    body.extend(0i32.to_be_bytes());
+                    body.extend(2i32.to_be_bytes());
+                    for (id, port) in [(1, port), (2, closed)] {
+                        body.extend((id as i32).to_be_bytes());
+                        write_string(&mut body, "127.0.0.1");
+                        body.extend((port as i32).to_be_bytes());
+                        body.extend((-1i16).to_be_bytes());
+                    }
+                    write_string(&mut body, "cluster-1");
+                    body.extend(1i32.to_be_bytes());
+                    body.extend((topics.len() as i32).to_be_bytes());
+                    for topic in topics {
+                        let partitions: &[i32] = if topic == "orders" { &[1, 2] } else { &[] };
+                        let error_code: i16 = if partitions.is_empty() { 3 } else { 0 };
+                        body.extend(error_code.to_be_bytes());
+                        write_string(&mut body, &topic);
+                        body.push(0);
+                        body.extend((partitions.len() as i32).to_be_bytes());
+                        for (index, leader) in partitions.iter().enumerate() {
+                            body.extend(0i16.to_be_bytes());
+                            body.extend((index as i32).to_be_bytes());
+                            body.extend(leader.to_be_bytes());
+                            // replicas and isr
+                            body.extend([0, 0, 0, 2, 0, 0, 0, 1, 0, 0, 0, 2]);
+                            body.extend([0, 0, 0, 1, 0, 0, 0, 1]);
+                        }
+                    }
+                }
+            }
+            stream
+                .write_all(&(body.len() as i32).to_be_bytes())
+                .unwrap();
+            stream.write_all(&body).unwrap();
+        }
+    }
+
+    fn probe(port: u16, password: &str, topic: &str, partitions: usize) -> Kafka {
+        Kafka {
+            sasl: Some(KafkaSasl {
+                mechanism: SaslMechanism::Plain,
+                user: "probe".to_string(),
+                password: SecretString::new(password.to_string()),
+            }),
+            topic: Some(KafkaTopic {
+                name: topic.to_string(),
+                partitions: Some(partitions),
+            }),
+            ..Kafka::new(vec![("127.0.0.1".to_string(), port)], None, &GO)
+        }
+    }
+
+    #[test]
+    fn unreachable_brokers_and_topic_are_reported() {
+        let port = kafka_server();
+
+        assert_matches!(
+            probe(port, "secret", "orders", 3).execute(),
+            Err(AssertionMatchingError(desc, report)) => {
+                assert!(desc.starts_with("Advertised brokers unreachable from this host: #2 127.0.0.1:"));
+                assert!(desc.ends_with("; Topic 'orders' has 2 partitions, expected 3"));
+                assert_eq!("cluster-1, controller #1, 2 brokers", report.data[1].1);
+                assert!(report.data[2].1.contains(" reachable in "));
+                assert!(report.data[3].1.contains(" unreachable: "));
+                assert_eq!(
+                    "2 partitions, replication factor 2, 2 under-replicated, 0 without leader",
+                    report.data[4].1
+                );
+            }
+        );
+        assert_matches!(
+            probe(port, "secret", "payments", 1).execute(),
+            Err(AssertionMatchingError(desc, _)) if desc.ends_with("Topic 'payments' does not exist")
+        );
+    }
+
+    #[test]
+    fn failed_authentication_is_reported() {
+        assert_matches!(
+            probe(kafka_server(), "guess", "orders", 2).execute(),
+            Err(AssertionMatchingError(desc, report)) => {
+                assert_eq!("None of the bootstrap servers returned the cluster metadata", desc);
+                assert_eq!(
+                    "failed: SASL authentication failed: Invalid username or password",
+                    report.data[0].1
+                );
+            }
+        );
+    }
+}
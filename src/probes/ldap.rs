@@ -0,0 +1,706 @@
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::io::{ErrorKind, Read, Write};
+use std::sync::Arc;
+use std::time::Instant;
+
+use rustls::ClientConfig;
+use secrecy::ExposeSecret;
+
+use crate::error::InquestError::{AssertionMatchingError, FailedExecutionError};
+use crate::probes::sql::Table;
+use crate::probes::stream::{connect, Failure, Stream};
+use crate::probes::tls::{certificate_mismatch, client_config, PeerCertificates};
+use crate::{Certificates, Data, GlobalOptions, Ldap, LdapSearch, Probe, ProbeReport, Result};
+
+const PROBE_NAME: &str = "LDAP";
+
+const STARTTLS_OID: &str = "1.3.6.1.4.1.1466.20037";
+
+/// Entries with photos or certificates are large, but a length beyond this is not spoken by LDAP
+const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+/// The BER tags of the universal types and the protocol operations of RFC 4511
+const BOOLEAN: u8 = 0x01;
+const INTEGER: u8 = 0x02;
+const OCTET_STRING: u8 = 0x04;
+const ENUMERATED: u8 = 0x0A;
+const SEQUENCE: u8 = 0x30;
+const SET: u8 = 0x31;
+const BIND_REQUEST: u8 = 0x60;
+const BIND_RESPONSE: u8 = 0x61;
+const UNBIND_REQUEST: u8 = 0x42;
+const SEARCH_REQUEST: u8 = 0x63;
+const SEARCH_RESULT_ENTRY: u8 = 0x64;
+const SEARCH_RESULT_DONE: u8 = 0x65;
+const SEARCH_RESULT_REFERENCE: u8 = 0x73;
+const EXTENDED_REQUEST: u8 = 0x77;
+const EXTENDED_RESPONSE: u8 = 0x78;
+
+/// The message ids of the requests, which are sent one after another
+const STARTTLS_ID: i64 = 1;
+const BIND_ID: i64 = 2;
+const SEARCH_ID: i64 = 3;
+const UNBIND_ID: i64 = 4;
+
+impl Ldap {
+    pub(crate) fn new(
+        host: String,
+        certs: Option<Certificates>,
+        options: &'static GlobalOptions,
+    ) -> Ldap {
+        Ldap {
+            host,
+            port: 389,
+            tls: false,
+            starttls: false,
+            tls_test: None,
+            bind_dn: None,
+            password: None,
+            timeout: options.timeout,
+            certs,
+            search: None,
+        }
+    }
+}
+
+/// The outcome of an operation
+struct LdapResult {
+    code: i64,
+    diagnostic: String,
+}
+
+impl Display for LdapResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self.code {
+            0 => "success",
+            1 => "operationsError",
+            2 => "protocolError",
+            4 => "sizeLimitExceeded",
+            8 => "strongerAuthRequired",
+            13 => "confidentialityRequired",
+            32 => "noSuchObject",
+            34 => "invalidDNSyntax",
+            48 => "inappropriateAuthentication",
+            49 => "invalidCredentials",
+            50 => "insufficientAccessRights",
+            51 => "busy",
+            52 => "unavailable",
+            53 => "unwillingToPerform",
+            _ => "",
+        };
+        write!(f, "{} {}", self.code, name)?;
+        match self.diagnostic.is_empty() {
+            true => Ok(()),
+            false => write!(f, ": {}", self.diagnostic),
+        }
+    }
+}
+
+/// Binds with the DN and searches the directory with a hand-rolled client, which only knows the
+/// few operations of the probe.
+impl Probe for Ldap {
+    fn execute(&self) -> Result<ProbeReport> {
+        let failed = |e: io::Error| FailedExecutionError {
+            probe_identifier: self.identifier(),
+            source: Box::new(e),
+        };
+        let search = match &self.search {
+            Some(search) => Some(self.search_request(search).ok_or_else(|| {
+                failed(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("invalid filter '{}'", search.filter),
+                ))
+            })?),
+            None => None,
+        };
+        let peer_certificates = PeerCertificates::default();
+        let config = match self.tls || self.starttls {
+            true => Some(Arc::new(
+                client_config(
+                    self.certs.as_ref(),
+                    self.tls_test.as_ref(),
+                    peer_certificates.clone(),
+                )
+                .map_err(|e| FailedExecutionError {
+                    probe_identifier: self.identifier(),
+                    source: Box::new(e),
+                })?,
+            )),
+            false => None,
+        };
+
+        let started = Instant::now();
+        let socket = connect(&self.host, self.port, self.timeout).map_err(failed)?;
+        let mut report = ProbeReport::new(self.identifier());
+        report.data.push((
+            "Connection".to_string(),
+            format!(
+                "{} connected in {:.1} ms",
+                socket
+                    .peer_addr()
+                    .map_or("unknown".to_string(), |peer| peer.to_string()),
+                started.elapsed().as_secs_f64() * 1000.0
+            ),
+        ));
+
+        let dialog = self.dialog(Stream::Plain(socket), config, search, &mut report.data);
+        match dialog {
+            Ok(()) => Ok(report),
+            Err(Failure::Refused(desc)) => Err(certificate_mismatch(
+                self.identifier(),
+                self.tls_test.as_ref(),
+                &peer_certificates,
+            )
+            .unwrap_or(AssertionMatchingError(desc, report))),
+            Err(Failure::Io(e)) => Err(failed(e)),
+        }
+    }
+
+    fn identifier(&self) -> String {
+        let scheme = match self.tls {
+            true => "ldaps",
+            false => "ldap",
+        };
+        format!("{} - {}://{}:{}", PROBE_NAME, scheme, self.host, self.port)
+    }
+}
+
+impl Ldap {
+    fn dialog(
+        &self,
+        mut stream: Stream,
+        config: Option<Arc<ClientConfig>>,
+        search: Option<Vec<u8>>,
+        data: &mut Data,
+    ) -> std::result::Result<(), Failure> {
+        if let Some(config) = config {
+            let key = match self.starttls {
+                true => {
+                    let request = element(0x80, STARTTLS_OID.as_bytes());
+                    write_message(&mut stream, STARTTLS_ID, EXTENDED_REQUEST, &request)?;
+                    let result = read_result(&mut stream, EXTENDED_RESPONSE)?;
+                    if result.code != 0 {
+                        return Err(Failure::Refused(format!(
+                            "StartTLS was refused: {}",
+                            result
+                        )));
+                    }
+                    "StartTLS"
+                }
+                false => "TLS",
+            };
+            stream = stream
+                .upgrade(&self.host, config)
+                .map_err(|e| Failure::Refused(e.to_string()))?;
+            data.extend(stream.tls_description().map(|tls| (key.to_string(), tls)));
+        }
+
+        let name = self.bind_dn.as_deref().unwrap_or_default();
+        let password = self
+            .password
+            .as_ref()
+            .map_or("", |password| password.expose_secret());
+        // many servers accept a name without password as unauthenticated bind (RFC 4513 5.1.2)
+        if !name.is_empty() && password.is_empty() {
+            return Err(Failure::Refused(format!(
+                "Bind as '{}' requires a password, otherwise it is an unauthenticated bind",
+                name
+            )));
+        }
+        let mut request = integer(INTEGER, 3);
+        request.extend(element(OCTET_STRING, name.as_bytes()));
+        // simple authentication
+        request.extend(element(0x80, password.as_bytes()));
+        let started = Instant::now();
+        write_message(&mut stream, BIND_ID, BIND_REQUEST, &request)?;
+        let result = read_result(&mut stream, BIND_RESPONSE)?;
+        let name = match name {
+            "" => "anonymous",
+            name => name,
+        };
+        if result.code != 0 {
+            return Err(Failure::Refused(format!(
+                "Bind as '{}' failed: {}",
+                name, result
+            )));
+        }
+        data.push((
+            "Bind".to_string(),
+            format!(
+                "{} in {:.1} ms",
+                name,
+                started.elapsed().as_secs_f64() * 1000.0
+            ),
+        ));
+
+        if let (Some(search), Some(request)) = (&self.search, search) {
+            let started = Instant::now();
+            write_message(&mut stream, SEARCH_ID, SEARCH_REQUEST, &request)?;
+            let mut entries = Vec::new();
+            let result = loop {
+                let (tag, contents) = read_message(&mut stream)?;
+                match tag {
+                    SEARCH_RESULT_ENTRY => entries.push(read_entry(&mut contents.as_slice())?),
+                    SEARCH_RESULT_REFERENCE => continue,
+                    _ => break read_ldap_result(tag, SEARCH_RESULT_DONE, &contents)?,
+                }
+            };
+            let limited = match result.code {
+                0 => "",
+                4 => ", limited by the server",
+                _ => {
+                    return Err(Failure::Refused(format!(
+                        "Search below '{}' failed: {}",
+                        search.base, result
+                    )))
+                }
+            };
+            data.push((
+                "Search".to_string(),
+                format!(
+                    "{} entries below '{}' in {:.1} ms{}",
+                    entries.len(),
+                    search.base,
+                    started.elapsed().as_secs_f64() * 1000.0,
+                    limited
+                ),
+            ));
+            if !entries.is_empty() {
+                data.push((
+                    "Entries".to_string(),
+                    format!("{}", table(&search.attributes, entries.as_slice())),
+                ));
+            }
+            match search.min_results {
+                Some(min) if entries.len() < min => {
+                    return Err(Failure::Refused(format!(
+                        "Expected at least {} entries, but found {}",
+                        min,
+                        entries.len()
+                    )))
+                }
+                _ => {}
+            }
+        }
+        write_message(&mut stream, UNBIND_ID, UNBIND_REQUEST, &[])?;
+        Ok(())
+    }
+
+    /// The search request, or none when the filter is invalid
+    fn search_request(&self, search: &LdapSearch) -> Option<Vec<u8>> {
+        let mut request = element(OCTET_STRING, search.base.as_bytes());
+        // the whole subtree, without dereferencing aliases
+        request.extend(integer(ENUMERATED, 2));
+        request.extend(integer(ENUMERATED, 0));
+        request.extend(integer(INTEGER, search.limit));
+        request.extend(integer(INTEGER, self.timeout.as_secs() as i64));
+        request.extend(element(BOOLEAN, &[0]));
+        request.extend(encode_filter(&search.filter)?);
+        let attributes = search
+            .attributes
+            .iter()
+            .flat_map(|attribute| element(OCTET_STRING, attribute.as_bytes()))
+            .collect::<Vec<_>>();
+        request.extend(element(SEQUENCE, &attributes));
+        Some(request)
+    }
+}
+
+/// An entry with its DN and the values of each attribute
+type Entry = (String, Vec<(String, Vec<String>)>);
+
+/// One row per entry, with the DN and the requested attributes as columns, or all attributes
+/// returned by the server when none were requested
+fn table(attributes: &[String], entries: &[Entry]) -> Table {
+    let attributes = match attributes.is_empty() {
+        true => entries
+            .iter()
+            .flat_map(|(_, attributes)| attributes.iter().map(|(name, _)| name.to_string()))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect(),
+        false => attributes.to_vec(),
+    };
+    let rows = entries
+        .iter()
+        .map(|(dn, values)| {
+            std::iter::once(dn.to_string())
+                .chain(attributes.iter().map(|attribute| {
+                    values
+                        .iter()
+                        .find(|(name, _)| name.eq_ignore_ascii_case(attribute))
+                        .map(|(_, values)| values.join(", "))
+                        .unwrap_or_default()
+                }))
+                .collect()
+        })
+        .collect();
+    let columns = std::iter::once("dn".to_string())
+        .chain(attributes)
+        .collect();
+    Table::new(columns, rows)
+}
+
+/// Encodes a filter of RFC 4515 like `(&(objectClass=person)(uid=pro*))`, the extensible match
+/// is not supported
+fn encode_filter(filter: &str) -> Option<Vec<u8>> {
+    let filter = filter.trim();
+    let wrapped = format!("({})", filter);
+    let mut rest = match filter.starts_with('(') {
+        true => filter,
+        false => wrapped.as_str(),
+    };
+    let encoded = parse_filter(&mut rest)?;
+    rest.trim().is_empty().then_some(encoded)
+}
+
+fn parse_filter(rest: &mut &str) -> Option<Vec<u8>> {
+    *rest = rest.trim_start().strip_prefix('(')?;
+    let encoded = if let Some(list) = rest.strip_prefix('&') {
+        *rest = list;
+        element(0xA0, &parse_list(rest)?)
+    } else if let Some(list) = rest.strip_prefix('|') {
+        *rest = list;
+        element(0xA1, &parse_list(rest)?)
+    } else if let Some(negated) = rest.strip_prefix('!') {
+        *rest = negated;
+        element(0xA2, &parse_filter(rest)?)
+    } else {
+        let end = rest.find(')')?;
+        let item = parse_item(&rest[..end])?;
+        *rest = &rest[end..];
+        item
+    };
+    *rest = rest.trim_start().strip_prefix(')')?;
+    Some(encoded)
+}
+
+fn parse_list(rest: &mut &str) -> Option<Vec<u8>> {
+    let mut list = Vec::new();
+    while rest.trim_start().starts_with('(') {
+        list.extend(parse_filter(rest)?);
+    }
+    Some(list)
+}
+
+fn parse_item(item: &str) -> Option<Vec<u8>> {
+    let equals = item.find('=')?;
+    let (attribute, tag) = match item[..equals].chars().last() {
+        Some('~') => (&item[..equals - 1], 0xA8),
+        Some('>') => (&item[..equals - 1], 0xA5),
+        Some('<') => (&item[..equals - 1], 0xA6),
+        _ => (&item[..equals], 0xA3),
+    };
+    let value = &item[equals + 1..];
+    if attribute.is_empty() {
+        return None;
+    }
+    let mut encoded = element(OCTET_STRING, attribute.as_bytes());
+    if tag == 0xA3 && value == "*" {
+        return Some(element(0x87, attribute.as_bytes()));
+    } else if tag == 0xA3 && value.contains('*') {
+        // an escaped asterisk is '\2a', so every asterisk is a wildcard
+        let parts = value.split('*').collect::<Vec<_>>();
+        let mut substrings = Vec::new();
+        for (index, part) in parts.iter().enumerate() {
+            let position = match index {
+                0 => 0x80,
+                last if last == parts.len() - 1 => 0x82,
+                _ => 0x81,
+            };
+            if !part.is_empty() {
+                substrings.extend(element(position, &unescape(part)?));
+            }
+        }
+        encoded.extend(element(SEQUENCE, &substrings));
+        return Some(element(0xA4, &encoded));
+    }
+    encoded.extend(element(OCTET_STRING, &unescape(value)?));
+    Some(element(tag, &encoded))
+}
+
+/// Resolves the escapes like `\28` for a parenthesis
+fn unescape(value: &str) -> Option<Vec<u8>> {
+    let mut unescaped = Vec::with_capacity(value.len());
+    let mut bytes = value.bytes();
+    while let Some(byte) = bytes.next() {
+        match byte {
+            b'\\' => {
+                let hex = [bytes.next()?, bytes.next()?];
+                unescaped.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+            }
+            b'(' | b')' => return None,
+            _ => unescaped.push(byte),
+        }
+    }
+    Some(unescaped)
+}
+
+fn element(tag: u8, contents: &[u8]) -> Vec<u8> {
+    let mut element = vec![tag];
+    match contents.len() {
+        short if short < 0x80 => element.push(short as u8),
+        long => {
+            let length = (long as u32).to_be_bytes();
+            let skipped = length.iter().take_while(|byte| **byte == 0).count();
+            element.push(0x80 | (4 - skipped) as u8);
+            element.extend(&length[skipped..]);
+        }
+    }
+    element.extend(contents);
+    element
+}
+
+/// Encodes the value in as few bytes as its two's complement needs
+fn integer(tag: u8, value: i64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let mut start = 0;
+    while start < 7
+        && ((bytes[start] == 0 && bytes[start + 1] & 0x80 == 0)
+            || (bytes[start] == 0xFF && bytes[start + 1] & 0x80 != 0))
+    {
+        start += 1;
+    }
+    element(tag, &bytes[start..])
+}
+
+fn write_message(stream: &mut impl Write, id: i64, tag: u8, operation: &[u8]) -> io::Result<()> {
+    let mut message = integer(INTEGER, id);
+    message.extend(element(tag, operation));
+    stream.write_all(&element(SEQUENCE, &message))?;
+    stream.flush()
+}
+
+/// The tag and contents of the operation of the next message
+fn read_message(stream: &mut impl Read) -> io::Result<(u8, Vec<u8>)> {
+    let contents = read_expected(stream, SEQUENCE)?;
+    let mut message = contents.as_slice();
+    read_expected(&mut message, INTEGER)?;
+    read_element(&mut message)
+}
+
+fn read_result(stream: &mut impl Read, expected: u8) -> io::Result<LdapResult> {
+    let (tag, contents) = read_message(stream)?;
+    read_ldap_result(tag, expected, &contents)
+}
+
+/// Also reads the notice of disconnection, which servers send instead of the expected response
+fn read_ldap_result(tag: u8, expected: u8, mut contents: &[u8]) -> io::Result<LdapResult> {
+    if tag != expected && tag != EXTENDED_RESPONSE {
+        return Err(invalid_data("unexpected response"));
+    }
+    let code = read_expected(&mut contents, ENUMERATED)?
+        .iter()
+        .fold(0, |code, byte| code << 8 | *byte as i64);
+    read_expected(&mut contents, OCTET_STRING)?;
+    let diagnostic = read_expected(&mut contents, OCTET_STRING)?;
+    Ok(LdapResult {
+        code,
+        diagnostic: String::from_utf8_lossy(&diagnostic).to_string(),
+    })
+}
+
+fn read_entry(contents: &mut &[u8]) -> io::Result<Entry> {
+    let dn = read_string(contents)?;
+    let attributes = read_expected(contents, SEQUENCE)?;
+    let mut attributes = attributes.as_slice();
+    let mut entry = Vec::new();
+    while !attributes.is_empty() {
+        let attribute = read_expected(&mut attributes, SEQUENCE)?;
+        let mut attribute = attribute.as_slice();
+        let name = read_string(&mut attribute)?;
+        let values = read_expected(&mut attribute, SET)?;
+        let mut values = values.as_slice();
+        let mut strings = Vec::new();
+        while !values.is_empty() {
+            strings.push(read_string(&mut values)?);
+        }
+        entry.push((name, strings));
+    }
+    Ok((dn, entry))
+}
+
+fn read_string(stream: &mut impl Read) -> io::Result<String> {
+    let value = read_expected(stream, OCTET_STRING)?;
+    Ok(String::from_utf8_lossy(&value).to_string())
+}
+
+fn read_expected(stream: &mut impl Read, expected: u8) -> io::Result<Vec<u8>> {
+    match read_element(stream)? {
+        (tag, contents) if tag == expected => Ok(contents),
+        _ => Err(invalid_data("unexpected element, is this an LDAP server?")),
+    }
+}
+
+fn read_element(stream: &mut impl Read) -> io::Result<(u8, Vec<u8>)> {
+    let mut header = [0; 2];
+    stream.read_exact(&mut header)?;
+    let length = match header[1] {
+        short if short & 0x80 == 0 => short as usize,
+        long => {
+            let count = (long & 0x7F) as usize;
+            if count == 0 || count > 4 {
+                return Err(invalid_data("unsupported length of element"));
+            }
+            let mut length = [0; 4];
+            stream.read_exact(&mut length[4 - count..])?;
+            u32::from_be_bytes(length) as usize
+        }
+    };
+    if length > MAX_MESSAGE_SIZE {
+        return Err(invalid_data(
+            "element is too large, is this an LDAP server?",
+        ));
+    }
+    let mut contents = vec![0; length];
+    stream.read_exact(&mut contents)?;
+    Ok((header[0], contents))
+}
+
+fn invalid_data(reason: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, reason.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+    use std::net::TcpListener;
+    use std::thread;
+
+    use secrecy::SecretString;
+
+    use crate::error::InquestError::AssertionMatchingError;
+    use crate::probes::ldap::{
+        element, encode_filter, integer, read_element, read_message, write_message, BIND_REQUEST,
+        BIND_RESPONSE, ENUMERATED, OCTET_STRING, SEARCH_REQUEST, SEARCH_RESULT_DONE,
+        SEARCH_RESULT_ENTRY, SEQUENCE, SET,
+    };
+    use crate::{Ldap, LdapSearch, Probe, GO};
+
+    #[test]
+    fn filter_is_encoded() {
+        let mut expected = vec![0xA0, 0x25, 0xA3, 0x15, 0x04, 0x0B];
+        expected.extend(b"objectClass\x04\x06person");
+        expected.extend([0xA4, 0x0C, 0x04, 0x03]);
+        expected.extend(b"uid\x30\x05\x80\x03pro");
+        assert_eq!(
+            Some(expected),
+            encode_filter("(&(objectClass=person)(uid=pro*))")
+        );
+
+        assert_eq!(
+            Some(vec![0x87, 0x04, b'm', b'a', b'i', b'l']),
+            encode_filter("mail=*")
+        );
+        assert_eq!(None, encode_filter("(&(uid=probe)"));
+    }
+
+    fn result(code: i64, diagnostic: &str) -> Vec<u8> {
+        let mut result = integer(ENUMERATED, code);
+        result.extend(element(OCTET_STRING, b""));
+        result.extend(element(OCTET_STRING, diagnostic.as_bytes()));
+        result
+    }
+
+    /// Accepts the bind of 'cn=probe,dc=corp' with the password 'secret' as well as an
+    /// unauthenticated bind, and finds two people
+    fn ldap_server() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                while let Ok((tag, contents)) = read_message(&mut stream) {
+                    match tag {
+                        BIND_REQUEST
+                            if contents.ends_with(b"secret") || contents.ends_with(&[0x80, 0]) =>
+                        {
+                            write_message(&mut stream, 2, BIND_RESPONSE, &result(0, ""))
+                        }
+                        BIND_REQUEST => write_message(
+                            &mut stream,
+                            2,
+                            BIND_RESPONSE,
+                            &result(49, "bad credentials"),
+                        ),
+                        SEARCH_REQUEST => {
+                            for uid in ["alice", "bob"] {
+                                let mut entry = element(
+                                    OCTET_STRING,
+                                    format!("uid={},ou=people,dc=corp", uid).as_bytes(),
+                                );
+                                let mut attribute = element(OCTET_STRING, b"cn");
+                                attribute.extend(element(
+                                    SET,
+                                    &element(OCTET_STRING, uid.to_uppercase().as_bytes()),
+                                ));
+                                entry.extend(element(SEQUENCE, &element(SEQUENCE, &attribute)));
+                                write_message(&mut stream, 3, SEARCH_RESULT_ENTRY, &entry).unwrap();
+                            }
+                            write_message(&mut stream, 3, SEARCH_RESULT_DONE, &result(0, ""))
+                        }
+                        _ => break,
+                    }
+                    .unwrap();
+                }
+            }
+        });
+        port
+    }
+
+    fn probe(port: u16, password: &str, min_results: usize) -> Ldap {
+        Ldap {
+            port,
+            bind_dn: Some("cn=probe,dc=corp".to_string()),
+            password: Some(SecretString::new(password.to_string())),
+            search: Some(LdapSearch {
+                base: "ou=people,dc=corp".to_string(),
+                filter: "(objectClass=person)".to_string(),
+                attributes: vec!["cn".to_string()],
+                limit: 100,
+                min_results: Some(min_results),
+            }),
+            ..Ldap::new("127.0.0.1".to_string(), None, &GO)
+        }
+    }
+
+    #[test]
+    fn entries_are_reported() {
+        let port = ldap_server();
+
+        let report = probe(port, "secret", 2).execute().unwrap();
+        assert!(report.data[2]
+            .1
+            .starts_with("2 entries below 'ou=people,dc=corp' in"));
+        assert_eq!(
+            " DN                           CN    \n \
+            uid=alice,ou=people,dc=corp  ALICE \n \
+            uid=bob,ou=people,dc=corp    BOB   \n",
+            report.data[3].1
+        );
+
+        assert_matches!(
+            probe(port, "secret", 3).execute(),
+            Err(AssertionMatchingError(desc, _))
+                if desc == "Expected at least 3 entries, but found 2"
+        );
+        assert_matches!(
+            probe(port, "guess", 0).execute(),
+            Err(AssertionMatchingError(desc, _)) if desc == "Bind as 'cn=probe,dc=corp' failed: \
+                49 invalidCredentials: bad credentials"
+        );
+        assert_matches!(
+            probe(port, "", 0).execute(),
+            Err(AssertionMatchingError(desc, _)) if desc == "Bind as 'cn=probe,dc=corp' requires \
+                a password, otherwise it is an unauthenticated bind"
+        );
+    }
+
+    #[test]
+    fn oversized_element_is_rejected_before_reading() {
+        let result = read_element(&mut &[SEQUENCE, 0x84, 0xFF, 0xFF, 0xFF, 0xFF][..]);
+
+        assert_eq!(Some(ErrorKind::InvalidData), result.err().map(|e| e.kind()));
+    }
+}
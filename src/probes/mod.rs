@@ -1,6 +1,27 @@
+mod amqp;
+mod body;
+mod dns;
+mod ftp;
+mod handshake;
+mod health;
 mod http;
+mod http_scenario;
+mod kafka;
+mod ldap;
+mod mongodb;
 mod mssql;
+mod mysql;
+mod ntp;
 mod oracle;
 mod postgres;
+mod protocol;
+mod redis;
+mod scram;
+mod sftp;
+mod smtp;
 mod sql;
+mod ssh;
+mod stream;
 mod tcp;
+mod tls;
+mod udp;
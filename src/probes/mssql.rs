@@ -91,6 +91,7 @@ async fn establish_connection(
         if cert_options.client_cert.is_some()
             || cert_options.client_key.is_some()
             || cert_options.client_pem.is_some()
+            || cert_options.client_pkcs12.is_some()
         {
             // TODO use proper logging/eventing
             println!("MTLS currently not supported by MSSQL driver");
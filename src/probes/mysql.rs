@@ -0,0 +1,342 @@
+use std::path::PathBuf;
+
+use mysql::consts::ColumnType;
+use mysql::prelude::Queryable;
+use mysql::{ClientIdentity, Column, Conn, OptsBuilder, Row, SslOpts, Value};
+use secrecy::{ExposeSecret, SecretString};
+
+use crate::error::InquestError::{ClientIdentityError, FailedAssertionError, FailedExecutionError};
+use crate::probes::sql::Table;
+use crate::{Certificates, Result};
+use crate::{GlobalOptions, MySql, Probe, ProbeReport, SqlTest};
+
+const PROBE_NAME: &str = "MySQL";
+
+/// Columns with this character set contain binary data
+const BINARY_CHARSET: u16 = 63;
+
+impl MySql {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        host: Option<String>,
+        port: Option<u16>,
+        database: Option<String>,
+        user: String,
+        password: SecretString,
+        sql: Option<SqlTest>,
+        options: &'static GlobalOptions,
+        certs: Option<Certificates>,
+    ) -> MySql {
+        MySql {
+            options,
+            host: host.unwrap_or("localhost".to_string()),
+            port: port.unwrap_or(3306),
+            user,
+            database,
+            password,
+            sql,
+            certs,
+        }
+    }
+
+    /// The mysql-crate reads the CA and the client identity from PEM files and brings its own
+    /// rustls, so `Certificates` cannot be loaded like for the other probes.
+    fn ssl_opts(&self) -> Result<Option<SslOpts>> {
+        let Some(certs) = &self.certs else {
+            return Ok(None);
+        };
+        if let Some(path) = &certs.client_pkcs12 {
+            return Err(FailedExecutionError {
+                probe_identifier: self.identifier(),
+                source: Box::new(ClientIdentityError {
+                    path: path.to_string(),
+                    reason: "PKCS#12 is not supported for MySQL, use PEM files instead".to_string(),
+                }),
+            });
+        }
+        let identity = match (&certs.client_pem, &certs.client_cert, &certs.client_key) {
+            (Some(pem), _, _) => Some(ClientIdentity::new(PathBuf::from(pem), PathBuf::from(pem))),
+            (None, Some(cert), Some(key)) => {
+                Some(ClientIdentity::new(PathBuf::from(cert), PathBuf::from(key)))
+            }
+            _ => None,
+        };
+        Ok(Some(
+            SslOpts::default()
+                .with_root_cert_path(certs.ca_cert.as_ref().map(PathBuf::from))
+                .with_client_identity(identity),
+        ))
+    }
+}
+
+/// Implements a MySQL probe based on the mysql-crate, which works with MariaDB as well.
+impl Probe for MySql {
+    fn execute(&self) -> Result<ProbeReport> {
+        let options = OptsBuilder::new()
+            .ip_or_hostname(Some(&self.host))
+            .tcp_port(self.port)
+            .user(Some(&self.user))
+            .pass(Some(self.password.expose_secret()))
+            .db_name(self.database.as_ref())
+            .prefer_socket(false)
+            .tcp_connect_timeout(Some(self.options.timeout))
+            .read_timeout(Some(self.options.timeout))
+            .write_timeout(Some(self.options.timeout))
+            .ssl_opts(self.ssl_opts()?);
+        let mut connection = Conn::new(options).map_err(|e| FailedExecutionError {
+            probe_identifier: self.identifier(),
+            source: Box::new(e),
+        })?;
+
+        let mut report = ProbeReport::new(self.identifier());
+        let (major, minor, patch) = connection.server_version();
+        report.data.push((
+            "Server version".to_string(),
+            format!("{}.{}.{}", major, minor, patch),
+        ));
+        if self.certs.is_some() {
+            let tls = connection
+                .query::<(String, String), _>(
+                    "SHOW SESSION STATUS WHERE Variable_name IN ('Ssl_version', 'Ssl_cipher')",
+                )
+                .map(|status| {
+                    status
+                        .into_iter()
+                        .map(|(_, value)| value)
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .unwrap_or_else(|e| format!("unknown: {}", e));
+            report.data.push(("TLS".to_string(), tls));
+        }
+
+        if let Some(sql) = &self.sql {
+            let table = run_sql(&mut connection, sql).map_err(|e| FailedAssertionError {
+                probe_identifier: self.identifier(),
+                desc: "Error execution sql-query!".to_string(),
+                source: Box::new(e),
+            })?;
+            report
+                .data
+                .push(("ResultSet".to_string(), format!("{}", table)));
+        }
+        Ok(report)
+    }
+
+    fn identifier(&self) -> String {
+        format!(
+            "{} - {}:{}/{}/{}",
+            PROBE_NAME,
+            self.host,
+            self.port,
+            self.database.as_deref().unwrap_or_default(),
+            self.user
+        )
+    }
+}
+
+/// Only the first result set is shown, its columns even without any rows.
+fn run_sql(connection: &mut Conn, sql: &SqlTest) -> mysql::Result<Table> {
+    let mut result = connection.query_iter(sql.query.as_str())?;
+    let columns = result
+        .columns()
+        .as_ref()
+        .iter()
+        .map(|column| column.name_str().to_string())
+        .collect();
+    let rows = match result.iter() {
+        Some(set) => set
+            .map(|row| row.map(|row| render_row(&row)))
+            .collect::<mysql::Result<Vec<_>>>()?,
+        None => Vec::new(),
+    };
+    Ok(Table::new(columns, rows))
+}
+
+fn render_row(row: &Row) -> Vec<String> {
+    row.columns_ref()
+        .iter()
+        .enumerate()
+        .map(|(index, column)| {
+            row.as_ref(index)
+                .map(|value| render(value, column))
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Values of the text protocol are already formatted by the server, except for binary data
+/// and bit-fields. The typed values only occur with the binary protocol.
+fn render(value: &Value, column: &Column) -> String {
+    match value {
+        Value::NULL => String::new(),
+        Value::Bytes(bytes) => match column.column_type() {
+            ColumnType::MYSQL_TYPE_BIT => format!(
+                "b'{:b}'",
+                bytes
+                    .iter()
+                    .fold(0u64, |bits, byte| (bits << 8) | *byte as u64)
+            ),
+            ColumnType::MYSQL_TYPE_GEOMETRY => hex(bytes),
+            _ if is_binary_string(column) => hex(bytes),
+            _ => String::from_utf8_lossy(bytes).to_string(),
+        },
+        Value::Int(v) => v.to_string(),
+        Value::UInt(v) => v.to_string(),
+        Value::Float(v) => v.to_string(),
+        Value::Double(v) => v.to_string(),
+        Value::Date(year, month, day, hour, minute, second, micros) => match column.column_type() {
+            ColumnType::MYSQL_TYPE_DATE | ColumnType::MYSQL_TYPE_NEWDATE => {
+                format!("{:04}-{:02}-{:02}", year, month, day)
+            }
+            _ => format!(
+                "{:04}-{:02}-{:02} {:02}:{:02}:{:02}{}",
+                year,
+                month,
+                day,
+                hour,
+                minute,
+                second,
+                fraction(*micros)
+            ),
+        },
+        Value::Time(negative, days, hours, minutes, seconds, micros) => format!(
+            "{}{:02}:{:02}:{:02}{}",
+            if *negative { "-" } else { "" },
+            *days * 24 + *hours as u32,
+            minutes,
+            seconds,
+            fraction(*micros)
+        ),
+    }
+}
+
+/// BINARY, VARBINARY and BLOB columns, in contrast to numbers and dates which use the binary
+/// character set as well
+fn is_binary_string(column: &Column) -> bool {
+    column.character_set() == BINARY_CHARSET
+        && matches!(
+            column.column_type(),
+            ColumnType::MYSQL_TYPE_STRING
+                | ColumnType::MYSQL_TYPE_VAR_STRING
+                | ColumnType::MYSQL_TYPE_VARCHAR
+                | ColumnType::MYSQL_TYPE_TINY_BLOB
+                | ColumnType::MYSQL_TYPE_MEDIUM_BLOB
+                | ColumnType::MYSQL_TYPE_LONG_BLOB
+                | ColumnType::MYSQL_TYPE_BLOB
+        )
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .fold("0x".to_string(), |hex, byte| hex + &format!("{:02X}", byte))
+}
+
+fn fraction(micros: u32) -> String {
+    if micros == 0 {
+        String::new()
+    } else {
+        format!(".{:06}", micros)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mysql::consts::ColumnType;
+    use mysql::{Column, Value};
+
+    use secrecy::SecretString;
+
+    use crate::error::InquestError::FailedExecutionError;
+    use crate::probes::mysql::{render, BINARY_CHARSET};
+    use crate::{Certificates, MySql, GO};
+
+    #[test]
+    fn values_are_rendered_by_column_type() {
+        const UTF8: u16 = 255;
+        let column =
+            |column_type, character_set| Column::new(column_type).with_character_set(character_set);
+        let text = |value: &str| Value::Bytes(value.as_bytes().to_vec());
+
+        assert_eq!(
+            "",
+            render(
+                &Value::NULL,
+                &column(ColumnType::MYSQL_TYPE_LONG, BINARY_CHARSET)
+            )
+        );
+        assert_eq!(
+            "42.50",
+            render(
+                &text("42.50"),
+                &column(ColumnType::MYSQL_TYPE_NEWDECIMAL, BINARY_CHARSET)
+            )
+        );
+        assert_eq!(
+            r#"{"a": 1}"#,
+            render(
+                &text(r#"{"a": 1}"#),
+                &column(ColumnType::MYSQL_TYPE_JSON, UTF8)
+            )
+        );
+        assert_eq!(
+            "0xCAFE",
+            render(
+                &Value::Bytes(vec![0xca, 0xfe]),
+                &column(ColumnType::MYSQL_TYPE_BLOB, BINARY_CHARSET)
+            )
+        );
+        assert_eq!(
+            "b'101'",
+            render(
+                &Value::Bytes(vec![0, 5]),
+                &column(ColumnType::MYSQL_TYPE_BIT, BINARY_CHARSET)
+            )
+        );
+        assert_eq!(
+            "2024-02-29 13:45:00.000250",
+            render(
+                &Value::Date(2024, 2, 29, 13, 45, 0, 250),
+                &column(ColumnType::MYSQL_TYPE_DATETIME, BINARY_CHARSET)
+            )
+        );
+        assert_eq!(
+            "2024-02-29",
+            render(
+                &Value::Date(2024, 2, 29, 0, 0, 0, 0),
+                &column(ColumnType::MYSQL_TYPE_DATE, BINARY_CHARSET)
+            )
+        );
+        assert_eq!(
+            "-26:30:00",
+            render(
+                &Value::Time(true, 1, 2, 30, 0, 0),
+                &column(ColumnType::MYSQL_TYPE_TIME, BINARY_CHARSET)
+            )
+        );
+    }
+
+    #[test]
+    fn unsupported_client_identity_names_the_probe() {
+        let certs = Certificates::new(None, None, None, Some("client.p12".to_string()), None, None);
+        let probe = MySql::new(
+            None,
+            None,
+            None,
+            "user".to_string(),
+            SecretString::new("password".to_string()),
+            None,
+            &GO,
+            Some(certs),
+        );
+        assert_matches!(
+            probe.ssl_opts(),
+            Err(FailedExecutionError { probe_identifier, source })
+                if probe_identifier.starts_with("MySQL - localhost:3306")
+                    && source.to_string().starts_with(
+                        "Unable to load the client certificate from 'client.p12'"
+                    )
+        );
+    }
+}
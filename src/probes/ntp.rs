@@ -0,0 +1,267 @@
+use std::io;
+use std::io::ErrorKind;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::error::InquestError::AssertionMatchingError;
+use crate::{Data, GlobalOptions, Ntp, Probe, ProbeReport, Result};
+
+const PROBE_NAME: &str = "NTP";
+
+/// Seconds from the NTP epoch (1900) to the Unix epoch (1970)
+const UNIX_OFFSET: i128 = 2_208_988_800;
+
+const NANOS: i128 = 1_000_000_000;
+
+impl Ntp {
+    pub(crate) fn new(servers: Vec<(String, u16)>, options: &'static GlobalOptions) -> Ntp {
+        Ntp {
+            servers,
+            max_offset: Duration::from_secs(1),
+            timeout: options.timeout,
+        }
+    }
+}
+
+/// Queries every server once with SNTP (RFC 4330). All of them have to answer, be synchronized
+/// and be within the maximum offset of the local clock.
+impl Probe for Ntp {
+    fn execute<'a>(&self) -> Result<ProbeReport> {
+        let mut report = ProbeReport::new(self.identifier());
+        let failures = self
+            .servers
+            .iter()
+            .filter_map(|(host, port)| check_server(self, host, *port, &mut report.data).err())
+            .collect::<Vec<_>>();
+
+        if failures.is_empty() {
+            Ok(report)
+        } else {
+            Err(AssertionMatchingError(failures.join("; "), report))
+        }
+    }
+
+    fn identifier(&self) -> String {
+        let servers = self
+            .servers
+            .iter()
+            .map(|(host, port)| format!("{}:{}", host, port))
+            .collect::<Vec<_>>();
+        format!("{} - {}", PROBE_NAME, servers.join(", "))
+    }
+}
+
+/// The result of one request, the times are in nanoseconds
+#[derive(Debug)]
+struct Sample {
+    leap: u8,
+    stratum: u8,
+    reference: [u8; 4],
+    offset: i128,
+    delay: i128,
+}
+
+fn check_server(
+    probe: &Ntp,
+    host: &str,
+    port: u16,
+    data: &mut Data,
+) -> std::result::Result<(), String> {
+    // a pool resolves to several servers, one of them is enough
+    let address = (host, port)
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addresses| addresses.next())
+        .ok_or_else(|| format!("Unable to resolve {}", host))?;
+    let sample = query(address, probe.timeout).map_err(|e| match e.kind() {
+        ErrorKind::WouldBlock | ErrorKind::TimedOut => format!(
+            "No response from {} within {} ms",
+            address,
+            probe.timeout.as_millis()
+        ),
+        _ => format!("Query of {} failed: {}", address, e),
+    })?;
+
+    if sample.stratum == 0 {
+        return Err(format!(
+            "{} refused the query with kiss code '{}'",
+            address,
+            String::from_utf8_lossy(&sample.reference)
+        ));
+    }
+    data.push((
+        format!("{} ({})", host, address),
+        format!(
+            "stratum {} (reference {}), delay {:.1} ms, offset {:+.1} ms",
+            sample.stratum,
+            reference(&sample),
+            millis(sample.delay),
+            millis(sample.offset)
+        ),
+    ));
+    if sample.leap == 3 {
+        return Err(format!("{} is not synchronized", address));
+    }
+    if sample.offset.unsigned_abs() > probe.max_offset.as_nanos() {
+        return Err(format!(
+            "The local clock is off by {:+.1} ms from {}, more than the allowed {} ms",
+            millis(sample.offset),
+            address,
+            probe.max_offset.as_millis()
+        ));
+    }
+    Ok(())
+}
+
+fn query(address: SocketAddr, timeout: Duration) -> io::Result<Sample> {
+    let local: SocketAddr = match address {
+        SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+        SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+    };
+    let socket = UdpSocket::bind(local)?;
+    socket.connect(address)?;
+    socket.set_read_timeout(Some(timeout))?;
+
+    let mut request = [0u8; 48];
+    // no leap warning, version 4, client mode
+    request[0] = 0b00_100_011;
+    let sent = now();
+    request[40..48].copy_from_slice(&to_timestamp(sent));
+    socket.send(&request)?;
+
+    let mut response = [0u8; 48];
+    loop {
+        let received = socket.recv(&mut response)?;
+        let arrived = now();
+        // late answers to an earlier request are skipped
+        if received < 48 || response[24..32] != request[40..48] {
+            continue;
+        }
+        let receive = from_timestamp(&response[32..40]);
+        let transmit = from_timestamp(&response[40..48]);
+        return Ok(Sample {
+            leap: response[0] >> 6,
+            stratum: response[1],
+            reference: response[12..16].try_into().unwrap(),
+            offset: ((receive - sent) + (transmit - arrived)) / 2,
+            delay: (arrived - sent) - (transmit - receive),
+        });
+    }
+}
+
+/// Primary servers name their source, all others the IPv4 address (or a hash) of theirs
+fn reference(sample: &Sample) -> String {
+    match sample.stratum {
+        1 => String::from_utf8_lossy(&sample.reference)
+            .trim_end_matches('\0')
+            .to_string(),
+        _ => Ipv4Addr::from(sample.reference).to_string(),
+    }
+}
+
+fn millis(nanos: i128) -> f64 {
+    nanos as f64 / 1_000_000.0
+}
+
+/// Nanoseconds since the NTP epoch
+fn now() -> i128 {
+    let since_unix = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    UNIX_OFFSET * NANOS + since_unix.as_nanos() as i128
+}
+
+fn to_timestamp(nanos: i128) -> [u8; 8] {
+    let seconds = (nanos / NANOS) as u32;
+    let fraction = (((nanos % NANOS) << 32) / NANOS) as u32;
+    let mut timestamp = [0u8; 8];
+    timestamp[..4].copy_from_slice(&seconds.to_be_bytes());
+    timestamp[4..].copy_from_slice(&fraction.to_be_bytes());
+    timestamp
+}
+
+fn from_timestamp(timestamp: &[u8]) -> i128 {
+    let seconds = u32::from_be_bytes(timestamp[..4].try_into().unwrap()) as i128;
+    let fraction = u32::from_be_bytes(timestamp[4..].try_into().unwrap()) as i128;
+    // the seconds wrap in 2036, RFC 4330 takes a cleared high bit as the next era
+    let seconds = match seconds < 1 << 31 {
+        true => seconds + (1 << 32),
+        false => seconds,
+    };
+    seconds * NANOS + ((fraction * NANOS) >> 32)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::UdpSocket;
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::error::InquestError::AssertionMatchingError;
+    use crate::probes::ntp::{now, to_timestamp, NANOS};
+    use crate::{Ntp, Probe, GO};
+
+    /// Answers with a clock that is ahead by the given seconds
+    fn server(ahead: i128, stratum: u8) -> u16 {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let port = server.local_addr().unwrap().port();
+        thread::spawn(move || {
+            let mut request = [0; 48];
+            while let Ok((_, client)) = server.recv_from(&mut request) {
+                let mut response = [0u8; 48];
+                response[0] = 0b00_100_100;
+                response[1] = stratum;
+                response[12..16].copy_from_slice(match stratum {
+                    0 => b"RATE",
+                    _ => &[192, 168, 0, 1],
+                });
+                response[24..32].copy_from_slice(&request[40..48]);
+                response[32..40].copy_from_slice(&to_timestamp(now() + ahead * NANOS));
+                response[40..48].copy_from_slice(&to_timestamp(now() + ahead * NANOS));
+                let _ = server.send_to(&response, client);
+            }
+        });
+        port
+    }
+
+    fn probe(port: u16) -> Ntp {
+        Ntp {
+            timeout: Duration::from_secs(1),
+            ..Ntp::new(vec![("127.0.0.1".to_string(), port)], &GO)
+        }
+    }
+
+    #[test]
+    fn offset_is_reported_and_asserted() {
+        let report = probe(server(0, 2)).execute().unwrap();
+        assert!(report.data[0]
+            .1
+            .starts_with("stratum 2 (reference 192.168.0.1), delay "));
+
+        assert_matches!(
+            probe(server(90, 2)).execute(),
+            Err(AssertionMatchingError(desc, report))
+                if desc.starts_with("The local clock is off by +90000")
+                    && desc.ends_with("more than the allowed 1000 ms")
+                    && report.data[0].1.contains("offset +90000")
+        );
+    }
+
+    #[test]
+    fn kiss_of_death_and_silence_fail() {
+        assert_matches!(
+            probe(server(0, 0)).execute(),
+            Err(AssertionMatchingError(desc, _)) if desc.ends_with("kiss code 'RATE'")
+        );
+
+        let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let probe = Ntp {
+            timeout: Duration::from_millis(200),
+            ..probe(silent.local_addr().unwrap().port())
+        };
+        assert_matches!(
+            probe.execute(),
+            Err(AssertionMatchingError(desc, _)) if desc.starts_with("No response from 127.0.0.1")
+        );
+    }
+}
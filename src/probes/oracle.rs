@@ -4,7 +4,6 @@ use crate::error::InquestError::{
     AssertionMatchingError, FailedAssertionError, FailedExecutionError,
 };
 use crate::probes::sql::Table;
-use crate::probes::tcp::foo;
 use crate::Result;
 use crate::{Data, GlobalOptions, Oracle, Probe, ProbeReport, SqlTest};
 use oracle::{Connection, Row};
@@ -62,7 +61,7 @@ impl Probe for Oracle {
 
 fn establish_connection(probe: &Oracle) -> Result<Connection> {
     let connection_string = format!("//{}:{}/{}", &probe.host, &probe.port, &probe.sid);
-    let r = Connection::connect(
+    Connection::connect(
         &probe.user,
         probe.password.expose_secret(),
         connection_string,
@@ -70,11 +69,7 @@ fn establish_connection(probe: &Oracle) -> Result<Connection> {
     .map_err(|e| FailedExecutionError {
         probe_identifier: probe.identifier(),
         source: Box::new(e),
-    });
-    if r.is_err() {
-        foo(probe)
-    }
-    r
+    })
 }
 
 fn run_sql(probe: &Oracle, connection: &Connection, _: &ProbeReport) -> Result<Data> {
@@ -4,6 +4,9 @@ use crate::error::InquestError::{
     AssertionMatchingError, FailedAssertionError, FailedExecutionError,
 };
 use crate::probes::sql::Table;
+use crate::probes::tls::{
+    certificate_mismatch, load_client_identity, recording_verifier, PeerCertificates,
+};
 use crate::{Certificates, Result};
 use crate::{Data, GlobalOptions, Postgres, Probe, ProbeReport, SqlTest};
 use chrono::Utc;
@@ -37,6 +40,7 @@ impl Postgres {
             password,
             sql,
             certs,
+            tls: None,
         }
     }
 }
@@ -44,19 +48,21 @@ impl Postgres {
 /// Implements a Postgres probe based on the postgres crate.
 impl Probe for Postgres {
     fn execute(&self) -> Result<ProbeReport> {
+        let peer_certificates = PeerCertificates::default();
         let future = async {
-            match establish_connection(self).await {
+            match establish_connection(self, peer_certificates.clone()).await {
                 Ok((client, con)) => Ok((client, con)),
                 Err(e) => Err(e),
             }
         };
         let tokio_runtime = Runtime::new().unwrap();
-        let mut client_con = tokio_runtime
-            .block_on(future)
-            .map_err(|e| FailedExecutionError {
-                probe_identifier: self.identifier(),
-                source: Box::new(e),
-            })?;
+        let mut client_con = tokio_runtime.block_on(future).map_err(|e| {
+            certificate_mismatch(self.identifier(), self.tls.as_ref(), &peer_certificates)
+                .unwrap_or(FailedExecutionError {
+                    probe_identifier: self.identifier(),
+                    source: Box::new(e),
+                })
+        })?;
 
         // The connection object performs the actual communication with the database,
         // so spawn it off to run on its own.
@@ -84,6 +90,7 @@ impl Probe for Postgres {
 
 async fn establish_connection(
     probe: &Postgres,
+    peer_certificates: PeerCertificates,
 ) -> Result<(Client, Connection<Socket, RustlsStream<Socket>>)> {
     let mut root_store = rustls::RootCertStore::empty();
     // root_store.add_server_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.0.iter().map(|ta| {
@@ -94,47 +101,47 @@ async fn establish_connection(
     //     )
     // }));
 
+    let mut anchors = Vec::new();
     for cert in rustls_native_certs::load_native_certs().expect("could not load platform certs") {
-        root_store.add(&rustls::Certificate(cert.0)).unwrap();
+        root_store
+            .add(&rustls::Certificate(cert.0.clone()))
+            .unwrap();
+        anchors.push(rustls::Certificate(cert.0));
     }
 
-    let tls_client_config = if let Some(cert_options) = &probe.certs {
-        if let Some(ca_cert_path) = &cert_options.ca_cert {
-            let ca_certs = certs::load_certificate_chain(ca_cert_path, probe)?;
-            ca_certs
-                .iter()
-                .try_for_each(|ca| root_store.add(ca))?
-                // .map_err(|e| FailedExecutionError {
-                //     probe_identifier: probe.identifier(),
-                //     source: Box::new(e),
-                // })?
-                ;
-        }
-
-        if let (Some(client_key), Some(client_cert)) =
-            (&cert_options.client_key, &cert_options.client_cert)
-        {
-            let certs = certs::load_certificate_chain(client_cert, probe)?;
-            let private_key = certs::load_private_key(client_key, probe)?;
-            rustls::ClientConfig::builder()
-                .with_safe_defaults()
-                .with_root_certificates(root_store)
-                .with_single_cert(certs, private_key)?
-            // .map_err(|e| FailedExecutionError {
-            //     probe_identifier: probe.identifier(),
-            //     source: Box::new(e),
-            // })?
-        } else {
-            rustls::ClientConfig::builder()
-                .with_safe_defaults()
-                .with_root_certificates(root_store)
-                .with_no_client_auth()
-        }
+    if let Some(ca_cert_path) = probe
+        .certs
+        .as_ref()
+        .and_then(|cert_options| cert_options.ca_cert.as_ref())
+    {
+        let ca_certs = certs::load_certificate_chain(ca_cert_path, probe)?;
+        ca_certs.iter().try_for_each(|ca| root_store.add(ca))?;
+        anchors.extend(ca_certs);
+    }
+    let (root_store, anchors) = if probe.certs.is_some() {
+        (root_store, anchors)
     } else {
-        rustls::ClientConfig::builder()
-            .with_safe_defaults()
-            .with_root_certificates(RootCertStore::empty())
-            .with_no_client_auth()
+        (RootCertStore::empty(), Vec::new())
+    };
+    let builder = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_custom_certificate_verifier(recording_verifier(
+            root_store,
+            anchors,
+            probe.tls.as_ref(),
+            peer_certificates,
+        ));
+
+    // PKCS#12, combined PEM or separate PEM files, the same way as for HTTP
+    let tls_client_config = match probe
+        .certs
+        .as_ref()
+        .map(load_client_identity)
+        .transpose()?
+        .flatten()
+    {
+        Some((chain, key)) => builder.with_single_cert(chain, key)?,
+        None => builder.with_no_client_auth(),
     };
 
     let tls = tokio_postgres_rustls::MakeRustlsConnect::new(tls_client_config);
@@ -333,8 +340,8 @@ impl ToSocketAddrs for Postgres {
 
 mod certs {
     use crate::InquestError::FailedExecutionError;
+    use crate::Probe;
     use crate::Result;
-    use crate::{InquestError, Probe};
     use std::{fs, io};
 
     pub(crate) fn load_certificate_chain(
@@ -356,38 +363,12 @@ mod certs {
 
         Ok(certs.into_iter().map(rustls::Certificate).collect())
     }
-
-    pub(crate) fn load_private_key(
-        filename: &str,
-        probe: &dyn Probe,
-    ) -> Result<rustls::PrivateKey> {
-        // Open keyfile.
-        let keyfile = fs::File::open(filename).map_err(|e| FailedExecutionError {
-            probe_identifier: probe.identifier(),
-            source: Box::new(e),
-        })?;
-        let mut reader = io::BufReader::new(keyfile);
-        // Load and return a single private key.
-        let keys =
-            rustls_pemfile::pkcs8_private_keys(&mut reader).map_err(|e| FailedExecutionError {
-                probe_identifier: probe.identifier(),
-                source: Box::new(e),
-            })?;
-        if keys.len() != 1 {
-            return Err(InquestError::EmptySource);
-        }
-
-        Ok(rustls::PrivateKey(keys[0].clone()))
-
-        // let mut data = Vec::new();
-        // keyfile.read_to_end(&mut data)?;
-        // Ok(rustls::PrivateKey(data))
-    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{Postgres, GO};
+    use crate::error::InquestError::FailedExecutionError;
+    use crate::{Certificates, Postgres, Probe, GO};
     use secrecy::SecretString;
     use std::str::FromStr;
 
@@ -408,4 +389,35 @@ mod tests {
         assert_eq!(5432, probe.port);
         assert_eq!("postgres", &probe.database);
     }
+
+    #[test]
+    fn client_pkcs12_is_used() {
+        let certs = Certificates::new(
+            None,
+            None,
+            None,
+            Some("does-not-exist.p12".to_string()),
+            None,
+            None,
+        );
+        let probe = Postgres::new(
+            None,
+            None,
+            None,
+            "user".to_string(),
+            SecretString::from_str("password").unwrap(),
+            None,
+            &GO,
+            Some(certs),
+        );
+
+        assert_matches!(
+            probe.execute(),
+            Err(FailedExecutionError { probe_identifier, source })
+                if probe_identifier == "Postgres - localhost:5432/postgres/user"
+                    && source.to_string().starts_with(
+                        "Unable to load the client certificate from 'does-not-exist.p12'"
+                    )
+        );
+    }
 }
@@ -0,0 +1,145 @@
+use roxmltree::{Document, Node};
+use serde_json::Value;
+
+use crate::HttpProtocol;
+
+const SOAP_11_ENVELOPE: &str = "http://schemas.xmlsoap.org/soap/envelope/";
+const SOAP_12_ENVELOPE: &str = "http://www.w3.org/2003/05/soap-envelope";
+
+impl HttpProtocol {
+    /// The errors reported within the response-body, which are not visible in the HTTP status
+    pub(super) fn errors(&self, body: &str) -> Vec<String> {
+        match self {
+            HttpProtocol::GraphQl => graphql_errors(body),
+            HttpProtocol::Soap => soap_faults(body),
+        }
+    }
+}
+
+fn graphql_errors(body: &str) -> Vec<String> {
+    let result = match serde_json::from_str::<Value>(body) {
+        Ok(result) => result,
+        Err(e) => return vec![format!("Response is not a GraphQL result: {}", e)],
+    };
+    let Value::Array(errors) = &result["errors"] else {
+        return Vec::new();
+    };
+    errors
+        .iter()
+        .map(|error| {
+            let message = error["message"]
+                .as_str()
+                .map_or(error.to_string(), str::to_string);
+            match &error["path"] {
+                Value::Array(path) if !path.is_empty() => {
+                    let path = path
+                        .iter()
+                        .map(|segment| match segment {
+                            Value::String(field) => field.clone(),
+                            index => index.to_string(),
+                        })
+                        .collect::<Vec<_>>();
+                    format!("GraphQL error at '{}': {}", path.join("."), message)
+                }
+                _ => format!("GraphQL error: {}", message),
+            }
+        })
+        .collect()
+}
+
+fn soap_faults(body: &str) -> Vec<String> {
+    let document = match Document::parse(body) {
+        Ok(document) => document,
+        Err(e) => return vec![format!("Response is not a SOAP envelope: {}", e)],
+    };
+    document
+        .descendants()
+        .filter(|node| is_soap_element(node, "Fault"))
+        .map(|fault| {
+            // SOAP 1.1 uses unqualified 'faultcode' and 'faultstring', SOAP 1.2 'Code' and 'Reason'
+            let code = descendant_text(fault, "faultcode").or_else(|| {
+                fault
+                    .descendants()
+                    .find(|node| is_soap_element(node, "Code"))
+                    .and_then(|code| descendant_text(code, "Value"))
+            });
+            let reason = descendant_text(fault, "faultstring").or_else(|| {
+                fault
+                    .descendants()
+                    .find(|node| is_soap_element(node, "Reason"))
+                    .and_then(|reason| descendant_text(reason, "Text"))
+            });
+            format!(
+                "SOAP Fault '{}': {}",
+                code.unwrap_or("unknown"),
+                reason.unwrap_or("no reason given")
+            )
+        })
+        .collect()
+}
+
+fn is_soap_element(node: &Node, name: &str) -> bool {
+    node.is_element()
+        && node.tag_name().name() == name
+        && matches!(
+            node.tag_name().namespace(),
+            Some(SOAP_11_ENVELOPE) | Some(SOAP_12_ENVELOPE)
+        )
+}
+
+fn descendant_text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.descendants()
+        .find(|node| node.is_element() && node.tag_name().name() == name)
+        .and_then(|node| node.text())
+        .map(str::trim)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::HttpProtocol;
+
+    #[test]
+    fn graphql_errors_are_detected() {
+        let body = r#"{
+            "data": { "order": null },
+            "errors": [{ "message": "Order not found", "path": ["order", 0, "id"] }]
+        }"#;
+
+        assert_eq!(
+            vec!["GraphQL error at 'order.0.id': Order not found".to_string()],
+            HttpProtocol::GraphQl.errors(body)
+        );
+        assert!(HttpProtocol::GraphQl
+            .errors(r#"{"data": {"order": {"id": 42}}}"#)
+            .is_empty());
+    }
+
+    #[test]
+    fn soap_faults_are_detected() {
+        let soap_11 = r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/">
+              <soap:Body>
+                <soap:Fault>
+                  <faultcode>soap:Server</faultcode>
+                  <faultstring>Backend unavailable</faultstring>
+                </soap:Fault>
+              </soap:Body>
+            </soap:Envelope>"#;
+        let soap_12 = r#"<env:Envelope xmlns:env="http://www.w3.org/2003/05/soap-envelope">
+              <env:Body>
+                <env:Fault>
+                  <env:Code><env:Value>env:Sender</env:Value></env:Code>
+                  <env:Reason><env:Text xml:lang="en">Invalid request</env:Text></env:Reason>
+                </env:Fault>
+              </env:Body>
+            </env:Envelope>"#;
+
+        assert_eq!(
+            vec!["SOAP Fault 'soap:Server': Backend unavailable".to_string()],
+            HttpProtocol::Soap.errors(soap_11)
+        );
+        assert_eq!(
+            vec!["SOAP Fault 'env:Sender': Invalid request".to_string()],
+            HttpProtocol::Soap.errors(soap_12)
+        );
+    }
+}
@@ -0,0 +1,407 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::sync::Arc;
+use std::time::Instant;
+
+use secrecy::ExposeSecret;
+
+use crate::error::InquestError::{AssertionMatchingError, FailedExecutionError};
+use crate::probes::stream::{connect, Stream};
+use crate::probes::tls::{certificate_mismatch, client_config, PeerCertificates};
+use crate::{Certificates, GlobalOptions, Probe, ProbeReport, Redis, Result};
+
+const PROBE_NAME: &str = "Redis";
+
+/// The replies to the probe's commands are small, anything larger is refused before allocating
+const MAX_BULK_SIZE: usize = 16 * 1024 * 1024;
+const MAX_ELEMENTS: usize = 1024 * 1024;
+
+impl Redis {
+    pub(crate) fn new(
+        host: String,
+        port: Option<u16>,
+        certs: Option<Certificates>,
+        options: &'static GlobalOptions,
+    ) -> Redis {
+        Redis {
+            host,
+            port: port.unwrap_or(6379),
+            user: None,
+            password: None,
+            tls: false,
+            tls_test: None,
+            timeout: options.timeout,
+            certs,
+            commands: Vec::new(),
+        }
+    }
+}
+
+/// A reply of the RESP2 protocol, which Redis speaks as long as no `HELLO 3` is sent
+#[derive(Debug, PartialEq)]
+enum Reply {
+    Status(String),
+    Error(String),
+    Integer(i64),
+    Bulk(Option<Vec<u8>>),
+    Array(Option<Vec<Reply>>),
+}
+
+/// Renders a reply similar to `redis-cli`, but without type hints for status, numbers and
+/// strings, so that patterns match the plain values.
+impl Display for Reply {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Reply::Status(status) => write!(f, "{}", status),
+            Reply::Error(error) => write!(f, "(error) {}", error),
+            Reply::Integer(integer) => write!(f, "{}", integer),
+            Reply::Bulk(None) | Reply::Array(None) => write!(f, "(nil)"),
+            Reply::Bulk(Some(bulk)) => write!(f, "{}", String::from_utf8_lossy(bulk)),
+            Reply::Array(Some(items)) if items.is_empty() => write!(f, "(empty array)"),
+            Reply::Array(Some(items)) => {
+                let items = items
+                    .iter()
+                    .enumerate()
+                    .map(|(index, item)| format!("{}) {}", index + 1, item))
+                    .collect::<Vec<_>>();
+                write!(f, "{}", items.join("\n"))
+            }
+        }
+    }
+}
+
+impl Probe for Redis {
+    fn execute<'a>(&self) -> Result<ProbeReport> {
+        let failed = |e: io::Error| FailedExecutionError {
+            probe_identifier: self.identifier(),
+            source: Box::new(e),
+        };
+        let peer_certificates = PeerCertificates::default();
+        let config = match self.tls {
+            true => Some(Arc::new(
+                client_config(
+                    self.certs.as_ref(),
+                    self.tls_test.as_ref(),
+                    peer_certificates.clone(),
+                )
+                .map_err(|e| FailedExecutionError {
+                    probe_identifier: self.identifier(),
+                    source: Box::new(e),
+                })?,
+            )),
+            false => None,
+        };
+
+        let started = Instant::now();
+        let socket = connect(&self.host, self.port, self.timeout).map_err(failed)?;
+        let mut report = ProbeReport::new(self.identifier());
+        report.data.push((
+            "Connection".to_string(),
+            format!(
+                "{} connected in {:.1} ms",
+                socket
+                    .peer_addr()
+                    .map_or("unknown".to_string(), |peer| peer.to_string()),
+                started.elapsed().as_secs_f64() * 1000.0
+            ),
+        ));
+        let mut stream = Stream::Plain(socket);
+        if let Some(config) = config {
+            stream = match stream.upgrade(&self.host, config) {
+                Ok(stream) => stream,
+                Err(e) => {
+                    return Err(certificate_mismatch(
+                        self.identifier(),
+                        self.tls_test.as_ref(),
+                        &peer_certificates,
+                    )
+                    .unwrap_or(AssertionMatchingError(e.to_string(), report)))
+                }
+            };
+            if let Some(tls) = stream.tls_description() {
+                report.data.push(("TLS".to_string(), tls));
+            }
+        }
+        let mut connection = BufReader::new(stream);
+
+        if let Some(password) = &self.password {
+            let mut arguments = vec!["AUTH"];
+            arguments.extend(self.user.as_deref());
+            arguments.push(password.expose_secret());
+            if let Reply::Error(error) = command(&mut connection, &arguments).map_err(failed)? {
+                return Err(AssertionMatchingError(
+                    format!(
+                        "Authentication as '{}' failed: {}",
+                        self.user.as_deref().unwrap_or("default"),
+                        error
+                    ),
+                    report,
+                ));
+            }
+        }
+
+        let started = Instant::now();
+        match command(&mut connection, &["PING"]).map_err(failed)? {
+            Reply::Status(pong) if pong == "PONG" => report.data.push((
+                "PING".to_string(),
+                format!("PONG in {:.1} ms", started.elapsed().as_secs_f64() * 1000.0),
+            )),
+            other => {
+                return Err(AssertionMatchingError(
+                    format!("PING was answered with '{}'", other),
+                    report,
+                ))
+            }
+        }
+
+        match command(&mut connection, &["INFO"]).map_err(failed)? {
+            Reply::Bulk(Some(info)) => report
+                .data
+                .extend(describe_server(&String::from_utf8_lossy(&info))),
+            // the command might be forbidden by the ACL or renamed
+            other => report.data.push(("Info".to_string(), other.to_string())),
+        }
+
+        let mut failures = Vec::new();
+        for redis_command in &self.commands {
+            let reply = command(&mut connection, &redis_command.arguments).map_err(failed)?;
+            let name = redis_command.arguments.join(" ");
+            match (&reply, &redis_command.expected) {
+                (Reply::Error(error), _) => {
+                    failures.push(format!("Command '{}' failed: {}", name, error))
+                }
+                (reply, Some(expected)) if !expected.is_match(&reply.to_string()) => {
+                    failures.push(format!("Reply of '{}' does not match '{}'", name, expected))
+                }
+                _ => {}
+            }
+            report.data.push((format!("> {}", name), reply.to_string()));
+        }
+
+        if failures.is_empty() {
+            Ok(report)
+        } else {
+            Err(AssertionMatchingError(failures.join("; "), report))
+        }
+    }
+
+    fn identifier(&self) -> String {
+        format!("{} - {}:{}", PROBE_NAME, self.host, self.port)
+    }
+}
+
+/// Sends the command as array of bulk strings and reads its reply
+fn command<R: Read + Write>(
+    connection: &mut BufReader<R>,
+    arguments: &[impl AsRef<str>],
+) -> io::Result<Reply> {
+    let mut request = format!("*{}\r\n", arguments.len()).into_bytes();
+    for argument in arguments {
+        let argument = argument.as_ref();
+        write!(request, "${}\r\n{}\r\n", argument.len(), argument)?;
+    }
+    let stream = connection.get_mut();
+    stream.write_all(&request)?;
+    stream.flush()?;
+    read_reply(connection)
+}
+
+fn read_reply(reader: &mut impl BufRead) -> io::Result<Reply> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(io::Error::new(
+            ErrorKind::UnexpectedEof,
+            "connection closed by the server",
+        ));
+    }
+    let line = line.trim_end_matches(['\r', '\n']);
+    let invalid = || io::Error::new(ErrorKind::InvalidData, format!("invalid reply '{}'", line));
+    let value = line.get(1..).ok_or_else(invalid)?;
+    let length = || value.parse::<i64>().map_err(|_| invalid());
+    let too_large = || {
+        io::Error::new(
+            ErrorKind::InvalidData,
+            format!("reply '{}' is too large, is this a Redis server?", line),
+        )
+    };
+    match line.chars().next() {
+        Some('+') => Ok(Reply::Status(value.to_string())),
+        Some('-') => Ok(Reply::Error(value.to_string())),
+        Some(':') => Ok(Reply::Integer(length()?)),
+        Some('$') => match usize::try_from(length()?) {
+            Ok(length) if length > MAX_BULK_SIZE => Err(too_large()),
+            Ok(length) => {
+                let mut bulk = vec![0; length + 2];
+                reader.read_exact(&mut bulk)?;
+                bulk.truncate(length);
+                Ok(Reply::Bulk(Some(bulk)))
+            }
+            Err(_) => Ok(Reply::Bulk(None)),
+        },
+        Some('*') => match usize::try_from(length()?) {
+            Ok(count) if count > MAX_ELEMENTS => Err(too_large()),
+            Ok(count) => Ok(Reply::Array(Some(
+                (0..count)
+                    .map(|_| read_reply(reader))
+                    .collect::<io::Result<_>>()?,
+            ))),
+            Err(_) => Ok(Reply::Array(None)),
+        },
+        _ => Err(invalid()),
+    }
+}
+
+/// Picks version, role and memory usage from the output of `INFO`
+fn describe_server(info: &str) -> Vec<(String, String)> {
+    let field = |name: &str| {
+        info.lines()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value.trim())
+            .unwrap_or("unknown")
+    };
+    let role = match field("role") {
+        "master" => format!("master with {} replicas", field("connected_slaves")),
+        "slave" => format!(
+            "replica of {}:{}, link {}",
+            field("master_host"),
+            field("master_port"),
+            field("master_link_status")
+        ),
+        other => other.to_string(),
+    };
+    let memory = match field("maxmemory") {
+        "0" | "unknown" => format!("{} used", field("used_memory_human")),
+        _ => format!(
+            "{} used of {} ({})",
+            field("used_memory_human"),
+            field("maxmemory_human"),
+            field("maxmemory_policy")
+        ),
+    };
+    vec![
+        ("Version".to_string(), field("redis_version").to_string()),
+        ("Role".to_string(), role),
+        ("Memory".to_string(), memory),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufReader, ErrorKind, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use regex::Regex;
+    use secrecy::SecretString;
+
+    use crate::error::InquestError::{AssertionMatchingError, FailedExecutionError};
+    use crate::probes::redis::{read_reply, Reply};
+    use crate::{Certificates, Probe, Redis, RedisCommand, GO};
+
+    const INFO: &str = "# Server\r\nredis_version:7.2.4\r\n\r\n# Replication\r\nrole:master\r\n\
+        connected_slaves:2\r\n\r\n# Memory\r\nused_memory_human:1.05M\r\nmaxmemory:0\r\n";
+
+    /// Answers like Redis with the ACL user 'probe' and the password 'secret'
+    fn redis_server() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut writer = stream;
+                while let Ok(Reply::Array(Some(request))) = read_reply(&mut reader) {
+                    let arguments = request.iter().map(Reply::to_string).collect::<Vec<_>>();
+                    let reply = match arguments[0].as_str() {
+                        "AUTH" if arguments[1..] == ["probe", "secret"] => "+OK\r\n".to_string(),
+                        "AUTH" => "-WRONGPASS invalid username-password pair\r\n".to_string(),
+                        "PING" => "+PONG\r\n".to_string(),
+                        "INFO" => format!("${}\r\n{}\r\n", INFO.len(), INFO),
+                        "GET" => "$5\r\nhello\r\n".to_string(),
+                        "LRANGE" => "*2\r\n:1\r\n$-1\r\n".to_string(),
+                        _ => "-ERR unknown command\r\n".to_string(),
+                    };
+                    writer.write_all(reply.as_bytes()).unwrap();
+                }
+            }
+        });
+        port
+    }
+
+    fn probe(port: u16, password: &str, commands: Vec<(&str, Option<&str>)>) -> Redis {
+        Redis {
+            user: Some("probe".to_string()),
+            password: Some(SecretString::new(password.to_string())),
+            commands: commands
+                .into_iter()
+                .map(|(command, expected)| RedisCommand {
+                    arguments: command.split_whitespace().map(String::from).collect(),
+                    expected: expected.map(|expected| Regex::new(expected).unwrap()),
+                })
+                .collect(),
+            ..Redis::new("127.0.0.1".to_string(), Some(port), None, &GO)
+        }
+    }
+
+    #[test]
+    fn server_and_replies_are_reported() {
+        let port = redis_server();
+
+        let report = probe(port, "secret", vec![("LRANGE list 0 1", None)])
+            .execute()
+            .unwrap();
+        assert_eq!(
+            vec![
+                ("Version", "7.2.4"),
+                ("Role", "master with 2 replicas"),
+                ("Memory", "1.05M used"),
+                ("> LRANGE list 0 1", "1) 1\n2) (nil)"),
+            ],
+            report.data[2..]
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.as_str()))
+                .collect::<Vec<_>>()
+        );
+        assert!(report.data[1].1.starts_with("PONG in "));
+
+        assert_matches!(
+            probe(port, "secret", vec![("GET key", Some("^world$")), ("SCAN 0", None)]).execute(),
+            Err(AssertionMatchingError(desc, _)) if desc == "Reply of 'GET key' does not match \
+                '^world$'; Command 'SCAN 0' failed: ERR unknown command"
+        );
+    }
+
+    #[test]
+    fn wrong_password_fails() {
+        assert_matches!(
+            probe(redis_server(), "guess", Vec::new()).execute(),
+            Err(AssertionMatchingError(desc, _))
+                if desc == "Authentication as 'probe' failed: WRONGPASS invalid username-password pair"
+        );
+    }
+
+    #[test]
+    fn missing_ca_names_the_probe() {
+        let certs = Certificates::new(None, None, None, None, None, Some("ca.crt".to_string()));
+        let probe = Redis {
+            tls: true,
+            ..Redis::new("127.0.0.1".to_string(), Some(6379), Some(certs), &GO)
+        };
+        assert_matches!(
+            probe.execute(),
+            Err(FailedExecutionError { probe_identifier, .. })
+                if probe_identifier == "Redis - 127.0.0.1:6379"
+        );
+    }
+
+    #[test]
+    fn oversized_reply_is_rejected_before_reading() {
+        for reply in ["$9223372036854775807\r\n", "*9223372036854775807\r\n"] {
+            assert_eq!(
+                Some(ErrorKind::InvalidData),
+                read_reply(&mut reply.as_bytes()).err().map(|e| e.kind())
+            );
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use std::io;
+use std::io::ErrorKind;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use pbkdf2::pbkdf2_hmac;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use sha2::{Digest, Sha256, Sha512};
+
+/// Iterations of the key derivation the server may ask for, since every one of them costs the
+/// probe an HMAC
+const MAX_ITERATIONS: u32 = 100_000;
+
+/// The hash function of a SCRAM mechanism
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum ScramHash {
+    Sha256,
+    Sha512,
+}
+
+/// The client side of a SCRAM exchange (RFC 5802) without channel binding. The password is used
+/// as given, without SASLprep, which makes no difference for ASCII passwords.
+pub(super) struct ScramClient {
+    hash: ScramHash,
+    password: String,
+    nonce: String,
+    client_first_bare: String,
+    server_signature: Option<Vec<u8>>,
+}
+
+impl ScramHash {
+    fn hmac(self, key: &[u8], data: &[u8]) -> Vec<u8> {
+        match self {
+            ScramHash::Sha256 => Hmac::<Sha256>::new_from_slice(key)
+                .expect("HMAC accepts keys of any size")
+                .chain_update(data)
+                .finalize()
+                .into_bytes()
+                .to_vec(),
+            ScramHash::Sha512 => Hmac::<Sha512>::new_from_slice(key)
+                .expect("HMAC accepts keys of any size")
+                .chain_update(data)
+                .finalize()
+                .into_bytes()
+                .to_vec(),
+        }
+    }
+
+    fn hash(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            ScramHash::Sha256 => Sha256::digest(data).to_vec(),
+            ScramHash::Sha512 => Sha512::digest(data).to_vec(),
+        }
+    }
+
+    fn salted_password(self, password: &[u8], salt: &[u8], iterations: u32) -> Vec<u8> {
+        match self {
+            ScramHash::Sha256 => {
+                let mut salted = [0; 32];
+                pbkdf2_hmac::<Sha256>(password, salt, iterations, &mut salted);
+                salted.to_vec()
+            }
+            ScramHash::Sha512 => {
+                let mut salted = [0; 64];
+                pbkdf2_hmac::<Sha512>(password, salt, iterations, &mut salted);
+                salted.to_vec()
+            }
+        }
+    }
+}
+
+impl ScramClient {
+    pub(super) fn new(hash: ScramHash, user: &str, password: &str) -> ScramClient {
+        let nonce = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(24)
+            .map(char::from)
+            .collect::<String>();
+        ScramClient::with_nonce(hash, user, password, nonce)
+    }
+
+    fn with_nonce(hash: ScramHash, user: &str, password: &str, nonce: String) -> ScramClient {
+        let user = user.replace('=', "=3D").replace(',', "=2C");
+        ScramClient {
+            hash,
+            password: password.to_string(),
+            client_first_bare: format!("n={},r={}", user, nonce),
+            nonce,
+            server_signature: None,
+        }
+    }
+
+    pub(super) fn client_first(&self) -> String {
+        format!("n,,{}", self.client_first_bare)
+    }
+
+    /// Answers the challenge of the server with the proof of the password
+    pub(super) fn client_final(&mut self, server_first: &str) -> io::Result<String> {
+        let invalid = |desc: String| io::Error::new(ErrorKind::InvalidData, desc);
+        let attribute = |name: char| {
+            server_first
+                .split(',')
+                .find_map(|field| field.strip_prefix(name)?.strip_prefix('='))
+                .ok_or_else(|| invalid(format!("invalid SCRAM challenge '{}'", server_first)))
+        };
+        let nonce = attribute('r')?;
+        if !nonce.starts_with(&self.nonce) {
+            return Err(invalid(
+                "SCRAM challenge does not continue the client nonce".to_string(),
+            ));
+        }
+        let salt = STANDARD
+            .decode(attribute('s')?)
+            .map_err(|e| invalid(format!("invalid SCRAM salt: {}", e)))?;
+        let iterations = attribute('i')?
+            .parse::<u32>()
+            .map_err(|e| invalid(format!("invalid SCRAM iteration count: {}", e)))?;
+        if iterations > MAX_ITERATIONS {
+            return Err(invalid(format!(
+                "SCRAM iteration count {} exceeds {}",
+                iterations, MAX_ITERATIONS
+            )));
+        }
+
+        let salted_password =
+            self.hash
+                .salted_password(self.password.as_bytes(), &salt, iterations);
+        let client_key = self.hash.hmac(&salted_password, b"Client Key");
+        let stored_key = self.hash.hash(&client_key);
+        // 'biws' is the encoded GS2 header 'n,,'
+        let client_final_without_proof = format!("c=biws,r={}", nonce);
+        let auth_message = format!(
+            "{},{},{}",
+            self.client_first_bare, server_first, client_final_without_proof
+        );
+        let client_signature = self.hash.hmac(&stored_key, auth_message.as_bytes());
+        let proof = client_key
+            .iter()
+            .zip(client_signature)
+            .map(|(key, signature)| key ^ signature)
+            .collect::<Vec<_>>();
+        let server_key = self.hash.hmac(&salted_password, b"Server Key");
+        self.server_signature = Some(self.hash.hmac(&server_key, auth_message.as_bytes()));
+
+        Ok(format!(
+            "{},p={}",
+            client_final_without_proof,
+            STANDARD.encode(proof)
+        ))
+    }
+
+    /// Verifies that the server knows the password as well
+    pub(super) fn verify_server_final(&self, server_final: &str) -> Result<(), String> {
+        if let Some(error) = server_final.strip_prefix("e=") {
+            return Err(error.to_string());
+        }
+        let verifier = server_final
+            .strip_prefix("v=")
+            .and_then(|verifier| STANDARD.decode(verifier).ok());
+        match (verifier, &self.server_signature) {
+            (Some(verifier), Some(signature)) if verifier == *signature => Ok(()),
+            _ => Err("invalid SCRAM server signature".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use crate::probes::scram::{ScramClient, ScramHash};
+
+    /// The example exchange of RFC 7677
+    #[test]
+    fn scram_sha_256_exchange() {
+        let mut client = ScramClient::with_nonce(
+            ScramHash::Sha256,
+            "user",
+            "pencil",
+            "rOprNGfwEbeRWgbNEkqO".to_string(),
+        );
+        assert_eq!("n,,n=user,r=rOprNGfwEbeRWgbNEkqO", client.client_first());
+
+        let client_final = client.client_final(
+            "r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,\
+            s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096",
+        );
+        assert_eq!(
+            "c=biws,r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,\
+            p=dHzbZapWIk4jUhN+Ute9ytag9zjfMHgsqmmiz7AndVQ=",
+            client_final.unwrap()
+        );
+        assert_eq!(
+            Ok(()),
+            client.verify_server_final("v=6rriTRBi23WpRR/wtup+mMhUZUn/dB5nLTJRsjl95G4=")
+        );
+        assert!(client.verify_server_final("e=invalid-proof").is_err());
+    }
+
+    #[test]
+    fn excessive_iteration_count_is_rejected() {
+        let mut client = ScramClient::with_nonce(
+            ScramHash::Sha512,
+            "user",
+            "pencil",
+            "rOprNGfwEbeRWgbNEkqO".to_string(),
+        );
+        assert_matches!(
+            client.client_final("r=rOprNGfwEbeRWgbNEkqO%hvYD,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4294967295"),
+            Err(e) if e.kind() == ErrorKind::InvalidData
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use std::io;
+use std::io::ErrorKind;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use russh::{client, Disconnect};
+use russh_sftp::client::SftpSession;
+use secrecy::ExposeSecret;
+use tokio::io::AsyncWriteExt;
+use tokio::runtime::Runtime;
+
+use crate::probes::ftp::listed;
+use crate::probes::ssh::{ssh_error, unpinned, HostKey};
+use crate::probes::stream::Failure;
+use crate::{Data, Ftp};
+
+/// Logs in over SSH and lists the directory with the SFTP subsystem, credentials are only sent
+/// when the host key matches the expected fingerprint
+pub(super) fn transfer(ftp: &Ftp, data: &mut Data) -> Result<(), Failure> {
+    Runtime::new()?.block_on(session(ftp, data))
+}
+
+async fn session(ftp: &Ftp, data: &mut Data) -> Result<(), Failure> {
+    let seen = Arc::new(Mutex::new(None));
+    let handler = HostKey {
+        seen: seen.clone(),
+        expected: ftp.fingerprint.clone(),
+    };
+    let config = Arc::new(client::Config {
+        inactivity_timeout: Some(ftp.timeout),
+        ..Default::default()
+    });
+    let started = Instant::now();
+    let connected = tokio::time::timeout(
+        ftp.timeout,
+        client::connect(config, (ftp.host.as_str(), ftp.port), handler),
+    )
+    .await
+    .map_err(|_| io::Error::new(ErrorKind::TimedOut, "connection timed out"))?;
+    let seen = seen.lock().unwrap().take();
+    let mut handle = match (connected, &seen, &ftp.fingerprint) {
+        (Ok(handle), _, _) => handle,
+        (Err(_), Some((_, fingerprint)), Some(expected)) if fingerprint != expected => {
+            return Err(Failure::Refused(format!(
+                "Host key fingerprint {} does not match the expected {}",
+                fingerprint, expected
+            )))
+        }
+        (Err(e), _, _) => return Err(ssh_error(e)),
+    };
+    data.push((
+        "Connection".to_string(),
+        format!(
+            "{}:{} connected in {:.1} ms",
+            ftp.host,
+            ftp.port,
+            started.elapsed().as_secs_f64() * 1000.0
+        ),
+    ));
+    if let Some((name, fingerprint)) = &seen {
+        data.push(("Host key".to_string(), format!("{} {}", name, fingerprint)));
+    }
+    if ftp.fingerprint.is_none() {
+        return Err(unpinned(&ftp.user, &seen));
+    }
+
+    let authenticated = handle
+        .authenticate_password(&ftp.user, ftp.password.expose_secret())
+        .await
+        .map_err(ssh_error)?;
+    if !authenticated {
+        return Err(Failure::Refused(format!("Login as '{}' failed", ftp.user)));
+    }
+    data.push(("Login".to_string(), format!("'{}' with password", ftp.user)));
+    let channel = handle.channel_open_session().await.map_err(ssh_error)?;
+    channel
+        .request_subsystem(true, "sftp")
+        .await
+        .map_err(ssh_error)?;
+    let sftp = SftpSession::new(channel.into_stream())
+        .await
+        .map_err(|e| Failure::Refused(format!("The SFTP subsystem is not available: {}", e)))?;
+
+    let directory = match &ftp.directory {
+        Some(directory) => directory.to_string(),
+        None => sftp.canonicalize(".").await.unwrap_or(".".to_string()),
+    };
+    let entries = sftp
+        .read_dir(directory.as_str())
+        .await
+        .map_err(|e| Failure::Refused(format!("Listing '{}' failed: {}", directory, e)))?
+        .map(|entry| {
+            let metadata = entry.metadata();
+            let kind = match metadata.is_dir() {
+                true => 'd',
+                false => '-',
+            };
+            format!(
+                "{} {:>10} {}",
+                kind,
+                metadata.size.unwrap_or_default(),
+                entry.file_name()
+            )
+        });
+    data.push((format!("Listing {}", directory), listed(entries)));
+
+    if ftp.upload {
+        let (name, content) = ftp.test_file();
+        let path = format!("{}/{}", directory.trim_end_matches('/'), name);
+        let started = Instant::now();
+        let refused = |e: &dyn std::fmt::Display| {
+            Failure::Refused(format!("Upload of '{}' failed: {}", path, e))
+        };
+        let mut file = sftp.create(path.as_str()).await.map_err(|e| refused(&e))?;
+        file.write_all(content.as_bytes())
+            .await
+            .map_err(|e| refused(&e))?;
+        file.shutdown().await.map_err(|e| refused(&e))?;
+        data.push((
+            "Upload".to_string(),
+            format!(
+                "{} with {} bytes in {:.1} ms",
+                name,
+                content.len(),
+                started.elapsed().as_secs_f64() * 1000.0
+            ),
+        ));
+        sftp.remove_file(path.as_str())
+            .await
+            .map_err(|e| Failure::Refused(format!("Delete of '{}' failed: {}", path, e)))?;
+        data.push(("Delete".to_string(), path));
+    }
+    // the connection is closed anyways
+    let _ = sftp.close().await;
+    let _ = handle.disconnect(Disconnect::ByApplication, "", "en").await;
+    Ok(())
+}
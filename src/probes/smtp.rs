@@ -0,0 +1,331 @@
+use std::io;
+use std::io::BufReader;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use rustls::ClientConfig;
+use secrecy::ExposeSecret;
+
+use crate::error::InquestError::{AssertionMatchingError, FailedExecutionError};
+use crate::probes::stream::{
+    command_reply, connect, expect, read_reply, step, text, upgrade, Failure, Reply, Stream,
+};
+use crate::probes::tls::{certificate_mismatch, client_config, PeerCertificates};
+use crate::{Certificates, Data, GlobalOptions, Probe, ProbeReport, Result, Smtp, SmtpMail};
+
+const PROBE_NAME: &str = "SMTP";
+
+impl Smtp {
+    pub(crate) fn new(
+        host: String,
+        certs: Option<Certificates>,
+        options: &'static GlobalOptions,
+    ) -> Smtp {
+        Smtp {
+            host,
+            port: 25,
+            tls: false,
+            starttls: true,
+            tls_test: None,
+            user: None,
+            password: None,
+            plaintext_auth: false,
+            timeout: options.timeout,
+            certs,
+            mail: None,
+        }
+    }
+}
+
+/// Walks through the dialog with the server and reports every reply, ending with `QUIT`
+impl Probe for Smtp {
+    fn execute(&self) -> Result<ProbeReport> {
+        let failed = |e: io::Error| FailedExecutionError {
+            probe_identifier: self.identifier(),
+            source: Box::new(e),
+        };
+        let peer_certificates = PeerCertificates::default();
+        let config = match self.tls || self.starttls {
+            true => Some(Arc::new(
+                client_config(
+                    self.certs.as_ref(),
+                    self.tls_test.as_ref(),
+                    peer_certificates.clone(),
+                )
+                .map_err(|e| FailedExecutionError {
+                    probe_identifier: self.identifier(),
+                    source: Box::new(e),
+                })?,
+            )),
+            false => None,
+        };
+
+        let started = Instant::now();
+        let socket = connect(&self.host, self.port, self.timeout).map_err(failed)?;
+        let local = socket.local_addr().map_err(failed)?;
+        let mut report = ProbeReport::new(self.identifier());
+        report.data.push((
+            "Connection".to_string(),
+            format!(
+                "{} connected in {:.1} ms",
+                socket
+                    .peer_addr()
+                    .map_or("unknown".to_string(), |peer| peer.to_string()),
+                started.elapsed().as_secs_f64() * 1000.0
+            ),
+        ));
+
+        match self.dialog(Stream::Plain(socket), local, config, &mut report.data) {
+            Ok(()) => Ok(report),
+            Err(Failure::Refused(desc)) => Err(certificate_mismatch(
+                self.identifier(),
+                self.tls_test.as_ref(),
+                &peer_certificates,
+            )
+            .unwrap_or(AssertionMatchingError(desc, report))),
+            Err(Failure::Io(e)) => Err(failed(e)),
+        }
+    }
+
+    fn identifier(&self) -> String {
+        format!("{} - {}:{}", PROBE_NAME, self.host, self.port)
+    }
+}
+
+impl Smtp {
+    fn dialog(
+        &self,
+        mut stream: Stream,
+        local: SocketAddr,
+        config: Option<Arc<ClientConfig>>,
+        data: &mut Data,
+    ) -> std::result::Result<(), Failure> {
+        if let (true, Some(config)) = (self.tls, &config) {
+            stream = upgrade(stream, &self.host, config.clone(), data)?;
+        }
+        let mut session = BufReader::new(stream);
+        let banner = read_reply(&mut session)?;
+        data.push(("Banner".to_string(), banner.to_string()));
+        expect(&banner, &[220], "The connection")?;
+
+        // the address literal of the local end, since the host name is not necessarily known
+        let ehlo = match local {
+            SocketAddr::V4(local) => format!("EHLO [{}]", local.ip()),
+            SocketAddr::V6(local) => format!("EHLO [IPv6:{}]", local.ip()),
+        };
+        let mut extensions = step(&mut session, &ehlo, "EHLO", &[250], data)?.lines;
+        if let (false, true, Some(config)) = (self.tls, self.starttls, config) {
+            if offers(&extensions, "STARTTLS").is_none() {
+                return Err(Failure::Refused(
+                    "The server does not offer STARTTLS".to_string(),
+                ));
+            }
+            step(&mut session, "STARTTLS", "STARTTLS", &[220], data)?;
+            let stream = upgrade(session.into_inner(), &self.host, config, data)?;
+            session = BufReader::new(stream);
+            extensions = step(&mut session, &ehlo, "EHLO", &[250], data)?.lines;
+        }
+
+        if let Some(password) = &self.password {
+            let user = self.user.as_deref().unwrap_or_default();
+            if !(self.tls || self.starttls || self.plaintext_auth) {
+                return Err(Failure::Refused(format!(
+                    "Authentication as '{}' would send the password unencrypted, \
+                    'plaintext-auth' allows it",
+                    user
+                )));
+            }
+            let refused = |reply: &Reply| {
+                Failure::Refused(format!(
+                    "Authentication as '{}' failed: {}",
+                    user,
+                    text(reply)
+                ))
+            };
+            let mechanisms = offers(&extensions, "AUTH").unwrap_or_default();
+            let reply = if mechanisms
+                .split(' ')
+                .any(|m| m.eq_ignore_ascii_case("PLAIN"))
+            {
+                let credentials = format!("\0{}\0{}", user, password.expose_secret());
+                let command = format!("AUTH PLAIN {}", STANDARD.encode(credentials));
+                command_reply(&mut session, &command)?
+            } else if mechanisms
+                .split(' ')
+                .any(|m| m.eq_ignore_ascii_case("LOGIN"))
+            {
+                let mut reply = command_reply(&mut session, "AUTH LOGIN")?;
+                for answer in [user, password.expose_secret()] {
+                    if reply.code != 334 {
+                        return Err(refused(&reply));
+                    }
+                    reply = command_reply(&mut session, &STANDARD.encode(answer))?;
+                }
+                reply
+            } else {
+                return Err(Failure::Refused(format!(
+                    "The server offers no PLAIN or LOGIN authentication, but '{}'",
+                    mechanisms
+                )));
+            };
+            data.push(("AUTH".to_string(), reply.to_string()));
+            if reply.code != 235 {
+                return Err(refused(&reply));
+            }
+        }
+
+        if let Some(mail) = &self.mail {
+            self.send(&mut session, mail, data)?;
+        }
+        let quit = command_reply(&mut session, "QUIT")?;
+        data.push(("QUIT".to_string(), quit.to_string()));
+        Ok(())
+    }
+
+    /// Sends a short plain text message, which the server accepts for delivery
+    fn send(
+        &self,
+        session: &mut BufReader<Stream>,
+        mail: &SmtpMail,
+        data: &mut Data,
+    ) -> std::result::Result<(), Failure> {
+        let from = format!("MAIL FROM:<{}>", mail.from);
+        step(session, &from, "MAIL FROM", &[250], data)?;
+        let to = format!("RCPT TO:<{}>", mail.to);
+        // 251 forwards to another address
+        step(session, &to, "RCPT TO", &[250, 251], data)?;
+        step(session, "DATA", "DATA", &[354], data)?;
+        let message = format!(
+            "From: <{}>\r\nTo: <{}>\r\nDate: {}\r\nSubject: inquest test message\r\n\r\n\
+            This message was sent by inquest to test the delivery through {}.\r\n.",
+            mail.from,
+            mail.to,
+            chrono::Local::now().to_rfc2822(),
+            self.host
+        );
+        step(session, &message, "Message", &[250], data)?;
+        Ok(())
+    }
+}
+
+/// The parameters of an extension of the EHLO reply, whose first line is the greeting
+fn offers<'a>(extensions: &'a [String], keyword: &str) -> Option<&'a str> {
+    extensions.iter().skip(1).find_map(|extension| {
+        let (name, parameters) = extension.split_once(' ').unwrap_or((extension, ""));
+        name.eq_ignore_ascii_case(keyword).then_some(parameters)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    use secrecy::SecretString;
+
+    use crate::error::InquestError::AssertionMatchingError;
+    use crate::{Probe, Smtp, SmtpMail, GO};
+
+    /// Accepts the user 'probe' with the password 'secret' and any message, but does not offer
+    /// STARTTLS
+    fn smtp_server() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let mut writer = stream.try_clone().unwrap();
+                let mut reader = BufReader::new(stream);
+                writer.write_all(b"220 mail.corp ESMTP\r\n").unwrap();
+                let mut line = String::new();
+                let mut message = false;
+                while reader.read_line(&mut line).unwrap_or_default() > 0 {
+                    let reply: &[u8] = match line.trim_end() {
+                        "." => {
+                            message = false;
+                            b"250 2.0.0 Ok: queued as 4711\r\n"
+                        }
+                        _ if message => b"",
+                        ehlo if ehlo.starts_with("EHLO [127.0.0.1]") => {
+                            b"250-mail.corp\r\n250-SIZE 1024\r\n250 AUTH LOGIN PLAIN\r\n"
+                        }
+                        // '\0probe\0secret'
+                        "AUTH PLAIN AHByb2JlAHNlY3JldA==" => b"235 2.7.0 Authenticated\r\n",
+                        auth if auth.starts_with("AUTH") => b"535 5.7.8 Bad credentials\r\n",
+                        "DATA" => {
+                            message = true;
+                            b"354 End data with <CR><LF>.<CR><LF>\r\n"
+                        }
+                        "QUIT" => b"221 2.0.0 Bye\r\n",
+                        command if command.contains(':') => b"250 2.1.0 Ok\r\n",
+                        _ => b"",
+                    };
+                    writer.write_all(reply).unwrap();
+                    line.clear();
+                }
+            }
+        });
+        port
+    }
+
+    fn probe(port: u16, password: &str) -> Smtp {
+        Smtp {
+            port,
+            starttls: false,
+            plaintext_auth: true,
+            user: Some("probe".to_string()),
+            password: Some(SecretString::new(password.to_string())),
+            mail: Some(SmtpMail {
+                from: "inquest@corp".to_string(),
+                to: "postmaster@corp".to_string(),
+            }),
+            ..Smtp::new("127.0.0.1".to_string(), None, &GO)
+        }
+    }
+
+    #[test]
+    fn dialog_is_reported() {
+        let port = smtp_server();
+
+        let report = probe(port, "secret").execute().unwrap();
+        let steps = report
+            .data
+            .iter()
+            .map(|(step, reply)| format!("{}: {}", step, reply))
+            .skip(1)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                "Banner: 220 mail.corp ESMTP",
+                "EHLO: 250 mail.corp\nSIZE 1024\nAUTH LOGIN PLAIN",
+                "AUTH: 235 2.7.0 Authenticated",
+                "MAIL FROM: 250 2.1.0 Ok",
+                "RCPT TO: 250 2.1.0 Ok",
+                "DATA: 354 End data with <CR><LF>.<CR><LF>",
+                "Message: 250 2.0.0 Ok: queued as 4711",
+                "QUIT: 221 2.0.0 Bye",
+            ],
+            steps
+        );
+
+        assert_matches!(
+            probe(port, "guess").execute(),
+            Err(AssertionMatchingError(desc, _))
+                if desc == "Authentication as 'probe' failed: 535 5.7.8 Bad credentials"
+        );
+        assert_matches!(
+            Smtp { plaintext_auth: false, ..probe(port, "secret") }.execute(),
+            Err(AssertionMatchingError(desc, report))
+                if desc == "Authentication as 'probe' would send the password unencrypted, \
+                    'plaintext-auth' allows it"
+                    && report.data.iter().all(|(step, _)| step != "AUTH")
+        );
+        assert_matches!(
+            Smtp { port, ..Smtp::new("127.0.0.1".to_string(), None, &GO) }.execute(),
+            Err(AssertionMatchingError(desc, _)) if desc == "The server does not offer STARTTLS"
+        );
+    }
+}
@@ -0,0 +1,579 @@
+use std::io;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use async_trait::async_trait;
+use russh::client;
+use russh::keys::key::{KeyPair, PublicKey};
+use russh::{ChannelMsg, Disconnect};
+use secrecy::ExposeSecret;
+use tokio::runtime::Runtime;
+
+use crate::error::InquestError::{
+    AssertionMatchingError, ClientIdentityError, FailedExecutionError,
+};
+use crate::probes::stream::{connect, Failure};
+use crate::{Data, GlobalOptions, Probe, ProbeReport, Result, Ssh};
+
+const PROBE_NAME: &str = "SSH";
+
+const SSH_MSG_KEXINIT: u8 = 20;
+
+impl Ssh {
+    pub(crate) fn new(host: String, options: &'static GlobalOptions) -> Ssh {
+        Ssh {
+            host,
+            port: 22,
+            fingerprint: None,
+            user: None,
+            password: None,
+            private_key: None,
+            command: "true".to_string(),
+            timeout: options.timeout,
+        }
+    }
+}
+
+/// The algorithms the server offers in its first key exchange message
+#[derive(Debug, PartialEq)]
+struct Algorithms {
+    kex: Vec<String>,
+    host_key: Vec<String>,
+    ciphers: Vec<String>,
+    macs: Vec<String>,
+}
+
+/// Inspects the algorithms of the server on a first connection, since the SSH client only tells
+/// the negotiated ones. A second connection completes the key exchange, and authenticates when
+/// a user is given.
+impl Probe for Ssh {
+    fn execute(&self) -> Result<ProbeReport> {
+        let failed = |e: io::Error| FailedExecutionError {
+            probe_identifier: self.identifier(),
+            source: Box::new(e),
+        };
+        let key = match &self.private_key {
+            Some(path) => Some(
+                russh::keys::load_secret_key(
+                    path,
+                    self.password.as_ref().map(|p| p.expose_secret().as_str()),
+                )
+                .map_err(|e| FailedExecutionError {
+                    probe_identifier: self.identifier(),
+                    source: Box::new(ClientIdentityError {
+                        path: path.to_string(),
+                        reason: e.to_string(),
+                    }),
+                })?,
+            ),
+            None => None,
+        };
+
+        let started = Instant::now();
+        let mut socket = connect(&self.host, self.port, self.timeout).map_err(failed)?;
+        let mut report = ProbeReport::new(self.identifier());
+        report.data.push((
+            "Connection".to_string(),
+            format!(
+                "{} connected in {:.1} ms",
+                socket
+                    .peer_addr()
+                    .map_or("unknown".to_string(), |peer| peer.to_string()),
+                started.elapsed().as_secs_f64() * 1000.0
+            ),
+        ));
+        let (banner, algorithms) = inspect(&mut socket).map_err(failed)?;
+        report.data.push(("Banner".to_string(), banner));
+        report.data.extend([
+            ("Key exchange".to_string(), algorithms.kex.join(", ")),
+            (
+                "Host key algorithms".to_string(),
+                algorithms.host_key.join(", "),
+            ),
+            ("Ciphers".to_string(), algorithms.ciphers.join(", ")),
+            ("MACs".to_string(), algorithms.macs.join(", ")),
+        ]);
+        drop(socket);
+
+        let session = Runtime::new()
+            .map_err(failed)?
+            .block_on(self.session(key, &mut report.data));
+        match session {
+            Ok(()) => Ok(report),
+            Err(Failure::Refused(desc)) => Err(AssertionMatchingError(desc, report)),
+            Err(Failure::Io(e)) => Err(failed(e)),
+        }
+    }
+
+    fn identifier(&self) -> String {
+        format!("{} - {}:{}", PROBE_NAME, self.host, self.port)
+    }
+}
+
+/// Remembers the host key, and accepts it when no fingerprint is expected
+pub(super) struct HostKey {
+    pub(super) seen: Arc<Mutex<Option<(String, String)>>>,
+    pub(super) expected: Option<String>,
+}
+
+#[async_trait]
+impl client::Handler for HostKey {
+    type Error = russh::Error;
+
+    async fn check_server_key(
+        &mut self,
+        key: &PublicKey,
+    ) -> std::result::Result<bool, Self::Error> {
+        let fingerprint = format!("SHA256:{}", key.fingerprint());
+        let accepted = self
+            .expected
+            .as_ref()
+            .map_or(true, |expected| *expected == fingerprint);
+        *self.seen.lock().unwrap() = Some((key.name().to_string(), fingerprint));
+        Ok(accepted)
+    }
+}
+
+impl Ssh {
+    /// Credentials are only sent when the host key was accepted
+    async fn session(
+        &self,
+        key: Option<KeyPair>,
+        data: &mut Data,
+    ) -> std::result::Result<(), Failure> {
+        let seen = Arc::new(Mutex::new(None));
+        let handler = HostKey {
+            seen: seen.clone(),
+            expected: self.fingerprint.clone(),
+        };
+        let config = Arc::new(client::Config {
+            inactivity_timeout: Some(self.timeout),
+            ..Default::default()
+        });
+        let connected = tokio::time::timeout(
+            self.timeout,
+            client::connect(config, (self.host.as_str(), self.port), handler),
+        )
+        .await
+        .map_err(|_| io::Error::new(ErrorKind::TimedOut, "key exchange timed out"))?;
+        let seen = seen.lock().unwrap().take();
+        if let Some((name, fingerprint)) = &seen {
+            data.push(("Host key".to_string(), format!("{} {}", name, fingerprint)));
+        }
+        let mut handle = match (connected, &seen, &self.fingerprint) {
+            (Ok(handle), _, _) => handle,
+            (Err(_), Some((_, fingerprint)), Some(expected)) if fingerprint != expected => {
+                return Err(Failure::Refused(format!(
+                    "Host key fingerprint {} does not match the expected {}",
+                    fingerprint, expected
+                )))
+            }
+            (Err(e), _, _) => return Err(ssh_error(e)),
+        };
+
+        if let Some(user) = &self.user {
+            if self.fingerprint.is_none() {
+                return Err(unpinned(user, &seen));
+            }
+            let started = Instant::now();
+            let (method, authenticated) = match key {
+                Some(key) => (
+                    "public key",
+                    handle.authenticate_publickey(user, Arc::new(key)).await,
+                ),
+                None => (
+                    "password",
+                    handle
+                        .authenticate_password(
+                            user,
+                            self.password
+                                .as_ref()
+                                .map_or("", |password| password.expose_secret()),
+                        )
+                        .await,
+                ),
+            };
+            if !authenticated.map_err(ssh_error)? {
+                return Err(Failure::Refused(format!(
+                    "Authentication as '{}' with {} failed",
+                    user, method
+                )));
+            }
+            data.push((
+                "Authentication".to_string(),
+                format!(
+                    "{} as '{}' in {:.1} ms",
+                    method,
+                    user,
+                    started.elapsed().as_secs_f64() * 1000.0
+                ),
+            ));
+            self.run_command(&handle, data).await?;
+        }
+        // the connection is closed anyways
+        let _ = handle.disconnect(Disconnect::ByApplication, "", "en").await;
+        Ok(())
+    }
+
+    async fn run_command(
+        &self,
+        handle: &client::Handle<HostKey>,
+        data: &mut Data,
+    ) -> std::result::Result<(), Failure> {
+        let started = Instant::now();
+        let mut channel = handle.channel_open_session().await.map_err(ssh_error)?;
+        channel
+            .exec(true, self.command.as_str())
+            .await
+            .map_err(ssh_error)?;
+        let mut status = None;
+        while let Some(message) = channel.wait().await {
+            if let ChannelMsg::ExitStatus { exit_status } = message {
+                status = Some(exit_status);
+            }
+        }
+        match status {
+            Some(0) => {
+                data.push((
+                    "Command".to_string(),
+                    format!(
+                        "'{}' exited with 0 in {:.1} ms",
+                        self.command,
+                        started.elapsed().as_secs_f64() * 1000.0
+                    ),
+                ));
+                Ok(())
+            }
+            Some(status) => Err(Failure::Refused(format!(
+                "Command '{}' exited with {}",
+                self.command, status
+            ))),
+            None => Err(Failure::Refused(format!(
+                "Command '{}' ended without exit status",
+                self.command
+            ))),
+        }
+    }
+}
+
+/// Credentials are not sent to a host whose key is unknown, the key that was seen is told so that
+/// its fingerprint can be verified and configured
+pub(super) fn unpinned(user: &str, seen: &Option<(String, String)>) -> Failure {
+    Failure::Refused(format!(
+        "Authentication as '{}' requires the 'fingerprint' of the host key, which is {}",
+        user,
+        seen.as_ref()
+            .map_or("unknown", |(_, fingerprint)| fingerprint.as_str())
+    ))
+}
+
+pub(super) fn ssh_error(e: russh::Error) -> Failure {
+    Failure::Io(io::Error::other(e))
+}
+
+/// Reads the banner and the algorithms of the key exchange, which are sent in plain text
+fn inspect(stream: &mut (impl Read + Write)) -> io::Result<(String, Algorithms)> {
+    stream.write_all(b"SSH-2.0-inquest\r\n")?;
+    let mut stream = BufReader::new(stream);
+    // other lines may precede the identification of the server
+    let mut banner = String::new();
+    for _ in 0..20 {
+        banner.clear();
+        if stream.read_line(&mut banner)? == 0 || banner.starts_with("SSH-") {
+            break;
+        }
+    }
+    if !banner.starts_with("SSH-") {
+        return Err(invalid_data("no SSH banner, is this an SSH server?"));
+    }
+
+    let mut length = [0; 4];
+    stream.read_exact(&mut length)?;
+    let length = u32::from_be_bytes(length) as usize;
+    if length > 35000 {
+        return Err(invalid_data("packet exceeds the maximum size"));
+    }
+    let mut packet = vec![0; length];
+    stream.read_exact(&mut packet)?;
+    let padding = *packet.first().unwrap_or(&0) as usize;
+    let payload = packet
+        .get(1..length.saturating_sub(padding))
+        .ok_or_else(|| invalid_data("invalid padding"))?;
+    if payload.first() != Some(&SSH_MSG_KEXINIT) {
+        return Err(invalid_data("expected the key exchange to start"));
+    }
+    // the message type and the random cookie
+    let mut payload = payload.get(17..).unwrap_or_default();
+    let mut name_list = || -> io::Result<Vec<String>> {
+        let mut length = [0; 4];
+        payload.read_exact(&mut length)?;
+        let length = u32::from_be_bytes(length) as usize;
+        let names = payload
+            .get(..length)
+            .ok_or_else(|| invalid_data("truncated name-list"))?;
+        payload = &payload[length..];
+        Ok(String::from_utf8_lossy(names)
+            .split(',')
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .collect())
+    };
+    let kex = name_list()?;
+    let host_key = name_list()?;
+    let ciphers = name_list()?;
+    // the algorithms from server to client are the same in practice
+    name_list()?;
+    let macs = name_list()?;
+    Ok((
+        banner.trim_end().to_string(),
+        Algorithms {
+            kex,
+            host_key,
+            ciphers,
+            macs,
+        },
+    ))
+}
+
+fn invalid_data(reason: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, reason.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use russh::keys::key::KeyPair;
+    use russh::server::{Auth, Msg, Session};
+    use russh::{server, Channel, ChannelId};
+    use secrecy::SecretString;
+    use tokio::runtime::Runtime;
+
+    use crate::error::InquestError::{AssertionMatchingError, FailedExecutionError};
+    use crate::probes::ssh::{inspect, Algorithms};
+    use crate::{FileTransfer, Ftp, Probe, Ssh, GO};
+
+    /// Accepts the user 'probe' with the password 'secret', and exits with 0 for `true` only
+    struct Server;
+
+    #[async_trait]
+    impl server::Handler for Server {
+        type Error = russh::Error;
+
+        async fn auth_password(&mut self, user: &str, password: &str) -> Result<Auth, Self::Error> {
+            Ok(match (user, password) {
+                ("probe", "secret") => Auth::Accept,
+                _ => Auth::Reject {
+                    proceed_with_methods: None,
+                },
+            })
+        }
+
+        async fn channel_open_session(
+            &mut self,
+            _channel: Channel<Msg>,
+            _session: &mut Session,
+        ) -> Result<bool, Self::Error> {
+            Ok(true)
+        }
+
+        async fn exec_request(
+            &mut self,
+            channel: ChannelId,
+            command: &[u8],
+            session: &mut Session,
+        ) -> Result<(), Self::Error> {
+            session.exit_status_request(channel, if command == b"true" { 0 } else { 1 });
+            session.eof(channel);
+            session.close(channel);
+            Ok(())
+        }
+    }
+
+    /// The port and the fingerprint of the host key
+    fn ssh_server() -> (u16, String) {
+        let key = KeyPair::generate_ed25519().unwrap();
+        let fingerprint = format!("SHA256:{}", key.clone_public_key().unwrap().fingerprint());
+        let config = Arc::new(server::Config {
+            keys: vec![key],
+            auth_rejection_time: Duration::ZERO,
+            ..Default::default()
+        });
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            Runtime::new().unwrap().block_on(async {
+                listener.set_nonblocking(true).unwrap();
+                let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+                while let Ok((stream, _)) = listener.accept().await {
+                    let session = server::run_stream(config.clone(), stream, Server);
+                    tokio::spawn(async move {
+                        if let Ok(session) = session.await {
+                            let _ = session.await;
+                        }
+                    });
+                }
+            })
+        });
+        (port, fingerprint)
+    }
+
+    fn probe(port: u16, fingerprint: &str, password: &str, command: &str) -> Ssh {
+        Ssh {
+            port,
+            fingerprint: Some(fingerprint.to_string()),
+            user: Some("probe".to_string()),
+            password: Some(SecretString::new(password.to_string())),
+            command: command.to_string(),
+            ..Ssh::new("127.0.0.1".to_string(), &GO)
+        }
+    }
+
+    #[test]
+    fn command_is_run_on_known_host() {
+        let (port, fingerprint) = ssh_server();
+
+        let report = probe(port, &fingerprint, "secret", "true")
+            .execute()
+            .unwrap();
+        assert_eq!(format!("ssh-ed25519 {}", fingerprint), report.data[6].1);
+        assert!(report.data[8].1.starts_with("'true' exited with 0 in"));
+
+        assert_matches!(
+            probe(port, &fingerprint, "secret", "false").execute(),
+            Err(AssertionMatchingError(desc, _)) if desc == "Command 'false' exited with 1"
+        );
+        assert_matches!(
+            probe(port, &fingerprint, "guess", "true").execute(),
+            Err(AssertionMatchingError(desc, _))
+                if desc == "Authentication as 'probe' with password failed"
+        );
+        assert_matches!(
+            Ssh { fingerprint: None, ..probe(port, &fingerprint, "secret", "true") }.execute(),
+            Err(AssertionMatchingError(desc, report)) if desc == format!(
+                "Authentication as 'probe' requires the 'fingerprint' of the host key, which is {}",
+                fingerprint
+            ) && report.data.iter().all(|(key, _)| key != "Authentication")
+        );
+        assert_matches!(
+            probe(port, "SHA256:unknown", "secret", "true").execute(),
+            Err(AssertionMatchingError(desc, _)) if desc == format!(
+                "Host key fingerprint {} does not match the expected SHA256:unknown",
+                fingerprint
+            )
+        );
+    }
+
+    #[test]
+    fn unreadable_private_key_names_the_probe() {
+        let probe = Ssh {
+            private_key: Some("does-not-exist".to_string()),
+            ..Ssh::new("127.0.0.1".to_string(), &GO)
+        };
+        assert_matches!(
+            probe.execute(),
+            Err(FailedExecutionError { probe_identifier, source })
+                if probe_identifier == "SSH - 127.0.0.1:22"
+                    && source.to_string().starts_with(
+                        "Unable to load the client certificate from 'does-not-exist'"
+                    )
+        );
+    }
+
+    #[test]
+    fn sftp_checks_the_host_key_before_the_login() {
+        let (port, fingerprint) = ssh_server();
+        let sftp = |expected: Option<&str>| Ftp {
+            port,
+            fingerprint: expected.map(str::to_string),
+            ..Ftp::new(
+                "127.0.0.1".to_string(),
+                FileTransfer::Sftp,
+                "probe".to_string(),
+                SecretString::new("secret".to_string()),
+                None,
+                &GO,
+            )
+        };
+        assert_matches!(
+            sftp(Some("SHA256:unknown")).execute(),
+            Err(AssertionMatchingError(desc, report)) if desc == format!(
+                "Host key fingerprint {} does not match the expected SHA256:unknown",
+                fingerprint
+            ) && report.data.iter().all(|(key, _)| key != "Login")
+        );
+        assert_matches!(
+            sftp(None).execute(),
+            Err(AssertionMatchingError(desc, report)) if desc == format!(
+                "Authentication as 'probe' requires the 'fingerprint' of the host key, which is {}",
+                fingerprint
+            ) && report.data.iter().any(|(key, _)| key == "Host key")
+                && report.data.iter().all(|(key, _)| key != "Login")
+        );
+    }
+
+    #[test]
+    fn algorithms_are_inspected() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut payload = vec![20];
+            payload.extend([0; 16]);
+            for names in [
+                "curve25519-sha256,diffie-hellman-group14-sha256",
+                "ssh-ed25519",
+                "aes256-gcm@openssh.com,aes128-ctr",
+                "aes256-gcm@openssh.com,aes128-ctr",
+                "hmac-sha2-256",
+                "hmac-sha2-256",
+                "none",
+                "none",
+                "",
+                "",
+            ] {
+                payload.extend((names.len() as u32).to_be_bytes());
+                payload.extend(names.as_bytes());
+            }
+            payload.extend([0; 5]);
+            let padding = 8 - (payload.len() + 5) % 8 + 8;
+            let mut packet = ((payload.len() + padding + 1) as u32)
+                .to_be_bytes()
+                .to_vec();
+            packet.push(padding as u8);
+            packet.extend(payload);
+            packet.extend(vec![0; padding]);
+            stream
+                .write_all(b"Welcome\r\nSSH-2.0-OpenSSH_9.6\r\n")
+                .unwrap();
+            stream.write_all(&packet).unwrap();
+            let mut banner = [0; 17];
+            stream.read_exact(&mut banner).unwrap();
+        });
+
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        let (banner, algorithms) = inspect(&mut stream).unwrap();
+        assert_eq!("SSH-2.0-OpenSSH_9.6", banner);
+        assert_eq!(
+            Algorithms {
+                kex: vec![
+                    "curve25519-sha256".to_string(),
+                    "diffie-hellman-group14-sha256".to_string()
+                ],
+                host_key: vec!["ssh-ed25519".to_string()],
+                ciphers: vec![
+                    "aes256-gcm@openssh.com".to_string(),
+                    "aes128-ctr".to_string()
+                ],
+                macs: vec!["hmac-sha2-256".to_string()],
+            },
+            algorithms
+        );
+    }
+}
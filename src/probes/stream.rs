@@ -0,0 +1,284 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{Shutdown, TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::Duration;
+
+use rustls::{ClientConfig, ClientConnection, ServerName, StreamOwned};
+
+use crate::probes::handshake::describe;
+use crate::Data;
+
+/// The connection of a probe speaking a protocol on top of TCP, which is optionally upgraded to
+/// TLS right away or later on like with STARTTLS.
+pub(super) enum Stream {
+    Plain(TcpStream),
+    Tls(Box<StreamOwned<ClientConnection, TcpStream>>),
+}
+
+/// Why the dialog of a probe ended early, either the connection broke or the server refused a
+/// step, which fails the probe with the description
+pub(super) enum Failure {
+    Io(io::Error),
+    Refused(String),
+}
+
+impl From<io::Error> for Failure {
+    fn from(e: io::Error) -> Self {
+        Failure::Io(e)
+    }
+}
+
+/// Connects to the first reachable address the host resolves to
+pub(super) fn connect(host: &str, port: u16, timeout: Duration) -> io::Result<TcpStream> {
+    let mut last_error = io::Error::new(ErrorKind::NotFound, "host resolved to no address");
+    for address in (host, port).to_socket_addrs()? {
+        match TcpStream::connect_timeout(&address, timeout) {
+            Ok(stream) => {
+                stream.set_read_timeout(Some(timeout))?;
+                stream.set_write_timeout(Some(timeout))?;
+                return Ok(stream);
+            }
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+impl Stream {
+    /// Completes a TLS handshake on the plain connection, a failed handshake is described like
+    /// by the TLS probe.
+    pub(super) fn upgrade(
+        self,
+        server_name: &str,
+        config: Arc<ClientConfig>,
+    ) -> io::Result<Stream> {
+        let Stream::Plain(mut socket) = self else {
+            return Ok(self);
+        };
+        let name = ServerName::try_from(server_name)
+            .map_err(|e| io::Error::new(ErrorKind::InvalidInput, e))?;
+        let mut connection = ClientConnection::new(config, name)
+            .map_err(|e| io::Error::new(ErrorKind::InvalidInput, e))?;
+        while connection.is_handshaking() {
+            connection.complete_io(&mut socket).map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!(
+                        "TLS handshake with '{}' failed: {}",
+                        server_name,
+                        describe(&e, server_name)
+                    ),
+                )
+            })?;
+        }
+        Ok(Stream::Tls(Box::new(StreamOwned::new(connection, socket))))
+    }
+
+    /// Ends the writing side, announcing the end with a TLS close notification so that the
+    /// server does not take the data as truncated
+    pub(super) fn close(&mut self) -> io::Result<()> {
+        match self {
+            Stream::Plain(socket) => socket.shutdown(Shutdown::Write),
+            Stream::Tls(stream) => {
+                stream.conn.send_close_notify();
+                stream.flush()?;
+                stream.sock.shutdown(Shutdown::Write)
+            }
+        }
+    }
+
+    /// The negotiated protocol version and cipher suite, like `TLSv1.3 TLS13_AES_256_GCM_SHA384`
+    pub(super) fn tls_description(&self) -> Option<String> {
+        let Stream::Tls(stream) = self else {
+            return None;
+        };
+        Some(format!(
+            "{} {}",
+            stream
+                .conn
+                .protocol_version()
+                .map_or("unknown".to_string(), |version| {
+                    format!("{:?}", version).replace('_', ".")
+                }),
+            stream
+                .conn
+                .negotiated_cipher_suite()
+                .map_or("unknown".to_string(), |suite| format!(
+                    "{:?}",
+                    suite.suite()
+                ))
+        ))
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Stream::Plain(stream) => stream.read(buf),
+            Stream::Tls(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Stream::Plain(stream) => stream.write(buf),
+            Stream::Tls(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Stream::Plain(stream) => stream.flush(),
+            Stream::Tls(stream) => stream.flush(),
+        }
+    }
+}
+
+/// A possibly multi-line reply of a line based protocol like SMTP or FTP
+#[derive(Debug, PartialEq)]
+pub(super) struct Reply {
+    pub(super) code: u16,
+    pub(super) lines: Vec<String>,
+}
+
+/// Renders the reply like the server sent it, but without repeating the code on every line
+impl Display for Reply {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.code, self.lines.join("\n"))
+    }
+}
+
+/// Completes the TLS handshake and reports the negotiated parameters. Nothing may be buffered
+/// in between, since the server waits for the handshake.
+pub(super) fn upgrade(
+    stream: Stream,
+    host: &str,
+    config: Arc<ClientConfig>,
+    data: &mut Data,
+) -> Result<Stream, Failure> {
+    let stream = stream
+        .upgrade(host, config)
+        .map_err(|e| Failure::Refused(e.to_string()))?;
+    data.extend(stream.tls_description().map(|tls| ("TLS".to_string(), tls)));
+    Ok(stream)
+}
+
+/// Sends the command and reports the reply, which is expected to have one of the codes
+pub(super) fn step(
+    session: &mut BufReader<Stream>,
+    command: &str,
+    name: &str,
+    codes: &[u16],
+    data: &mut Data,
+) -> Result<Reply, Failure> {
+    let reply = command_reply(session, command)?;
+    data.push((name.to_string(), reply.to_string()));
+    expect(&reply, codes, name)?;
+    Ok(reply)
+}
+
+pub(super) fn expect(reply: &Reply, codes: &[u16], name: &str) -> Result<(), Failure> {
+    match codes.contains(&reply.code) {
+        true => Ok(()),
+        false => Err(Failure::Refused(format!(
+            "{} was refused: {}",
+            name,
+            text(reply)
+        ))),
+    }
+}
+
+/// The reply on one line, for the description of a failure
+pub(super) fn text(reply: &Reply) -> String {
+    format!("{} {}", reply.code, reply.lines.join(" "))
+}
+
+pub(super) fn command_reply(session: &mut BufReader<Stream>, command: &str) -> io::Result<Reply> {
+    let stream = session.get_mut();
+    stream.write_all(command.as_bytes())?;
+    stream.write_all(b"\r\n")?;
+    stream.flush()?;
+    read_reply(session)
+}
+
+/// Reads a reply, whose lines in between do not necessarily start with the code like with FTP
+pub(super) fn read_reply(session: &mut impl BufRead) -> io::Result<Reply> {
+    let mut read_line = || -> io::Result<String> {
+        let mut line = String::new();
+        match session.read_line(&mut line)? {
+            0 => Err(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "the server closed the connection",
+            )),
+            _ => Ok(line.trim_end().to_string()),
+        }
+    };
+    let first = read_line()?;
+    let code = first
+        .get(..3)
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(|| {
+            io::Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid reply '{}', expected a three digit code", first),
+            )
+        })?;
+    let mut lines = vec![first.get(4..).unwrap_or_default().to_string()];
+    if first[3..].starts_with('-') {
+        let (last, continued) = (format!("{} ", code), format!("{}-", code));
+        loop {
+            let line = read_line()?;
+            // the trailing space of a last line without text is trimmed
+            if line == last.trim_end() {
+                lines.push(String::new());
+                break;
+            }
+            if let Some(text) = line.strip_prefix(&last) {
+                lines.push(text.to_string());
+                break;
+            }
+            lines.push(line.strip_prefix(&continued).unwrap_or(&line).to_string());
+        }
+    }
+    Ok(Reply { code, lines })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::probes::stream::{read_reply, Reply};
+
+    #[test]
+    fn multi_line_reply_is_read() {
+        let mut reply = "250-mail.corp\r\n250-8BITMIME\r\n250 STARTTLS\r\n220 next".as_bytes();
+        assert_eq!(
+            Reply {
+                code: 250,
+                lines: vec![
+                    "mail.corp".to_string(),
+                    "8BITMIME".to_string(),
+                    "STARTTLS".to_string()
+                ]
+            },
+            read_reply(&mut reply).unwrap()
+        );
+
+        // lines in between may leave out the code
+        let mut reply = "211-Features:\r\n MDTM\r\n UTF8\r\n211 End\r\n".as_bytes();
+        assert_eq!(
+            Reply {
+                code: 211,
+                lines: vec![
+                    "Features:".to_string(),
+                    " MDTM".to_string(),
+                    " UTF8".to_string(),
+                    "End".to_string()
+                ]
+            },
+            read_reply(&mut reply).unwrap()
+        );
+    }
+}
@@ -1,10 +1,159 @@
-use std::net::{TcpStream, ToSocketAddrs};
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::Instant;
 
-pub(crate) fn foo<A: ToSocketAddrs>(addr: A) {
-    let x = TcpStream::connect(addr);
+use crate::error::InquestError::{AssertionMatchingError, FailedExecutionError};
+use crate::{Data, GlobalOptions, Probe, ProbeReport, Result, Tcp};
 
-    match x {
-        Ok(c) => println!("{:?}", c),
-        Err(_) => println!("could not connect"),
+const PROBE_NAME: &str = "TCP";
+
+impl Tcp {
+    pub(crate) fn new(host: String, port: u16, options: &'static GlobalOptions) -> Tcp {
+        Tcp {
+            host,
+            port,
+            timeout: options.timeout,
+            payload: None,
+            banner: None,
+        }
+    }
+}
+
+/// Connects to every address the host resolves to, since a firewall or a load-balancer might
+/// only block some of them.
+impl Probe for Tcp {
+    fn execute<'a>(&self) -> Result<ProbeReport> {
+        let addresses = (self.host.as_str(), self.port)
+            .to_socket_addrs()
+            .map_err(|e| FailedExecutionError {
+                probe_identifier: self.identifier(),
+                source: Box::new(e),
+            })?;
+
+        let mut report = ProbeReport::new(self.identifier());
+        let failures = addresses
+            .filter_map(|address| check_address(self, address, &mut report.data).err())
+            .collect::<Vec<_>>();
+
+        if failures.is_empty() {
+            Ok(report)
+        } else {
+            Err(AssertionMatchingError(failures.join("; "), report))
+        }
+    }
+
+    fn identifier(&self) -> String {
+        format!("{} - {}:{}", PROBE_NAME, self.host, self.port)
+    }
+}
+
+fn check_address(
+    probe: &Tcp,
+    address: SocketAddr,
+    data: &mut Data,
+) -> std::result::Result<(), String> {
+    let started = Instant::now();
+    let mut stream = TcpStream::connect_timeout(&address, probe.timeout).map_err(|e| {
+        format!(
+            "Unable to connect to {} within {} ms: {}",
+            address,
+            started.elapsed().as_millis(),
+            e
+        )
+    })?;
+    let latency = started.elapsed();
+    let local_address = stream
+        .local_addr()
+        .map_or("unknown".to_string(), |local| local.to_string());
+    data.push((
+        format!("Connection {}", address),
+        format!(
+            "connected in {:.1} ms from {}",
+            latency.as_secs_f64() * 1000.0,
+            local_address
+        ),
+    ));
+
+    if probe.payload.is_none() && probe.banner.is_none() {
+        return Ok(());
+    }
+    let _ = stream.set_read_timeout(Some(probe.timeout));
+    let _ = stream.set_write_timeout(Some(probe.timeout));
+    if let Some(payload) = &probe.payload {
+        stream
+            .write_all(payload.as_bytes())
+            .map_err(|e| format!("Unable to send the payload to {}: {}", address, e))?;
+    }
+    let mut buffer = vec![0; 4096];
+    let read = stream
+        .read(&mut buffer)
+        .map_err(|e| format!("Nothing received from {}: {}", address, e))?;
+    let banner = String::from_utf8_lossy(&buffer[..read])
+        .trim_end()
+        .to_string();
+    data.push((format!("Banner {}", address), banner.clone()));
+
+    match &probe.banner {
+        Some(expected) if !expected.is_match(&banner) => Err(format!(
+            "Banner of {} does not match '{}' but was '{}'",
+            address, expected, banner
+        )),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::net::TcpListener;
+    use std::thread;
+
+    use regex::Regex;
+
+    use crate::error::InquestError::AssertionMatchingError;
+    use crate::{Probe, Tcp, GO};
+
+    #[test]
+    fn connects_and_reads_banner() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for mut stream in listener.incoming().take(2).flatten() {
+                let _ = stream.write_all(b"SSH-2.0-OpenSSH_9.6\r\n");
+            }
+        });
+        let probe = |banner: &str| Tcp {
+            banner: Some(Regex::new(banner).unwrap()),
+            ..Tcp::new("127.0.0.1".to_string(), port, &GO)
+        };
+
+        let report = probe("^SSH-2.0").execute().unwrap();
+        assert!(report.data[0].1.starts_with("connected in"));
+        assert_eq!(
+            (
+                format!("Banner 127.0.0.1:{}", port),
+                "SSH-2.0-OpenSSH_9.6".to_string()
+            ),
+            report.data[1]
+        );
+
+        assert_matches!(
+            probe("^220 ").execute(),
+            Err(AssertionMatchingError(desc, _)) if desc.contains("does not match '^220 '")
+        );
+    }
+
+    #[test]
+    fn unreachable_port_fails() {
+        // the port is free again after the listener has been dropped
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        let result = Tcp::new("127.0.0.1".to_string(), port, &GO).execute();
+
+        assert_matches!(result, Err(AssertionMatchingError(desc, _)) if desc.starts_with("Unable to connect to 127.0.0.1"));
     }
 }
@@ -0,0 +1,511 @@
+use std::fmt::{Display, Formatter};
+use std::io::BufReader;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use std::{fs, io};
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, TimeZone, Utc};
+use p12_keystore::KeyStore;
+use rustls::client::{ServerCertVerified, ServerCertVerifier, WebPkiVerifier};
+use rustls::{Certificate, ClientConfig, PrivateKey, RootCertStore, ServerName};
+use rustls_pemfile::Item;
+use secrecy::ExposeSecret;
+use sha2::{Digest, Sha256};
+use x509_parser::objects::{oid2sn, oid_registry};
+use x509_parser::parse_x509_certificate;
+use x509_parser::prelude::GeneralName;
+use x509_parser::public_key::PublicKey;
+
+use crate::error::InquestError;
+use crate::{CertificateTest, Certificates, Result, TlsTest};
+
+/// The certificate chain presented by the server, shared between the verifier and the probe.
+/// It is completed with the trust anchor which issued it, since servers rarely present the root.
+pub(super) type PeerCertificates = Arc<Mutex<Vec<Certificate>>>;
+
+/// Verifies the server certificate with webpki, but records the presented chain first so that
+/// it can be inspected after the handshake (or a failed one).
+/// When a `TlsTest` is given, the handshake is aborted if the chain does not match.
+struct RecordingVerifier {
+    verifier: WebPkiVerifier,
+    /// the certificates of the root store, to find the anchor of a chain
+    anchors: Vec<Certificate>,
+    tls_test: Option<TlsTest>,
+    peer_certificates: PeerCertificates,
+}
+
+/// The relevant information of a X.509 certificate for a report.
+#[derive(Debug, Clone)]
+pub(super) struct CertificateDetails {
+    pub(super) subject: String,
+    pub(super) subject_alternative_names: Vec<String>,
+    pub(super) issuer: String,
+    pub(super) serial: String,
+    pub(super) not_before: DateTime<Utc>,
+    pub(super) not_after: DateTime<Utc>,
+    pub(super) key_type: String,
+    pub(super) pin_sha256: String,
+}
+
+impl ServerCertVerifier for RecordingVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        server_name: &ServerName,
+        scts: &mut dyn Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        let mut chain = vec![end_entity.clone()];
+        chain.extend_from_slice(intermediates);
+        chain.extend(trust_anchor(&chain, &self.anchors));
+        if let Ok(mut recorded) = self.peer_certificates.lock() {
+            *recorded = chain.clone();
+        }
+        if let Some(tls_test) = &self.tls_test {
+            check_chain(&chain, tls_test).map_err(rustls::Error::General)?;
+        }
+        self.verifier.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            scts,
+            ocsp_response,
+            now,
+        )
+    }
+}
+
+/// Creates a verifier for the given trust-anchors, which records the chain presented by the
+/// server in `peer_certificates` and checks it against the optional `TlsTest`.
+pub(super) fn recording_verifier(
+    root_store: RootCertStore,
+    anchors: Vec<Certificate>,
+    tls_test: Option<&TlsTest>,
+    peer_certificates: PeerCertificates,
+) -> Arc<dyn ServerCertVerifier> {
+    Arc::new(RecordingVerifier {
+        verifier: WebPkiVerifier::new(root_store, None),
+        anchors,
+        tls_test: tls_test.cloned(),
+        peer_certificates,
+    })
+}
+
+/// Finds the anchor which signed the last certificate of the chain, unless the chain already
+/// ends with a self-signed one.
+fn trust_anchor(chain: &[Certificate], anchors: &[Certificate]) -> Option<Certificate> {
+    let (_, last) = parse_x509_certificate(&chain.last()?.0).ok()?;
+    if last.subject().as_raw() == last.issuer().as_raw() {
+        return None;
+    }
+    anchors
+        .iter()
+        .find(|anchor| {
+            parse_x509_certificate(&anchor.0).is_ok_and(|(_, anchor)| {
+                anchor.subject().as_raw() == last.issuer().as_raw()
+                    && last.verify_signature(Some(anchor.public_key())).is_ok()
+            })
+        })
+        .cloned()
+}
+
+/// Creates a rustls client-configuration which trusts the platform certificates as well as the
+/// optional custom CA, and uses the client-certificate if one is configured.
+/// The chain presented by the server is recorded in `peer_certificates`.
+pub(super) fn client_config(
+    certs: Option<&Certificates>,
+    tls_test: Option<&TlsTest>,
+    peer_certificates: PeerCertificates,
+) -> Result<ClientConfig> {
+    let mut root_store = RootCertStore::empty();
+    let mut anchors = rustls_native_certs::load_native_certs()?
+        .into_iter()
+        .map(|cert| Certificate(cert.0))
+        .collect::<Vec<_>>();
+    for anchor in &anchors {
+        // some platform certificates cannot be parsed, they are skipped
+        let _ = root_store.add(anchor);
+    }
+
+    if let Some(ca_cert_path) = certs.and_then(|certs| certs.ca_cert.as_ref()) {
+        for ca in load_certificates(ca_cert_path)? {
+            root_store.add(&ca)?;
+            anchors.push(ca);
+        }
+    }
+
+    let builder = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_custom_certificate_verifier(recording_verifier(
+            root_store,
+            anchors,
+            tls_test,
+            peer_certificates,
+        ));
+
+    match certs.map(load_client_identity).transpose()?.flatten() {
+        Some((chain, key)) => Ok(builder.with_single_cert(chain, key)?),
+        None => Ok(builder.with_no_client_auth()),
+    }
+}
+
+/// Loads the client identity (certificate chain and private key) for mutual TLS. The sources are
+/// tried in the order PKCS#12 bundle, combined PEM, separate certificate and key PEM files.
+pub(super) fn load_client_identity(
+    certs: &Certificates,
+) -> Result<Option<(Vec<Certificate>, PrivateKey)>> {
+    if let Some(path) = &certs.client_pkcs12 {
+        let password = certs
+            .client_pkcs12_password
+            .as_ref()
+            .map(|password| password.expose_secret().as_str())
+            .unwrap_or_default();
+        return load_pkcs12(path, password).map(Some);
+    }
+    if let Some(path) = &certs.client_pem {
+        return load_identity(path, path).map(Some);
+    }
+    if let (Some(cert_path), Some(key_path)) = (&certs.client_cert, &certs.client_key) {
+        return load_identity(cert_path, key_path).map(Some);
+    }
+    Ok(None)
+}
+
+/// Checks the chain presented by the server against the pins and the expected issuer.
+/// A pin matches when any public key within the chain, including its trust anchor, has the given
+/// SHA-256 hash.
+pub(super) fn check_chain(
+    chain: &[Certificate],
+    tls_test: &TlsTest,
+) -> std::result::Result<(), String> {
+    let details = chain
+        .iter()
+        .filter_map(|cert| CertificateDetails::try_from(cert).ok())
+        .collect::<Vec<_>>();
+
+    if !tls_test.pin_sha256.is_empty() {
+        let pinned = details.iter().any(|cert| {
+            tls_test
+                .pin_sha256
+                .iter()
+                .any(|pin| pin.trim_start_matches("sha256//") == cert.pin_sha256)
+        });
+        if !pinned {
+            return Err(format!(
+                "None of the presented public keys matches the pins, presented were '{}'",
+                details
+                    .iter()
+                    .map(|cert| cert.pin_sha256.as_str())
+                    .collect::<Vec<_>>()
+                    .join("', '")
+            ));
+        }
+    }
+    if let Some(expected_issuer) = &tls_test.expected_issuer {
+        let issuer = details
+            .first()
+            .map(|cert| cert.issuer.as_str())
+            .unwrap_or_default();
+        if !issuer.contains(expected_issuer.as_str()) {
+            return Err(format!(
+                "Expected issuer '{}' but was '{}'",
+                expected_issuer, issuer
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Describes each certificate of the chain which expires earlier than the `CertificateTest`
+/// allows.
+pub(super) fn expiring_certificates(
+    chain: &[CertificateDetails],
+    certificate_test: Option<&CertificateTest>,
+) -> Vec<String> {
+    let Some(min_days_valid) = certificate_test.and_then(|test| test.min_days_valid) else {
+        return Vec::new();
+    };
+    let now = Utc::now();
+    chain
+        .iter()
+        .filter(|details| details.days_valid(now) < min_days_valid as i64)
+        .map(|details| {
+            format!(
+                "Certificate '{}' expires in {} days, expected at least {}",
+                details.subject,
+                details.days_valid(now),
+                min_days_valid
+            )
+        })
+        .collect()
+}
+
+/// In case a probe failed, this checks whether the recorded chain is the reason and returns the
+/// dedicated error.
+pub(super) fn certificate_mismatch(
+    probe_identifier: String,
+    tls_test: Option<&TlsTest>,
+    peer_certificates: &PeerCertificates,
+) -> Option<InquestError> {
+    let chain = peer_certificates.lock().ok()?.clone();
+    let desc = check_chain(&chain, tls_test?).err()?;
+    Some(InquestError::CertificateMismatchError {
+        probe_identifier,
+        desc,
+        presented_chain: chain
+            .iter()
+            .filter_map(|cert| CertificateDetails::try_from(cert).ok())
+            .map(|cert| format!("{} (issued by {})", cert.subject, cert.issuer))
+            .collect(),
+    })
+}
+
+/// Loads all certificates from a PEM file.
+fn load_certificates(path: &str) -> Result<Vec<Certificate>> {
+    let mut reader = BufReader::new(fs::File::open(path)?);
+    Ok(rustls_pemfile::certs(&mut reader)?
+        .into_iter()
+        .map(Certificate)
+        .collect())
+}
+
+/// Loads the certificate chain and the private key from PEM files, which can be the same file.
+fn load_identity(cert_path: &str, key_path: &str) -> Result<(Vec<Certificate>, PrivateKey)> {
+    fn read_pem(path: &str) -> Result<Vec<Item>> {
+        let file = fs::File::open(path).map_err(|e| identity_error(path, e))?;
+        rustls_pemfile::read_all(&mut BufReader::new(file)).map_err(|e| identity_error(path, e))
+    }
+
+    let chain = read_pem(cert_path)?
+        .into_iter()
+        .filter_map(|item| match item {
+            Item::X509Certificate(cert) => Some(Certificate(cert)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if chain.is_empty() {
+        return Err(identity_error(cert_path, "no certificate found"));
+    }
+    let key = read_pem(key_path)?
+        .into_iter()
+        .find_map(|item| match item {
+            Item::RSAKey(der) | Item::PKCS8Key(der) | Item::ECKey(der) => Some(PrivateKey(der)),
+            _ => None,
+        })
+        .ok_or_else(|| identity_error(key_path, "no private key found"))?;
+    Ok((chain, key))
+}
+
+/// Loads the first private key and its certificate chain from a PKCS#12 bundle.
+fn load_pkcs12(path: &str, password: &str) -> Result<(Vec<Certificate>, PrivateKey)> {
+    let data = fs::read(path).map_err(|e| identity_error(path, e))?;
+    let keystore = KeyStore::from_pkcs12(&data, password).map_err(|e| identity_error(path, e))?;
+    let (_, key_chain) = keystore
+        .private_key_chain()
+        .ok_or_else(|| identity_error(path, "no private key found"))?;
+    let chain = key_chain
+        .chain()
+        .iter()
+        .map(|cert| Certificate(cert.as_der().to_vec()))
+        .collect::<Vec<_>>();
+    if chain.is_empty() {
+        return Err(identity_error(path, "no certificate found"));
+    }
+    Ok((chain, PrivateKey(key_chain.key().to_vec())))
+}
+
+fn identity_error<E: ToString>(path: &str, reason: E) -> InquestError {
+    InquestError::ClientIdentityError {
+        path: path.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+impl CertificateDetails {
+    /// Number of full days the certificate is still valid. Negative when already expired.
+    pub(super) fn days_valid(&self, now: DateTime<Utc>) -> i64 {
+        (self.not_after - now).num_days()
+    }
+}
+
+impl TryFrom<&Certificate> for CertificateDetails {
+    type Error = InquestError;
+
+    fn try_from(certificate: &Certificate) -> Result<Self> {
+        fn timestamp(seconds: i64) -> DateTime<Utc> {
+            Utc.timestamp_opt(seconds, 0).single().unwrap_or_default()
+        }
+
+        let (_, cert) = parse_x509_certificate(&certificate.0).map_err(|e| {
+            InquestError::IOError(io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+        })?;
+
+        let subject_alternative_names = match cert.subject_alternative_name() {
+            Ok(Some(extension)) => extension
+                .value
+                .general_names
+                .iter()
+                .map(|name| match name {
+                    GeneralName::DNSName(dns) => format!("DNS:{}", dns),
+                    GeneralName::IPAddress(ip) => match ip.len() {
+                        4 => format!("IP:{}", IpAddr::from(<[u8; 4]>::try_from(*ip).unwrap())),
+                        16 => format!("IP:{}", IpAddr::from(<[u8; 16]>::try_from(*ip).unwrap())),
+                        _ => format!("IP:{:02x?}", ip),
+                    },
+                    other => format!("{}", other),
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        let public_key = cert.public_key();
+        let algorithm = oid2sn(&public_key.algorithm.algorithm, oid_registry())
+            .map(|name| name.to_string())
+            .unwrap_or_else(|_| public_key.algorithm.algorithm.to_id_string());
+        let key_type = match public_key.parsed() {
+            Ok(key @ PublicKey::RSA(_)) => format!("RSA {} bit", key.key_size()),
+            Ok(key @ PublicKey::EC(_)) => format!("EC {} bit", key.key_size()),
+            _ => algorithm,
+        };
+        let pin_sha256 = STANDARD.encode(Sha256::digest(public_key.raw));
+
+        Ok(CertificateDetails {
+            subject: cert.subject().to_string(),
+            subject_alternative_names,
+            issuer: cert.issuer().to_string(),
+            serial: cert.raw_serial_as_string(),
+            not_before: timestamp(cert.validity().not_before.timestamp()),
+            not_after: timestamp(cert.validity().not_after.timestamp()),
+            key_type,
+            pin_sha256,
+        })
+    }
+}
+
+impl Display for CertificateDetails {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Subject: {}", self.subject)?;
+        writeln!(f, "SANs: {}", self.subject_alternative_names.join(", "))?;
+        writeln!(f, "Issuer: {}", self.issuer)?;
+        writeln!(f, "Serial: {}", self.serial)?;
+        writeln!(f, "Valid from: {}", self.not_before)?;
+        writeln!(
+            f,
+            "Valid until: {} ({} days left)",
+            self.not_after,
+            self.days_valid(Utc::now())
+        )?;
+        writeln!(f, "Key: {}", self.key_type)?;
+        write!(f, "Pin: sha256//{}", self.pin_sha256)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use rustls::Certificate;
+
+    use crate::error::InquestError;
+    use crate::probes::tls::{check_chain, load_client_identity, CertificateDetails};
+    use crate::{Certificates, TlsTest};
+
+    /// self-signed EC certificate, valid for 100 years
+    pub(crate) const TEST_CERTIFICATE: &str = "-----BEGIN CERTIFICATE-----
+MIIBjzCCATWgAwIBAgICEmcwCgYIKoZIzj0EAwIwFzEVMBMGA1UEAwwMaW5xdWVz
+dC10ZXN0MCAXDTI2MTAxODE3MTQ1N1oYDzIxMjYwOTI0MTcxNDU3WjAXMRUwEwYD
+VQQDDAxpbnF1ZXN0LXRlc3QwWTATBgcqhkjOPQIBBggqhkjOPQMBBwNCAARTQw4n
+wfrdGQkxBY9hcQB1qufbLvhBCuVitENr+1MuanMMDSTVMlBVCNfXW+M8Xa9oU20T
+kSnb2y2A1LzE3fdZo28wbTAdBgNVHQ4EFgQUhHwEIfN/Wc70aKdeiFe8hmYx4Jww
+HwYDVR0jBBgwFoAUhHwEIfN/Wc70aKdeiFe8hmYx4JwwDwYDVR0TAQH/BAUwAwEB
+/zAaBgNVHREEEzARgglsb2NhbGhvc3SHBH8AAAEwCgYIKoZIzj0EAwIDSAAwRQIg
+e/6S5qFpG6Pz9d1ISb73FFPbqhTcFl3AtJ3iZfH/fmsCIQDsRiCIVC3/UkNVHR5s
+uXE9DLMzR9B2IWkE6/Y+3bT4uw==
+-----END CERTIFICATE-----
+";
+
+    pub(crate) fn test_certificate() -> Certificate {
+        let der = rustls_pemfile::certs(&mut TEST_CERTIFICATE.as_bytes()).unwrap();
+        Certificate(der[0].clone())
+    }
+
+    #[test]
+    fn certificate_details_extracted() {
+        let details = CertificateDetails::try_from(&test_certificate()).unwrap();
+
+        assert_eq!("CN=inquest-test", details.subject);
+        assert_eq!("CN=inquest-test", details.issuer);
+        assert_eq!("12:67", details.serial);
+        assert_eq!(
+            vec!["DNS:localhost", "IP:127.0.0.1"],
+            details.subject_alternative_names
+        );
+        assert_eq!("EC 256 bit", details.key_type);
+        assert_eq!(
+            "ZJaE2Dk5vSL3Pu+co2Jk8t4wTQHBlzyz0E6i7BBB2EQ=",
+            details.pin_sha256
+        );
+        assert_eq!(
+            Utc.with_ymd_and_hms(2126, 9, 24, 17, 14, 57).unwrap(),
+            details.not_after
+        );
+        assert_eq!(
+            30,
+            details.days_valid(Utc.with_ymd_and_hms(2126, 8, 25, 12, 0, 0).unwrap())
+        );
+    }
+
+    #[test]
+    fn chain_checked_against_pins_and_issuer() {
+        let chain = vec![test_certificate()];
+        let expect = |pins: &[&str], issuer: Option<&str>| TlsTest {
+            pin_sha256: pins.iter().map(|pin| pin.to_string()).collect(),
+            expected_issuer: issuer.map(|issuer| issuer.to_string()),
+        };
+
+        assert_eq!(Ok(()), check_chain(&chain, &expect(&[], None)));
+        assert_eq!(
+            Ok(()),
+            check_chain(
+                &chain,
+                &expect(
+                    &[
+                        "sha256//AAAA2Dk5vSL3Pu+co2Jk8t4wTQHBlzyz0E6i7BBB2EQ=",
+                        "sha256//ZJaE2Dk5vSL3Pu+co2Jk8t4wTQHBlzyz0E6i7BBB2EQ="
+                    ],
+                    Some("CN=inquest-test")
+                )
+            )
+        );
+        assert!(check_chain(
+            &chain,
+            &expect(&["AAAA2Dk5vSL3Pu+co2Jk8t4wTQHBlzyz0E6i7BBB2EQ="], None)
+        )
+        .is_err());
+        assert_eq!(
+            Err("Expected issuer 'CN=Acme' but was 'CN=inquest-test'".to_string()),
+            check_chain(&chain, &expect(&[], Some("CN=Acme")))
+        );
+    }
+
+    #[test]
+    fn client_identity_fails_with_path() {
+        let certs = Certificates::new(
+            Some("does-not-exist.crt".to_string()),
+            Some("does-not-exist.key".to_string()),
+            None,
+            None,
+            None,
+            None,
+        );
+
+        assert_matches!(
+            load_client_identity(&certs),
+            Err(InquestError::ClientIdentityError { path, .. }) if path == "does-not-exist.crt"
+        );
+    }
+}
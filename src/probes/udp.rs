@@ -0,0 +1,204 @@
+use std::io::ErrorKind;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+use crate::error::InquestError::{AssertionMatchingError, FailedExecutionError};
+use crate::probes::body::{preview, DEFAULT_PREVIEW_SIZE};
+use crate::{Data, GlobalOptions, Probe, ProbeReport, Result, Udp};
+
+const PROBE_NAME: &str = "UDP";
+
+/// Without an expected response, the probe only waits this long for an ICMP port-unreachable
+const ICMP_WAIT: Duration = Duration::from_millis(500);
+
+impl Udp {
+    pub(crate) fn new(host: String, port: u16, options: &'static GlobalOptions) -> Udp {
+        Udp {
+            host,
+            port,
+            timeout: options.timeout,
+            payload: Vec::new(),
+            response: None,
+        }
+    }
+}
+
+/// Sends the payload to every address the host resolves to. Since UDP is connection-less,
+/// silence is only a failure when a response is expected, while an ICMP port-unreachable
+/// always is.
+impl Probe for Udp {
+    fn execute<'a>(&self) -> Result<ProbeReport> {
+        let addresses = (self.host.as_str(), self.port)
+            .to_socket_addrs()
+            .map_err(|e| FailedExecutionError {
+                probe_identifier: self.identifier(),
+                source: Box::new(e),
+            })?;
+
+        let mut report = ProbeReport::new(self.identifier());
+        let failures = addresses
+            .filter_map(|address| check_address(self, address, &mut report.data).err())
+            .collect::<Vec<_>>();
+
+        if failures.is_empty() {
+            Ok(report)
+        } else {
+            Err(AssertionMatchingError(failures.join("; "), report))
+        }
+    }
+
+    fn identifier(&self) -> String {
+        format!("{} - {}:{}", PROBE_NAME, self.host, self.port)
+    }
+}
+
+fn check_address(
+    probe: &Udp,
+    address: SocketAddr,
+    data: &mut Data,
+) -> std::result::Result<(), String> {
+    let local: SocketAddr = match address {
+        SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+        SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+    };
+    // a connected socket receives the ICMP errors for the destination
+    let socket = UdpSocket::bind(local)
+        .and_then(|socket| socket.connect(address).map(|_| socket))
+        .map_err(|e| format!("Unable to open a socket for {}: {}", address, e))?;
+    let started = Instant::now();
+    socket
+        .send(&probe.payload)
+        .map_err(|e| format!("Unable to send to {}: {}", address, e))?;
+    data.push((
+        format!("Sent {}", address),
+        format!(
+            "{} bytes from {}",
+            probe.payload.len(),
+            socket
+                .local_addr()
+                .map_or("unknown".to_string(), |local| local.to_string())
+        ),
+    ));
+
+    let wait = match probe.response {
+        Some(_) => probe.timeout,
+        None => ICMP_WAIT.min(probe.timeout),
+    };
+    let _ = socket.set_read_timeout(Some(wait));
+    let mut buffer = vec![0; 65535];
+    let received = match socket.recv(&mut buffer) {
+        Ok(received) => received,
+        Err(e) if e.kind() == ErrorKind::ConnectionRefused => {
+            return Err(format!(
+                "Port {} is unreachable, ICMP port-unreachable received after {} ms",
+                address,
+                started.elapsed().as_millis()
+            ))
+        }
+        Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+            return match probe.response {
+                Some(_) => Err(format!(
+                    "No response from {} within {} ms",
+                    address,
+                    wait.as_millis()
+                )),
+                None => {
+                    data.push((
+                        format!("Response {}", address),
+                        format!(
+                            "none within {} ms, delivery is not confirmed",
+                            wait.as_millis()
+                        ),
+                    ));
+                    Ok(())
+                }
+            };
+        }
+        Err(e) => return Err(format!("Unable to receive from {}: {}", address, e)),
+    };
+    let response = &buffer[..received];
+    data.push((
+        format!("Response {}", address),
+        format!(
+            "{} bytes in {:.1} ms\n{}",
+            received,
+            started.elapsed().as_secs_f64() * 1000.0,
+            preview(None, response, DEFAULT_PREVIEW_SIZE)
+                .content
+                .trim_end()
+        ),
+    ));
+
+    match &probe.response {
+        Some(expected) if !expected.is_match(&String::from_utf8_lossy(response)) => Err(format!(
+            "Response of {} does not match '{}'",
+            address, expected
+        )),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::UdpSocket;
+    use std::thread;
+    use std::time::Duration;
+
+    use regex::Regex;
+
+    use crate::error::InquestError::AssertionMatchingError;
+    use crate::{Probe, Udp, GO};
+
+    #[test]
+    fn response_is_reported_and_matched() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let port = server.local_addr().unwrap().port();
+        thread::spawn(move || {
+            let mut buffer = [0; 512];
+            while let Ok((_, client)) = server.recv_from(&mut buffer) {
+                let _ = server.send_to(b"PONG", client);
+            }
+        });
+        let probe = |response: &str| Udp {
+            payload: b"PING".to_vec(),
+            response: Some(Regex::new(response).unwrap()),
+            ..Udp::new("127.0.0.1".to_string(), port, &GO)
+        };
+
+        let report = probe("^PONG$").execute().unwrap();
+        assert!(report.data[0].1.starts_with("4 bytes from 127.0.0.1:"));
+        assert!(report.data[1].1.ends_with(" ms\nPONG"));
+
+        assert_matches!(
+            probe("^OK").execute(),
+            Err(AssertionMatchingError(desc, _)) if desc.ends_with("does not match '^OK'")
+        );
+    }
+
+    #[test]
+    fn unreachable_port_differs_from_silence() {
+        // the port is free again after the socket has been dropped
+        let closed = UdpSocket::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let probe = |port: u16, response: Option<&str>| Udp {
+            timeout: Duration::from_millis(200),
+            response: response.map(|response| Regex::new(response).unwrap()),
+            ..Udp::new("127.0.0.1".to_string(), port, &GO)
+        };
+        let silent_port = silent.local_addr().unwrap().port();
+
+        assert_matches!(
+            probe(closed, None).execute(),
+            Err(AssertionMatchingError(desc, _)) if desc.contains("ICMP port-unreachable")
+        );
+        assert_matches!(
+            probe(silent_port, Some(".*")).execute(),
+            Err(AssertionMatchingError(desc, _)) if desc.starts_with("No response from 127.0.0.1")
+        );
+        assert!(probe(silent_port, None).execute().is_ok());
+    }
+}
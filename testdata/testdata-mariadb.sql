@@ -0,0 +1,59 @@
+CREATE TABLE testdata (
+    some_tinyint tinyint,
+    some_smallint smallint,
+    some_mediumint mediumint,
+    some_int int,
+    some_bigint bigint unsigned,
+    some_decimal decimal(10, 2),
+    some_float float,
+    some_double double,
+    some_bit bit(3),
+    some_boolean boolean,
+    some_char char(1),
+    some_varchar varchar(10),
+    some_text text,
+    some_binary binary(2),
+    some_varbinary varbinary(10),
+    some_blob blob,
+    some_enum enum('small', 'large'),
+    some_set set('a', 'b', 'c'),
+    some_date date,
+    some_time time(3),
+    some_datetime datetime(6),
+    some_timestamp timestamp,
+    some_year year,
+    some_json json,
+    some_uuid uuid,
+    some_inet6 inet6,
+    some_point point
+);
+
+INSERT INTO testdata VALUES (
+    1,
+    10,
+    100,
+    1000,
+    18446744073709551615,
+    42.50,
+    0.5,
+    2 / 3,
+    b'101',
+    true,
+    'c',
+    'varchar-10',
+    'text',
+    0xCAFE,
+    0xCAFE,
+    0xCAFEBABE,
+    'large',
+    'a,c',
+    '2024-02-29',
+    '-26:30:00.250',
+    '2024-02-29 13:45:00.000250',
+    current_timestamp,
+    2024,
+    '{"a": 1}',
+    '123e4567-e89b-12d3-a456-426614174000',
+    '::ffff:192.168.0.1',
+    POINT(1, 2)
+);
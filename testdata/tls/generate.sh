@@ -0,0 +1,24 @@
+#!/bin/bash
+# Generates the CA and the 'localhost' certificate used by the unit tests, both valid for 100 years.
+# The key of the CA is not kept.
+
+set -e
+set -o pipefail
+
+cd "$(dirname "$0")"
+
+openssl ecparam -name prime256v1 -genkey -noout | openssl pkcs8 -topk8 -nocrypt -out ca.key
+openssl req -x509 -new -key ca.key \
+  -subj "/CN=inquest-test-ca" \
+  -days 36500 \
+  -addext "basicConstraints=critical,CA:TRUE" \
+  -addext "keyUsage=critical,keyCertSign" \
+  -out ca.crt
+
+openssl ecparam -name prime256v1 -genkey -noout | openssl pkcs8 -topk8 -nocrypt -out localhost.key
+openssl req -new -key localhost.key -subj "/CN=localhost" |
+  openssl x509 -req -CA ca.crt -CAkey ca.key -set_serial 4711 -days 36500 \
+    -extfile <(printf "subjectAltName=DNS:localhost,IP:127.0.0.1\nbasicConstraints=CA:FALSE\nextendedKeyUsage=serverAuth\n") \
+    -out localhost.crt
+
+rm ca.key
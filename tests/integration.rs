@@ -76,3 +76,15 @@ fn run_mssql_probe() {
         assert_eq!(report.probe_identifier.as_str(), "MSSql - localhost:1433/SA");
     });
 }
+
+#[test]
+fn run_mysql_probe() {
+    setup();
+    let result = run_from_config(Path::new("tests/integration-mysql.conf"));
+    assert!(result.is_ok());
+    let reports = result.unwrap();
+    assert_matches!(reports.1.as_slice(), []);
+    assert_matches!(reports.0.as_slice(), [report] => {
+        assert_eq!(report.probe_identifier.as_str(), "MySQL - localhost:3306/test/admin");
+    });
+}